@@ -0,0 +1,52 @@
+//! Draws into a `Canvas` without any terminal or async runtime and prints the result.
+//!
+//! Run with `cargo run --example standalone --no-default-features`.
+
+use renterm::{
+    canvas::Canvas, cell::Cell, color::Color, rect::Rect, style::Style, surface::Surface,
+    text::DrawableStr, vector::Vector2,
+};
+
+/// Outlines `rect` with box drawing characters.
+fn draw_border(surface: &mut impl Surface, rect: Rect, style: &Style) {
+    let start = rect.position();
+    let end = rect.bottom_right() - Vector2::new(1, 1);
+    for x in start.x + 1..end.x {
+        surface.set_cell(Vector2::new(x, start.y), Cell::new_styled("─", style.clone()));
+        surface.set_cell(Vector2::new(x, end.y), Cell::new_styled("─", style.clone()));
+    }
+    for y in start.y + 1..end.y {
+        surface.set_cell(Vector2::new(start.x, y), Cell::new_styled("│", style.clone()));
+        surface.set_cell(Vector2::new(end.x, y), Cell::new_styled("│", style.clone()));
+    }
+    let corners = [
+        (Vector2::new(start.x, start.y), "┌"),
+        (Vector2::new(end.x, start.y), "┐"),
+        (Vector2::new(start.x, end.y), "└"),
+        (Vector2::new(end.x, end.y), "┘"),
+    ];
+    for (position, corner) in corners {
+        surface.set_cell(position, Cell::new_styled(corner, style.clone()));
+    }
+}
+
+fn main() {
+    let mut canvas: Canvas = Canvas::new(Vector2::new(24, 5));
+    let border_style = Style::default().with_foreground_color(Color::new_one_byte(8 + 6));
+    let bounds = Rect::new(Vector2::new(0, 0), canvas.size());
+    draw_border(&mut canvas, bounds, &border_style);
+
+    let title = DrawableStr::new(
+        "renterm",
+        Style::default()
+            .with_foreground_color(Color::new_rgb(255, 200, 0))
+            .with_background_color(Color::new_one_byte(4)),
+    );
+    canvas.draw_in(&title, Rect::new(Vector2::new(2, 1), Vector2::new(20, 1)));
+
+    let mut view = canvas.to_sub_view(Rect::new(Vector2::new(2, 3), Vector2::new(20, 1)));
+    let text = DrawableStr::truncated("drawn without a terminal", Style::default(), true);
+    view.draw(&text);
+
+    print!("{}", canvas.to_ansi_string());
+}
@@ -8,14 +8,25 @@ use crate::DefaultScalar;
 
 use super::cell::Cell;
 use super::rect::Rect;
+use super::style::Style;
 use super::view::SurfaceView;
 use super::surface::Surface;
 use super::vector::Vector2;
 
+/// Largest width and height a canvas grows to unless it was given another
+/// limit with `Canvas::with_max_size`.
+pub const DEFAULT_MAX_SIZE: usize = 4096;
+
+/// Most cells a canvas holds unless it was given another limit with
+/// `Canvas::set_max_cells`, about 64 MiB of cells.
+pub const DEFAULT_MAX_CELLS: usize = 1 << 20;
+
 #[derive(Clone, PartialEq, Eq, Default)]
 pub struct Canvas<S: Scalar = DefaultScalar> {
     cells: Vec<Cell>,
     size: Vector2<S>,
+    max_size: Option<Vector2<S>>,
+    max_cells: Option<usize>,
 }
 
 impl <'a, S: Scalar> Into<Box<&'a dyn Surface<S>>> for &'a Canvas<S> {
@@ -60,13 +71,14 @@ impl <S: Scalar> Surface<S> for Canvas<S> {
         self.size.clone()
     }
     fn set_size(&mut self, size: Vector2<S>) {
+        let size = self.clamp_size(size);
         if self.size == size {
             return;
         }
         let old_cells = self.cells.clone();
         let old_size = self.size.clone();
         self.size = size;
-        self.cells = vec![Cell::default(); S::abs(self.size.x * self.size.y).as_()];
+        self.cells = vec![Cell::default(); cell_count(&self.size)];
         for y in 0..S::abs(self.size.y.min(old_size.y)).as_() {
             for x in 0..S::abs(self.size.x.clone().min(old_size.x)).as_() {
                 let index = y * old_size.x.as_() + x;
@@ -79,38 +91,15 @@ impl <S: Scalar> Surface<S> for Canvas<S> {
         }
     }
     fn get_cell(&self, position: Vector2<S>) -> Cell {
-        let x = position.x;
-        let y = position.y;
-
-        if x < S::zero() || y < S::zero() {
-            return Cell::default();
-        }
-        if position.x >= self.size.x || position.y >= self.size.y {
-            return Cell::default();
-        }
-        let index = y * self.size.x + x;
-        if self.cells.len() <= index.as_() {
-            return Cell::default();
+        match self.cell_index(&position) {
+            Some(index) => self.cells[index].clone(),
+            None => Cell::default(),
         }
-
-        self.cells[index.as_()].clone()
     }
     fn set_cell(&mut self, position: Vector2<S>, cell: Cell) {
-        let x = position.x;
-        let y = position.y;
-
-        if x < S::zero() || y < S::zero() {
-            return;
-        }
-        if position.x >= self.size.x || position.y >= self.size.y {
-            return;
-        }
-        let index = y * self.size.x + x;
-        if self.cells.len() <= index.as_() {
-            return;
+        if let Some(index) = self.cell_index(&position) {
+            self.cells[index] = cell;
         }
-
-        self.cells[index.as_()] = cell;
     }
     fn to_sub_view(&mut self, rect: Rect<S>) -> SurfaceView<S> {
         let corner = rect.bottom_right();
@@ -124,12 +113,110 @@ impl <S: Scalar> Surface<S> for Canvas<S> {
 
 
 
+/// `None` for scalars too small to hold `DEFAULT_MAX_SIZE`, which can't
+/// exceed it either.
+fn default_max_size<S: Scalar>() -> Option<Vector2<S>> {
+    S::from_usize(DEFAULT_MAX_SIZE).map(|max| Vector2::new(max, max))
+}
+
+/// Clamps `size` to `max_size`, then drops the rows that would take it
+/// over `max_cells`.
+fn clamp_to<S: Scalar>(size: Vector2<S>, max_size: Option<Vector2<S>>, max_cells: usize) -> Vector2<S> {
+    let size: Vector2<S> = Vector2::new(size.x.max(S::zero()), size.y.max(S::zero()));
+    let size = match max_size {
+        Some(max_size) => Vector2::new(size.x.min(max_size.x), size.y.min(max_size.y)),
+        None => size,
+    };
+    let width: usize = size.x.as_();
+    if width == 0 || width.saturating_mul(size.y.as_()) <= max_cells {
+        return size;
+    }
+    let rows = S::from_usize(max_cells / width).unwrap_or(size.y);
+
+    Vector2::new(size.x, rows.min(size.y))
+}
+
+fn cell_count<S: Scalar>(size: &Vector2<S>) -> usize {
+    size.x.as_().checked_mul(size.y.as_()).unwrap_or(0)
+}
+
 impl <S: Scalar> Canvas<S> {
     pub fn new(size: Vector2<S>) -> Self {
         Self::new_filled(size, Cell::default())
     }
     pub fn new_filled(size: Vector2<S>, cell: Cell) -> Self {
-        let cells = vec![cell; S::abs(size.x * size.y).as_()];
-        Canvas { cells, size }
+        let size = clamp_to(size, default_max_size(), DEFAULT_MAX_CELLS);
+        let cells = vec![cell; cell_count(&size)];
+        Canvas { cells, size, max_size: None, max_cells: None }
+    }
+    /// Limits how large the canvas grows, `set_size` clamps larger sizes to
+    /// `max_size` instead of allocating for them.
+    pub fn with_max_size(mut self, max_size: Vector2<S>) -> Self {
+        self.max_size = Some(max_size);
+        let size = self.size.clone();
+        self.set_size(size);
+        self
+    }
+    /// Resets every cell to the default, keeping the canvas' size and its
+    /// allocation.
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::default());
+    }
+    pub fn max_size(&self) -> Option<Vector2<S>> {
+        self.max_size.clone().or_else(default_max_size)
+    }
+    /// Limits how many cells the canvas holds, `set_size` leaves out the
+    /// rows past it. Shrinks the canvas when it is already larger.
+    pub fn set_max_cells(&mut self, max_cells: usize) {
+        if self.max_cells() == max_cells {
+            return;
+        }
+        self.max_cells = Some(max_cells);
+        let size = self.size.clone();
+        self.set_size(size);
+    }
+    pub fn max_cells(&self) -> usize {
+        self.max_cells.unwrap_or(DEFAULT_MAX_CELLS)
+    }
+    fn clamp_size(&self, size: Vector2<S>) -> Vector2<S> {
+        clamp_to(size, self.max_size(), self.max_cells())
+    }
+    /// Where the cell at `position` is in `cells`, `None` outside of the
+    /// canvas. Computed in `usize` so that it can't overflow a small `S`.
+    fn cell_index(&self, position: &Vector2<S>) -> Option<usize> {
+        if position.x < S::zero() || position.y < S::zero() {
+            return None;
+        }
+        if position.x >= self.size.x || position.y >= self.size.y {
+            return None;
+        }
+        let index = position.y.as_()
+            .checked_mul(self.size.x.as_())?
+            .checked_add(position.x.as_())?;
+
+        (index < self.cells.len()).then_some(index)
+    }
+    /// Renders the canvas row by row with SGR escape codes, resetting the
+    /// style at the end of every row.
+    pub fn to_ansi_string(&self) -> String {
+        let mut output = String::new();
+        for y in 0..self.size.y.as_() {
+            let mut last_style = None;
+            for x in 0..self.size.x.as_() {
+                let cell = self.get_cell(Vector2::new(S::from_usize(x).unwrap(), S::from_usize(y).unwrap()));
+                if last_style.as_ref() != Some(&cell.style) {
+                    if last_style.as_ref().is_some_and(Style::has_attributes) {
+                        output += "\x1b[0m";
+                    }
+                    let style: Vec<u8> = cell.style.clone().into();
+                    output += &String::from_utf8_lossy(&style);
+                    last_style = Some(cell.style.clone());
+                }
+                output += &cell.to_string();
+            }
+            output += "\x1b[0m\n";
+        }
+
+        output
     }
 }
@@ -1,3 +1,5 @@
+use unicode_width::UnicodeWidthStr;
+
 use super::style::Style;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -30,6 +32,8 @@ impl<T: Into<String>> From<T> for CellValue {
 pub struct Cell {
     pub value: CellValue,
     pub style: Style,
+    /// Target of an OSC 8 hyperlink covering this cell.
+    pub hyperlink: Option<String>,
 }
 
 impl Cell {
@@ -37,25 +41,48 @@ impl Cell {
         Cell {
             value: value.into(),
             style: Style::default(),
+            hyperlink: None,
         }
     }
     pub fn new_styled(value: impl Into<CellValue>, style: Style) -> Self {
         Cell {
             value: value.into(),
             style,
+            hyperlink: None,
         }
     }
     pub fn empty_styled(style: Style) -> Self {
         Cell {
             value: " ".into(),
             style,
+            hyperlink: None,
+        }
+    }
+    /// The cell covered by the right half of a wide character in the cell
+    /// before it. Nothing is written for it, the wide character already
+    /// moved the cursor past it.
+    pub fn wide_continuation(style: Style) -> Self {
+        Cell {
+            value: "".into(),
+            style,
+            hyperlink: None,
         }
     }
+    pub fn with_hyperlink(self, hyperlink: Option<String>) -> Self {
+        Cell { hyperlink, ..self }
+    }
     pub fn is_empty(&self) -> bool {
         match &self.value.value {
             CellValueEnum::String(value) => value == " ",
         }
     }
+    /// How many columns the cell's value takes up in a terminal: 2 for wide
+    /// characters like CJK, 0 for a `wide_continuation`.
+    pub fn width(&self) -> usize {
+        match &self.value.value {
+            CellValueEnum::String(value) => value.width(),
+        }
+    }
     pub fn to_string(&self) -> String {
         match &self.value.value {
             CellValueEnum::String(value) => value.clone(),
@@ -68,6 +95,7 @@ impl Default for Cell {
         Cell {
             value: " ".into(),
             style: Style::default(),
+            hyperlink: None,
         }
     }
 }
@@ -25,6 +25,32 @@ impl Color {
             color: ColorEnum::Rgb(r, g, b),
         }
     }
+    /// The RGB value of the color, `None` for the default color, whose value
+    /// is up to the terminal.
+    pub fn to_rgb(&self) -> Option<(u8, u8, u8)> {
+        match self.color {
+            ColorEnum::Default => None,
+            ColorEnum::OneByte(index) => Some(index_to_rgb(index)),
+            ColorEnum::Rgb(r, g, b) => Some((r, g, b)),
+        }
+    }
+    /// Moves the color `amount` of the way towards `other`, from 0 to 1.
+    /// The default color has no known value to mix, so it becomes `other`.
+    pub fn mix(&self, other: &Color, amount: f64) -> Color {
+        let (Some(from), Some(to)) = (self.to_rgb(), other.to_rgb()) else {
+            return other.clone();
+        };
+        let amount = amount.clamp(0.0, 1.0);
+        let channel = |from: u8, to: u8| {
+            (from as f64 + (to as f64 - from as f64) * amount).round() as u8
+        };
+
+        Color::new_rgb(
+            channel(from.0, to.0),
+            channel(from.1, to.1),
+            channel(from.2, to.2),
+        )
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -40,6 +66,108 @@ pub enum ColorType {
     Background,
 }
 
+/// How many colors the outer terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// The xterm defaults for the 16 basic colors.
+const ANSI_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The color an xterm shows for an index of the 256 color palette.
+fn index_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_PALETTE[index as usize],
+        16..=231 => {
+            let index = index - 16;
+            (
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[(index / 6 % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..=7 => 16,
+            249..=255 => 231,
+            level => 232 + ((level as u16 - 8) * 24 / 247) as u8,
+        };
+    }
+    let level = |value: u8| -> u8 {
+        match value {
+            0..=47 => 0,
+            48..=114 => 1,
+            value => (value - 35) / 40,
+        }
+    };
+
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+fn rgb_to_16(r: u8, g: u8, b: u8) -> u8 {
+    let distance = |(pr, pg, pb): (u8, u8, u8)| -> i32 {
+        let dr = r as i32 - pr as i32;
+        let dg = g as i32 - pg as i32;
+        let db = b as i32 - pb as i32;
+        dr * dr + dg * dg + db * db
+    };
+
+    (0..16u8)
+        .min_by_key(|&index| distance(ANSI_PALETTE[index as usize]))
+        .unwrap_or(0)
+}
+
+impl Color {
+    /// Approximates the color with one the given depth can show.
+    pub fn downsample(&self, depth: ColorDepth) -> Color {
+        match (&self.color, depth) {
+            (_, ColorDepth::TrueColor) | (ColorEnum::Default, _) => self.clone(),
+            (ColorEnum::Rgb(r, g, b), ColorDepth::Ansi256) => {
+                Color::new_one_byte(rgb_to_256(*r, *g, *b))
+            }
+            (ColorEnum::OneByte(_), ColorDepth::Ansi256) => self.clone(),
+            (ColorEnum::Rgb(r, g, b), ColorDepth::Ansi16) => {
+                Color::new_one_byte(rgb_to_16(*r, *g, *b))
+            }
+            (ColorEnum::OneByte(index), ColorDepth::Ansi16) if *index < 16 => self.clone(),
+            (ColorEnum::OneByte(index), ColorDepth::Ansi16) => {
+                let (r, g, b) = index_to_rgb(*index);
+                Color::new_one_byte(rgb_to_16(r, g, b))
+            }
+        }
+    }
+}
+
 impl Default for Color {
     fn default() -> Self {
         Color {
@@ -38,6 +38,21 @@ impl <S: Scalar> Rect<S> {
     }
 }
 
+/// A rect from its position and size.
+impl <S: Scalar> From<(Vector2<S>, Vector2<S>)> for Rect<S> {
+    fn from((position, size): (Vector2<S>, Vector2<S>)) -> Self {
+        Rect::new(position, size)
+    }
+}
+
+/// A rect from `(x, y, width, height)`.
+impl <S: Scalar, A: Into<S>, B: Into<S>, C: Into<S>, D: Into<S>>
+From<(A, B, C, D)> for Rect<S> {
+    fn from((x, y, width, height): (A, B, C, D)) -> Self {
+        Rect::new(Vector2::new(x, y), Vector2::new(width, height))
+    }
+}
+
 impl <S: Scalar> Div<S> for Rect<S> {
     type Output = Rect<S>;
 
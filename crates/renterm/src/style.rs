@@ -1,4 +1,4 @@
-use super::color::{Color, ColorType};
+use super::color::{Color, ColorDepth, ColorType};
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct Style {
@@ -6,6 +6,12 @@ pub struct Style {
     background_color: Color,
     is_bold: bool,
     is_italic: bool,
+    is_underline: bool,
+    is_strikethrough: bool,
+    is_dim: bool,
+    /// Emitted as SGR 7 instead of swapping the colors, so that the host
+    /// terminal resolves reversed default colors itself.
+    is_inverse: bool,
 }
 
 impl Style {
@@ -15,6 +21,65 @@ impl Style {
     pub fn foreground_color(&self) -> Color {
         self.foreground_color.clone()
     }
+    pub fn is_bold(&self) -> bool {
+        self.is_bold
+    }
+    pub fn is_italic(&self) -> bool {
+        self.is_italic
+    }
+    pub fn is_underline(&self) -> bool {
+        self.is_underline
+    }
+    pub fn is_strikethrough(&self) -> bool {
+        self.is_strikethrough
+    }
+    pub fn is_dim(&self) -> bool {
+        self.is_dim
+    }
+    pub fn is_inverse(&self) -> bool {
+        self.is_inverse
+    }
+    /// Whether any attribute besides the colors is set. Attributes are only
+    /// ever turned on when a style is emitted, so leaving such a style takes a
+    /// reset.
+    pub fn has_attributes(&self) -> bool {
+        self.is_bold
+            || self.is_italic
+            || self.is_underline
+            || self.is_strikethrough
+            || self.is_dim
+            || self.is_inverse
+    }
+    pub fn with_bold(&self, is_bold: bool) -> Self {
+        let mut style = self.clone();
+        style.is_bold = is_bold;
+        style
+    }
+    pub fn with_italic(&self, is_italic: bool) -> Self {
+        let mut style = self.clone();
+        style.is_italic = is_italic;
+        style
+    }
+    pub fn with_underline(&self, is_underline: bool) -> Self {
+        let mut style = self.clone();
+        style.is_underline = is_underline;
+        style
+    }
+    pub fn with_strikethrough(&self, is_strikethrough: bool) -> Self {
+        let mut style = self.clone();
+        style.is_strikethrough = is_strikethrough;
+        style
+    }
+    pub fn with_dim(&self, is_dim: bool) -> Self {
+        let mut style = self.clone();
+        style.is_dim = is_dim;
+        style
+    }
+    pub fn with_inverse(&self, is_inverse: bool) -> Self {
+        let mut style = self.clone();
+        style.is_inverse = is_inverse;
+        style
+    }
     pub fn with_background_color(&self, color: impl Into<Color>) -> Self {
         let mut style = self.clone();
         style.background_color = color.into();
@@ -25,6 +90,13 @@ impl Style {
         style.foreground_color = color.into();
         style
     }
+    /// Approximates both colors with ones the given depth can show.
+    pub fn downsample(&self, depth: ColorDepth) -> Self {
+        let mut style = self.clone();
+        style.foreground_color = self.foreground_color.downsample(depth);
+        style.background_color = self.background_color.downsample(depth);
+        style
+    }
 }
 
 impl From<Style> for Vec<u8> {
@@ -34,6 +106,17 @@ impl From<Style> for Vec<u8> {
         let fg = val.foreground_color();
         bytes.extend(bg.to_vec(ColorType::Background));
         bytes.extend(fg.to_vec(ColorType::Foreground));
+        let attributes = [
+            (val.is_bold, 1),
+            (val.is_dim, 2),
+            (val.is_italic, 3),
+            (val.is_underline, 4),
+            (val.is_inverse, 7),
+            (val.is_strikethrough, 9),
+        ];
+        for (_, code) in attributes.iter().filter(|(is_set, _)| *is_set) {
+            bytes.extend(format!("\x1b[{}m", code).as_bytes());
+        }
 
         bytes
     }
@@ -1,4 +1,4 @@
-use crate::canvas::Canvas;
+use crate::canvas::{Canvas, DEFAULT_MAX_CELLS, DEFAULT_MAX_SIZE};
 use crate::surface::Surface;
 use crate::cell::Cell;
 use crate::rect::Rect;
@@ -6,7 +6,7 @@ use crate::vector::Vector2;
 
 #[test]
 fn canvas_view_read() {
-    let mut canvas = Canvas::new_filled(Vector2::new(100, 100), Cell::new('#'));
+    let mut canvas: Canvas = Canvas::new_filled(Vector2::new(100, 100), Cell::new('#'));
     let view = canvas.to_sub_view(Rect::new(Vector2::new(10, 10), Vector2::new(50, 50)));
     assert_eq!(view.get_cell(Vector2::new(10, 10)), Cell::new('#'));
     assert_eq!(view.get_cell(Vector2::new(0, 0)), Cell::new('#'));
@@ -15,11 +15,74 @@ fn canvas_view_read() {
     assert_eq!(view.get_cell(Vector2::new(51, 51)), Cell::new(' '));
 }
 
+#[test]
+fn clipped_view_keeps_its_size() {
+    let mut canvas: Canvas = Canvas::new(Vector2::new(20, 20));
+    let mut view = canvas.to_sub_view(Rect::new(Vector2::new(5, 5), Vector2::new(10, 10)));
+    view.set_clip(Some(Rect::new(Vector2::new(0, 0), Vector2::new(3, 3))));
+    view.set_size(Vector2::new(100, 100));
+    assert_eq!(view.size(), Vector2::new(100, 100));
+    view.set_cell(Vector2::new(2, 2), Cell::new('A'));
+    view.set_cell(Vector2::new(3, 3), Cell::new('B'));
+    view.set_cell(Vector2::new(50, 50), Cell::new('C'));
+    assert_eq!(view.get_cell(Vector2::new(2, 2)), Cell::new('A'));
+    assert_eq!(view.get_cell(Vector2::new(3, 3)), Cell::new(' '));
+    assert_eq!(canvas.get_cell(Vector2::new(7, 7)), Cell::new('A'));
+    assert_eq!(canvas.get_cell(Vector2::new(8, 8)), Cell::new(' '));
+    assert_eq!(canvas.size(), Vector2::new(20, 20));
+}
+
 #[test]
 fn canvas_view_write() {
-    let mut canvas = Canvas::new(Vector2::new(100, 100));
+    let mut canvas: Canvas = Canvas::new(Vector2::new(100, 100));
     let mut view = canvas.to_sub_view(Rect::new(Vector2::new(10, 10), Vector2::new(50, 50)));
     view.set_cell(Vector2::new(0, 0), Cell::new('A'));
     println!("{:?}", &canvas);
     assert_eq!(canvas.get_cell(Vector2::new(10, 10)), Cell::new('A'));
 }
+
+#[test]
+fn oversized_canvas_is_clamped_to_its_max_size() {
+    let mut canvas: Canvas = Canvas::new(Vector2::new(10, 10)).with_max_size(Vector2::new(100, 50));
+    canvas.set_size(Vector2::new(i32::MAX, i32::MAX));
+    assert_eq!(canvas.size(), Vector2::new(100, 50));
+    canvas.set_cell(Vector2::new(99, 49), Cell::new('#'));
+    assert_eq!(canvas.get_cell(Vector2::new(99, 49)), Cell::new('#'));
+    assert_eq!(canvas.get_cell(Vector2::new(100, 50)), Cell::new(' '));
+}
+
+#[test]
+fn canvas_indexing_does_not_overflow_small_scalars() {
+    let mut canvas = Canvas::<i16>::new(Vector2::new(300i16, 300i16));
+    canvas.set_cell(Vector2::new(299i16, 299i16), Cell::new('#'));
+    assert_eq!(canvas.get_cell(Vector2::new(299i16, 299i16)), Cell::new('#'));
+    canvas.set_size(Vector2::new(i16::MAX, -1i16));
+    assert_eq!(canvas.size(), Vector2::new(DEFAULT_MAX_SIZE as i16, 0i16));
+}
+
+#[test]
+fn clearing_a_canvas_keeps_its_size() {
+    let mut canvas: Canvas = Canvas::new_filled(Vector2::new(4, 3), Cell::new('#'));
+    canvas.clear();
+    assert_eq!(canvas, Canvas::new(Vector2::new(4, 3)));
+}
+
+#[test]
+fn huge_sizes_are_capped_at_the_default_cell_count() {
+    let mut canvas: Canvas = Canvas::new(Vector2::new(10, 10));
+    canvas.set_size(Vector2::new(i32::MAX, i32::MAX));
+    let size = canvas.size();
+    assert_eq!(size.x, DEFAULT_MAX_SIZE as i32);
+    assert!((size.x * size.y) as usize <= DEFAULT_MAX_CELLS);
+    assert!(DEFAULT_MAX_CELLS * std::mem::size_of::<Cell>() <= 128 << 20);
+}
+
+#[test]
+fn lowering_the_cell_limit_drops_rows() {
+    let mut canvas: Canvas = Canvas::new_filled(Vector2::new(10, 10), Cell::new('#'));
+    canvas.set_max_cells(35);
+    assert_eq!(canvas.size(), Vector2::new(10, 3));
+    assert_eq!(canvas.get_cell(Vector2::new(9, 2)), Cell::new('#'));
+    canvas.set_size(Vector2::new(5, 10));
+    assert_eq!(canvas.size(), Vector2::new(5, 7));
+}
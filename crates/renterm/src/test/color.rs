@@ -0,0 +1,42 @@
+use crate::color::{Color, ColorDepth};
+
+#[test]
+fn truecolor_is_left_alone() {
+    let color = Color::new_rgb(12, 34, 56);
+    assert_eq!(color.downsample(ColorDepth::TrueColor), color);
+}
+
+#[test]
+fn rgb_downsamples_to_palette() {
+    assert_eq!(
+        Color::new_rgb(255, 0, 0).downsample(ColorDepth::Ansi256),
+        Color::new_one_byte(196)
+    );
+    assert_eq!(
+        Color::new_rgb(128, 128, 128).downsample(ColorDepth::Ansi256),
+        Color::new_one_byte(243)
+    );
+    assert_eq!(
+        Color::new_rgb(250, 10, 10).downsample(ColorDepth::Ansi16),
+        Color::new_one_byte(9)
+    );
+    assert_eq!(
+        Color::new_one_byte(21).downsample(ColorDepth::Ansi16),
+        Color::new_one_byte(4)
+    );
+    assert_eq!(
+        Color::default().downsample(ColorDepth::Ansi16),
+        Color::default()
+    );
+}
+
+#[test]
+fn colors_mix_towards_each_other() {
+    let tint = Color::new_rgb(100, 0, 0);
+    assert_eq!(
+        Color::new_rgb(0, 0, 200).mix(&tint, 0.25),
+        Color::new_rgb(25, 0, 150)
+    );
+    assert_eq!(Color::new_one_byte(0).mix(&tint, 1.0), tint);
+    assert_eq!(Color::default().mix(&tint, 0.25), tint);
+}
@@ -1 +1,6 @@
 mod canvas;
+mod color;
+mod rect;
+mod standalone;
+mod style;
+mod text;
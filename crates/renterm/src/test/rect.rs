@@ -0,0 +1,35 @@
+use crate::rect::Rect;
+use crate::vector::Vector2;
+
+#[test]
+fn rect_from_position_and_size() {
+    let rect: Rect = Rect::from((Vector2::new(1, 2), Vector2::new(3, 4)));
+
+    assert_eq!(rect, Rect::new(Vector2::new(1, 2), Vector2::new(3, 4)));
+}
+
+#[test]
+fn rect_from_four_scalars() {
+    let rect: Rect = (1, 2, 3, 4).into();
+    assert_eq!(rect.position(), Vector2::new(1, 2));
+    assert_eq!(rect.size(), Vector2::new(3, 4));
+
+    let rect: Rect<i32> = (1u16, 2i8, 3u8, 4i16).into();
+    assert_eq!(rect, Rect::new(Vector2::new(1, 2), Vector2::new(3, 4)));
+}
+
+#[test]
+fn negative_tuple_sizes_are_clamped() {
+    let rect: Rect = (5, 5, -2, 3).into();
+
+    assert_eq!(rect.size(), Vector2::new(0, 3));
+}
+
+#[test]
+fn vector_to_and_from_tuples() {
+    let vector: Vector2<u16> = (3u8, 4u16).into();
+    assert_eq!(vector, Vector2::new(3u16, 4u16));
+
+    let (x, y): (u16, u16) = vector.into();
+    assert_eq!((x, y), (3, 4));
+}
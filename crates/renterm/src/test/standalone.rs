@@ -0,0 +1,45 @@
+use crate::canvas::Canvas;
+use crate::cell::Cell;
+use crate::color::Color;
+use crate::rect::Rect;
+use crate::style::Style;
+use crate::surface::Surface;
+use crate::text::DrawableStr;
+use crate::vector::Vector2;
+
+fn row_text(canvas: &Canvas, y: i32) -> String {
+    (0..canvas.size().x)
+        .map(|x| canvas.get_cell(Vector2::new(x, y)).to_string())
+        .collect()
+}
+
+#[test]
+fn draw_text_into_standalone_canvas() {
+    let mut canvas: Canvas = Canvas::new_filled(Vector2::new(12, 3), Cell::new('.'));
+    let text = DrawableStr::new("renterm", Style::default());
+    canvas.draw_in(&text, Rect::new(Vector2::new(2, 1), Vector2::new(8, 1)));
+    assert_eq!(row_text(&canvas, 0), "............");
+    assert_eq!(row_text(&canvas, 1), "..renterm...");
+}
+
+#[test]
+fn ansi_string_emits_style_changes_once() {
+    let mut canvas: Canvas = Canvas::new(Vector2::new(4, 2));
+    let red = Style::default().with_foreground_color(Color::new_one_byte(1));
+    canvas.draw_in(
+        &DrawableStr::new("ab", red),
+        Rect::new(Vector2::new(0, 0), Vector2::new(2, 1)),
+    );
+    let output = canvas.to_ansi_string();
+    let rows: Vec<&str> = output.lines().collect();
+    assert_eq!(rows[0], "\x1b[49m\x1b[31mab\x1b[49m\x1b[39m  \x1b[0m");
+    assert_eq!(rows[1], "\x1b[49m\x1b[39m    \x1b[0m");
+}
+
+#[test]
+fn ansi_string_resets_after_attributes() {
+    let mut canvas: Canvas = Canvas::new(Vector2::new(2, 1));
+    canvas.set_cell(Vector2::new(0, 0), Cell::new_styled("a", Style::default().with_inverse(true)));
+    let output = canvas.to_ansi_string();
+    assert_eq!(output, "\x1b[49m\x1b[39m\x1b[7ma\x1b[0m\x1b[49m\x1b[39m \x1b[0m\n");
+}
@@ -0,0 +1,32 @@
+use crate::color::Color;
+use crate::style::Style;
+
+#[test]
+fn style_flags_read_back() {
+    let style = Style::default()
+        .with_bold(true)
+        .with_italic(true)
+        .with_underline(true)
+        .with_strikethrough(true)
+        .with_dim(true)
+        .with_foreground_color(Color::new_one_byte(2));
+    assert!(style.is_bold());
+    assert!(style.is_italic());
+    assert!(style.is_underline());
+    assert!(style.is_strikethrough());
+    assert!(style.is_dim());
+    assert_eq!(style.foreground_color(), Color::new_one_byte(2));
+
+    let plain = style.with_bold(false).with_dim(false);
+    assert!(!plain.is_bold());
+    assert!(!plain.is_dim());
+    assert!(plain.is_italic());
+    assert!(!Style::default().is_underline());
+}
+
+#[test]
+fn style_attributes_are_encoded() {
+    let style = Style::default().with_bold(true).with_inverse(true);
+    let bytes: Vec<u8> = style.into();
+    assert_eq!(bytes, b"\x1b[49m\x1b[39m\x1b[1m\x1b[7m");
+}
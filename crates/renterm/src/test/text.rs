@@ -0,0 +1,55 @@
+use crate::canvas::Canvas;
+use crate::rect::Rect;
+use crate::style::Style;
+use crate::surface::Surface;
+use crate::text::DrawableStr;
+use crate::vector::Vector2;
+
+fn draw_str(text: &DrawableStr) -> String {
+    let mut canvas: Canvas = Canvas::new(Vector2::new(10, 1));
+    canvas.draw_in(text, Rect::new(Vector2::new(2, 0), Vector2::new(6, 1)));
+    (0..canvas.size().x)
+        .map(|x| canvas.get_cell(Vector2::new(x, 0)).to_string())
+        .collect()
+}
+
+#[test]
+fn truncate_without_ellipsis() {
+    let text = DrawableStr::truncated("a long title", Style::default(), false);
+    assert_eq!(draw_str(&text), "  a long  ");
+}
+
+#[test]
+fn truncate_with_ellipsis() {
+    let text = DrawableStr::truncated("a long title", Style::default(), true);
+    assert_eq!(draw_str(&text), "  a lon…  ");
+}
+
+#[test]
+fn fitting_string_has_no_ellipsis() {
+    let text = DrawableStr::truncated("title", Style::default(), true);
+    assert_eq!(draw_str(&text), "  title   ");
+}
+
+#[test]
+fn wide_characters_take_two_cells() {
+    let text = DrawableStr::truncated("中文", Style::default(), true);
+    assert_eq!(draw_str(&text), "  中文    ");
+    assert_eq!(text.size(), Vector2::new(4usize, 1usize));
+
+    let mut canvas: Canvas = Canvas::new(Vector2::new(6, 1));
+    canvas.draw_in(&text, Rect::new(Vector2::new(0, 0), Vector2::new(6, 1)));
+    let widths: Vec<usize> = (0..6)
+        .map(|x| canvas.get_cell(Vector2::new(x, 0)).width())
+        .collect();
+    assert_eq!(widths, vec![2, 0, 2, 0, 1, 1]);
+}
+
+#[test]
+fn truncated_wide_characters_stay_within_their_slot() {
+    let text = DrawableStr::truncated("ab中文字", Style::default(), true);
+    assert_eq!(draw_str(&text), "  ab中…   ");
+
+    let text = DrawableStr::truncated("abc中文", Style::default(), false);
+    assert_eq!(draw_str(&text), "  abc中   ");
+}
@@ -1,3 +1,5 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
 use crate::scalar::Scalar;
 
 use super::{cell::Cell, drawable::Drawable, style::Style, surface::Surface, vector::Vector2};
@@ -5,26 +7,59 @@ use super::{cell::Cell, drawable::Drawable, style::Style, surface::Surface, vect
 #[derive(Debug)]
 pub struct DrawableStr<'a> {
     string: &'a str,
-    style: Style
+    style: Style,
+    ellipsis: bool,
 }
 
 impl <'a> DrawableStr<'a> {
     pub fn new(string: &'a str, style: Style) -> Self {
-        DrawableStr::<'a> { string, style }
+        DrawableStr::<'a> { string, style, ellipsis: false }
+    }
+    /// Like `new`, but when the string doesn't fit, its last visible cell is
+    /// replaced with an ellipsis if `ellipsis` is set.
+    pub fn truncated(string: &'a str, style: Style, ellipsis: bool) -> Self {
+        DrawableStr::<'a> { string, style, ellipsis }
     }
     pub fn size(&self) -> Vector2<usize> {
-        Vector2::new(self.string.len(), 1 as usize)
+        Vector2::new(text_width(self.string), 1 as usize)
     }
 }
 
+/// How many columns `text` takes up in a terminal, counting wide characters
+/// like CJK and emoji as two.
+pub fn text_width(text: &str) -> usize {
+    text.width()
+}
+
 impl <S: Scalar> Drawable<S> for DrawableStr<'_> {
+    /// Draws one character per cell, and wide characters over two cells, the
+    /// second holding a `Cell::wide_continuation`. A wide character that
+    /// doesn't fit in the last column is left out.
     fn draw(&self, canvas: &mut dyn Surface<S>) {
-        let str = self.string;
-        let chars = str.chars().collect::<Vec<char>>();
-        let mut x: S = S::zero();
-        for c in chars {
-            canvas.set_cell((x, S::zero()).into(), Cell::new_styled(c, self.style.clone()));
-            x = x + S::one();
+        let width: usize = canvas.size().x.max(S::zero()).as_();
+        let fits = text_width(self.string) <= width;
+        // The ellipsis takes the last column of a string that doesn't fit.
+        let limit = if fits || !self.ellipsis { width } else { width.saturating_sub(1) };
+        let mut x = 0;
+        for c in self.string.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if char_width == 0 {
+                continue;
+            }
+            if x + char_width > limit {
+                break;
+            }
+            let position = (S::from_usize(x).unwrap(), S::zero()).into();
+            canvas.set_cell(position, Cell::new_styled(c, self.style.clone()));
+            for offset in 1..char_width {
+                let position = (S::from_usize(x + offset).unwrap(), S::zero()).into();
+                canvas.set_cell(position, Cell::wide_continuation(self.style.clone()));
+            }
+            x += char_width;
+        }
+        if !fits && self.ellipsis && x < width {
+            let position = (S::from_usize(x).unwrap(), S::zero()).into();
+            canvas.set_cell(position, Cell::new_styled('…', self.style.clone()));
         }
     }
 }
@@ -1,5 +1,7 @@
 use std::ops::{Add, Div, Sub};
 
+use num_traits::Bounded;
+
 use crate::{scalar::Scalar, DefaultScalar};
 
 use super::rect::Rect;
@@ -47,6 +49,47 @@ impl <S: Scalar> Vector2<S> {
             y: self.y.signum(),
         }
     }
+    /// Converts both components to another scalar type, failing if either
+    /// doesn't fit.
+    pub fn try_cast<T: Scalar + TryFrom<S>>(self) -> Result<Vector2<T>, T::Error> {
+        Ok(Vector2 {
+            x: T::try_from(self.x)?,
+            y: T::try_from(self.y)?,
+        })
+    }
+    /// Converts both components to another scalar type, clamping values that
+    /// don't fit to its bounds.
+    pub fn saturating_cast<T: Scalar + TryFrom<S> + Bounded>(self) -> Vector2<T> {
+        let cast = |value: S| {
+            T::try_from(value).unwrap_or_else(|_| {
+                if value < S::zero() {
+                    T::min_value()
+                } else {
+                    T::max_value()
+                }
+            })
+        };
+        Vector2 {
+            x: cast(self.x),
+            y: cast(self.y),
+        }
+    }
+}
+
+impl TryFrom<Vector2<i32>> for Vector2<i16> {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(value: Vector2<i32>) -> Result<Self, Self::Error> {
+        value.try_cast()
+    }
+}
+
+impl TryFrom<Vector2<i32>> for Vector2<u16> {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(value: Vector2<i32>) -> Result<Self, Self::Error> {
+        value.try_cast()
+    }
 }
 
 impl <T: Scalar> From<Vector2<T>> for Rect<T> {
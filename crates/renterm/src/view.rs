@@ -5,12 +5,15 @@ use super::{cell::Cell, rect::Rect, surface::Surface, vector::Vector2};
 pub struct SurfaceView<'a, S: Scalar = DefaultScalar> {
     canvas: Box<&'a mut dyn Surface<S>>,
     rect: Rect<S>,
+    /// Part of the view, in its own coordinates, that reaches the parent.
+    /// Without one, all of `rect` does.
+    clip: Option<Rect<S>>,
 }
 
 impl <'a, S: Scalar> From<Box<&'a mut dyn Surface<S>>> for SurfaceView<'a, S> {
     fn from(canvas: Box<&'a mut dyn Surface<S>>) -> SurfaceView<'a, S> {
         let rect: Rect<S> = Rect::<S>::new(Vector2::<S>::null(), canvas.size());
-        SurfaceView { rect, canvas }
+        SurfaceView { rect, canvas, clip: None }
     }
 }
 
@@ -22,11 +25,23 @@ impl<'a, S: Scalar> SurfaceView<'a, S> {
         if position.x >= self.rect.size().x || position.y >= self.rect.size().y {
             return false;
         }
+        if let Some(clip) = &self.clip {
+            return clip.contains(position.clone());
+        }
         true
     }
     pub fn set_rect(&mut self, rect: Rect<S>) {
         self.rect = rect;
     }
+    /// Only lets cells inside `clip` through to the parent, while the view
+    /// keeps its size. The clip is in the view's coordinates and isn't
+    /// changed by `set_size`.
+    pub fn set_clip(&mut self, clip: Option<Rect<S>>) {
+        self.clip = clip;
+    }
+    pub fn clip(&self) -> Option<Rect<S>> {
+        self.clip.clone()
+    }
 }
 
 impl <'a, S: Scalar> Surface<S> for SurfaceView<'a, S> {
@@ -53,6 +68,6 @@ impl <'a, S: Scalar> Surface<S> for SurfaceView<'a, S> {
     }
 
     fn to_sub_view(&mut self, rect: Rect<S>) -> SurfaceView<S> {
-        SurfaceView { rect, canvas: Box::new(self) }
+        SurfaceView { rect, canvas: Box::new(self), clip: None }
     }
 }
@@ -7,6 +7,17 @@ use clap::Parser;
 pub struct CliArgs {
     pub log_file: Option<String>,
     pub enable_logging: bool,
+    pub debug_layout: bool,
+    /// Log how long each frame took to draw and how much it wrote.
+    pub debug_metrics: bool,
+    /// Refuse to start when the config file is invalid, instead of using the defaults.
+    pub strict_config: bool,
+    /// Validate the config file and exit instead of starting the UI.
+    pub check_config: bool,
+    /// Project file to open. A `.citymux.kdl` that is found is only pointed out.
+    pub project: Option<String>,
+    /// Start with the first pane zoomed, see `zoom::zoom`.
+    pub zoom: bool,
 }
 
 impl CliArgs {
@@ -17,10 +28,35 @@ impl CliArgs {
             .get_one::<bool>("enableLogging")
             .map(|e| *e)
             .unwrap_or_default();
+        let debug_layout = matches
+            .get_one::<bool>("debugLayout")
+            .copied()
+            .unwrap_or_default();
+        let debug_metrics = matches
+            .get_one::<bool>("debugMetrics")
+            .copied()
+            .unwrap_or_default();
+
+        let strict_config = matches
+            .get_one::<bool>("strictConfig")
+            .copied()
+            .unwrap_or_default();
+        let check_config = matches
+            .get_one::<bool>("checkConfig")
+            .copied()
+            .unwrap_or_default();
+        let project = matches.get_one::<String>("project").cloned();
+        let zoom = matches.get_one::<bool>("zoom").copied().unwrap_or_default();
 
         CliArgs {
             log_file,
             enable_logging,
+            debug_layout,
+            debug_metrics,
+            strict_config,
+            check_config,
+            project,
+            zoom,
         }
     }
 }
@@ -42,4 +78,48 @@ pub fn get_clap_parser() -> Command {
                 .num_args(0)
                 .required(false),
         )
+        .arg(
+            Arg::new("strictConfig")
+                .long("strict-config")
+                .help("Exit with an error when the config file is invalid")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("checkConfig")
+                .long("check-config")
+                .help("Check the config file for errors and exit")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("project")
+                .long("project")
+                .value_name("FILE")
+                .help("Open the layout and commands of a project file")
+                .required(false),
+        )
+        .arg(
+            Arg::new("zoom")
+                .long("zoom")
+                .help("Start with the first pane zoomed to the whole screen")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("debugLayout")
+                .long("debug-layout")
+                .help("Draw pane ids and dimensions over the layout")
+                .num_args(0)
+                .hide(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("debugMetrics")
+                .long("debug-metrics")
+                .help("Log render timings at trace level")
+                .num_args(0)
+                .hide(true)
+                .required(false),
+        )
 }
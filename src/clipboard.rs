@@ -0,0 +1,56 @@
+use std::process::Stdio;
+
+use anyhow::Context;
+use tokio::{io::AsyncWriteExt, task::JoinHandle};
+
+use crate::{escape_codes::SetClipboard, process::shell_command, state::StateContainer};
+
+fn copy_process(command: &str) -> tokio::process::Command {
+    let mut process = shell_command(command);
+    process
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    process
+}
+
+/// Puts `text` on the clipboard. It is piped to `Config::copy_command` when
+/// one is set, for hosts that block OSC 52, and sent to the host terminal
+/// with OSC 52 otherwise. The command isn't waited for here, the task that
+/// waits for it is returned.
+pub async fn copy_to_clipboard(
+    state_container: &StateContainer,
+    text: &str,
+) -> anyhow::Result<Option<JoinHandle<()>>> {
+    let command = { state_container.config.read().await.copy_command.clone() };
+    let Some(command) = command else {
+        let sequence: Vec<u8> = SetClipboard::new(text).into();
+        let stdout = state_container.stdout.clone();
+        let mut stdout = stdout.lock().await;
+        stdout.write_all(&sequence).await?;
+        stdout.flush().await?;
+
+        return Ok(None);
+    };
+
+    let mut child = copy_process(&command)
+        .spawn()
+        .with_context(|| format!("Could not run copy command {:?}", command))?;
+    let stdin = child.stdin.take();
+    let text = text.to_string();
+    let task = tokio::spawn(async move {
+        if let Some(mut stdin) = stdin {
+            if let Err(err) = stdin.write_all(text.as_bytes()).await {
+                tracing::warn!("Could not write to copy command: {:?}", err);
+            }
+        }
+        match child.wait().await {
+            Ok(status) if status.success() => {}
+            Ok(status) => tracing::warn!("Copy command failed: {:?}", status),
+            Err(err) => tracing::warn!("Could not wait for copy command: {:?}", err),
+        }
+    });
+
+    Ok(Some(task))
+}
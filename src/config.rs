@@ -1,8 +1,357 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
+use anyhow::Context;
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton};
+use renterm::{
+    canvas::DEFAULT_MAX_CELLS,
+    color::{Color, ColorDepth},
+};
+
+use crate::{
+    hooks::Hooks,
+    keybind::{builtin_prefix_action, Action, KeyBinding, MouseArea, MouseBinding},
+    span::SpanDirection,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RenderStrategy {
+    /// Keep the previous frame around and only emit the cells that changed.
+    DoubleBuffer,
+    /// Emit every cell of every frame.
+    Direct,
+}
+
+impl RenderStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "double_buffer" => Some(RenderStrategy::DoubleBuffer),
+            "direct" => Some(RenderStrategy::Direct),
+            _ => None,
+        }
+    }
+}
+
+/// How the active pane's title is told apart from the others, on top of its color.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ActiveIndicator {
+    None,
+    /// The title is prefixed with this marker.
+    Marker(String),
+    /// The title colors are swapped.
+    Reverse,
+}
+
+impl ActiveIndicator {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "none" => ActiveIndicator::None,
+            "reverse" => ActiveIndicator::Reverse,
+            marker => ActiveIndicator::Marker(marker.to_string()),
+        }
+    }
+}
+
+/// A program that can be opened in a new pane with a key binding.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Launcher {
+    /// Shown as the pane title until the program sets one.
+    pub name: String,
+    pub command: String,
+    /// Key pressed after the prefix to open it.
+    pub key: Option<KeyCode>,
+    /// Background tint of the pane, given as `tint="#203040"`.
+    pub tint: Option<Color>,
+}
+
+impl Launcher {
+    /// Splits the command into the program and its arguments.
+    pub fn command_line(&self) -> Option<(String, Vec<String>)> {
+        let mut parts = split_command_line(&self.command).into_iter();
+        let program = parts.next()?;
+
+        Some((program, parts.collect()))
+    }
+}
+
+/// Splits a command into words like a shell would, honouring single and
+/// double quotes and backslash escapes. An unterminated quote runs to the end.
+pub fn split_command_line(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                words.extend(word.take());
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.next());
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => word.push('\\'),
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    words
+}
+
+/// A fixed string typed into the active pane with a key binding, like a macro.
 #[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SendString {
+    /// Key pressed after the prefix to send it.
+    pub key: KeyCode,
+    pub bytes: Vec<u8>,
+}
+
+/// Fraction of the split pane's space that a new pane takes, per direction.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SplitRatio {
+    pub horizontal: f64,
+    pub vertical: f64,
+}
+
+impl SplitRatio {
+    pub fn new(ratio: f64) -> Self {
+        SplitRatio {
+            horizontal: ratio,
+            vertical: ratio,
+        }
+    }
+    pub fn for_direction(&self, direction: SpanDirection) -> f64 {
+        match direction {
+            SpanDirection::Horizontal => self.horizontal,
+            SpanDirection::Vertical => self.vertical,
+        }
+    }
+}
+
+/// Picks the color depth of the outer terminal from `COLORTERM` and `TERM`.
+/// Windows Terminal, which sets `WT_SESSION`, and the Windows console both
+/// support 24-bit color without setting either of them.
+pub fn detect_color_depth(
+    colorterm: Option<&str>,
+    term: Option<&str>,
+    wt_session: Option<&str>,
+) -> ColorDepth {
+    if matches!(colorterm, Some("truecolor" | "24bit")) || wt_session.is_some() {
+        return ColorDepth::TrueColor;
+    }
+    if term.is_some_and(|term| term.contains("256color")) {
+        return ColorDepth::Ansi256;
+    }
+    if cfg!(target_os = "windows") && colorterm.is_none() && term.is_none() {
+        return ColorDepth::TrueColor;
+    }
+
+    ColorDepth::Ansi16
+}
+
+/// Characters pane borders are drawn with.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BorderStyle {
+    /// `+`, `-` and `|`, for terminals without box-drawing glyphs.
+    Ascii,
+    Single,
+    /// Like `Single`, with rounded corners.
+    Rounded,
+}
+
+/// The characters of a border: vertical, horizontal, then the corners
+/// clockwise from the top left.
+pub struct BorderGlyphs {
+    pub vertical: &'static str,
+    pub horizontal: &'static str,
+    pub top_left: &'static str,
+    pub top_right: &'static str,
+    pub bottom_right: &'static str,
+    pub bottom_left: &'static str,
+}
+
+impl BorderStyle {
+    /// Parses `ascii`, `single` and `rounded`. `auto` is handled by the caller.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ascii" => Some(BorderStyle::Ascii),
+            "single" => Some(BorderStyle::Single),
+            "rounded" => Some(BorderStyle::Rounded),
+            _ => None,
+        }
+    }
+    pub fn glyphs(self) -> BorderGlyphs {
+        let (top_left, top_right, bottom_right, bottom_left) = match self {
+            BorderStyle::Ascii => ("+", "+", "+", "+"),
+            BorderStyle::Single => ("┌", "┐", "┘", "└"),
+            BorderStyle::Rounded => ("╭", "╮", "╯", "╰"),
+        };
+        let (vertical, horizontal) = match self {
+            BorderStyle::Ascii => ("|", "-"),
+            BorderStyle::Single | BorderStyle::Rounded => ("│", "─"),
+        };
+
+        BorderGlyphs {
+            vertical,
+            horizontal,
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        }
+    }
+}
+
+/// Terminals that have no box-drawing glyphs at all.
+const ASCII_ONLY_TERMS: &[&str] = &["dumb", "vt52"];
+
+/// Picks the border style from `TERM` and the locale, the first of `LC_ALL`
+/// and `LC_CTYPE` that is set. Box-drawing glyphs are only given up on when
+/// the terminal has none, or when the locale names a character set other
+/// than UTF-8, like `en_US.ISO-8859-1`. `C`, `POSIX` and `LANG` say little
+/// about what the terminal shows, so they keep the glyphs.
+pub fn detect_border_style(term: Option<&str>, locale: Option<&str>) -> BorderStyle {
+    if term.is_some_and(|term| ASCII_ONLY_TERMS.contains(&term)) {
+        return BorderStyle::Ascii;
+    }
+    let codeset = locale
+        .and_then(|locale| locale.split('@').next()?.split_once('.'))
+        .map(|(_, codeset)| codeset.to_ascii_lowercase());
+    if codeset.is_some_and(|codeset| codeset != "utf-8" && codeset != "utf8") {
+        return BorderStyle::Ascii;
+    }
+
+    BorderStyle::Single
+}
+
+/// The locale that decides the character set, see `detect_border_style`.
+fn locale_from_env() -> Option<String> {
+    ["LC_ALL", "LC_CTYPE"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+fn detect_border_style_from_env() -> BorderStyle {
+    detect_border_style(
+        std::env::var("TERM").ok().as_deref(),
+        locale_from_env().as_deref(),
+    )
+}
+
+/// Parses colors written like `#203040`.
+pub fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+
+    Some(Color::new_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn color_depth_from_name(name: &str) -> Option<ColorDepth> {
+    match name {
+        "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+        "256" => Some(ColorDepth::Ansi256),
+        "16" => Some(ColorDepth::Ansi16),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Config {
     pub default_shell: String,
+    pub default_shell_args: Vec<String>,
+    /// Value of `TERM` for programs started in panes.
+    pub term: String,
+    /// Extra environment variables for programs started in panes.
+    pub env: HashMap<String, String>,
+    pub render_strategy: RenderStrategy,
+    /// Colors are approximated to what the outer terminal can show.
+    pub color_depth: ColorDepth,
+    pub use_alternate_screen: bool,
+    pub scroll_lines: usize,
+    pub software_cursor: bool,
+    /// Marks where the cursor of each inactive pane is with a dim inverse cell.
+    pub show_inactive_cursors: bool,
+    pub message_duration_ms: u64,
+    pub pane_gap: u16,
+    pub confirm_multiline_paste: bool,
+    /// Ask before quitting while more than one pane is open.
+    pub confirm_kill: bool,
+    pub enable_mouse: bool,
+    /// Clicks that focus an inactive pane aren't forwarded to its program.
+    pub focus_click_swallow: bool,
+    /// Rings the host terminal's bell when a background pane rings its own.
+    pub forward_bell: bool,
+    /// Alt shortcuts only match when no other modifier is held, so that
+    /// e.g. ctrl+alt+n reaches the program instead of opening a pane.
+    pub strict_shortcuts: bool,
+    /// Key that has to be pressed before a citymux key binding.
+    pub prefix: KeyBinding,
+    /// How long the prefix waits for the key after it, 0 waits forever.
+    pub prefix_timeout_ms: u64,
+    /// Upper bound on the number of frames drawn per second.
+    pub max_fps: u32,
+    /// Most cells the screen is drawn with, rows past it are left blank on
+    /// huge hosts instead of taking up memory.
+    pub max_canvas_cells: usize,
+    pub active_indicator: ActiveIndicator,
+    /// Set to `auto` in the config for `detect_border_style`, the default.
+    pub border_style: BorderStyle,
+    pub launchers: Vec<Launcher>,
+    pub mouse_bindings: Vec<MouseBinding>,
+    /// Set in the `keybindings` block, see `parse_key_bindings`.
+    pub send_strings: Vec<SendString>,
+    pub default_split_ratio: SplitRatio,
+    /// Opens new panes before the active one, to its left or above it.
+    pub split_before: bool,
+    /// Whether citymux exits once its last pane is closed.
+    pub exit_on_last_pane: bool,
+    /// Reserves the bottom row for a status bar.
+    pub status_bar: bool,
+    /// Shell command whose first line of output is shown on the right of the status bar.
+    pub status_right_command: Option<String>,
+    /// How often `status_right_command` is run.
+    pub status_interval_ms: u64,
+    /// Set in the `hooks` block, like `hooks { pane_created "notify-send $1"; }`.
+    pub hooks: Hooks,
+    /// Treat every Enter as the keypad Enter, sending `ESC O M` to panes in
+    /// application keypad mode.
+    pub keypad_enter: bool,
+    /// Turns on win32-input-mode on Windows hosts, see `escape_codes::SetWin32InputMode`.
+    pub win32_input: bool,
+    /// Asks the host terminal to report keys with the kitty keyboard protocol.
+    pub enhanced_keyboard: bool,
+    /// Template of pane titles, see `draw::format_title`.
+    pub title_format: String,
+    /// Characters that end a word when double clicking to select one.
+    pub word_separators: String,
+    /// Shell command, like `wl-copy` or `pbcopy`, that selected text is piped
+    /// to. Without one it is copied with OSC 52.
+    pub copy_command: Option<String>,
+    /// Starts with the first pane zoomed, like `--zoom`.
+    pub start_zoomed: bool,
 }
 
 pub fn get_default_config() -> Config {
@@ -12,55 +361,745 @@ pub fn get_default_config() -> Config {
         "bash".to_string()
     };
     let default_shell = std::env::var("SHELL").ok().unwrap_or(os_default_shell);
+    let color_depth = detect_color_depth(
+        std::env::var("COLORTERM").ok().as_deref(),
+        std::env::var("TERM").ok().as_deref(),
+        std::env::var("WT_SESSION").ok().as_deref(),
+    );
 
-    Config { default_shell }
+    Config {
+        default_shell,
+        default_shell_args: Vec::new(),
+        term: "xterm-citymux".to_string(),
+        env: HashMap::new(),
+        render_strategy: RenderStrategy::DoubleBuffer,
+        color_depth,
+        use_alternate_screen: true,
+        scroll_lines: 3,
+        software_cursor: false,
+        show_inactive_cursors: false,
+        message_duration_ms: 2000,
+        pane_gap: 0,
+        confirm_multiline_paste: true,
+        confirm_kill: true,
+        enable_mouse: true,
+        focus_click_swallow: true,
+        forward_bell: false,
+        strict_shortcuts: true,
+        prefix: KeyBinding::new(KeyCode::Char('b'), KeyModifiers::CONTROL),
+        prefix_timeout_ms: 2000,
+        max_fps: 60,
+        max_canvas_cells: DEFAULT_MAX_CELLS,
+        active_indicator: ActiveIndicator::None,
+        border_style: detect_border_style_from_env(),
+        launchers: Vec::new(),
+        mouse_bindings: Vec::new(),
+        send_strings: Vec::new(),
+        default_split_ratio: SplitRatio::new(0.5),
+        split_before: false,
+        exit_on_last_pane: true,
+        status_bar: false,
+        status_right_command: None,
+        status_interval_ms: 5000,
+        hooks: Hooks::default(),
+        keypad_enter: false,
+        win32_input: true,
+        enhanced_keyboard: true,
+        title_format: "[{title}]".to_string(),
+        word_separators: " \t,;:.!?'\"`()[]{}<>|=".to_string(),
+        copy_command: None,
+        start_zoomed: false,
+    }
 }
 
-fn get_xdg_config_dir() -> Option<PathBuf> {
-    let dir = std::env::var("XDG_CONFIG_HOME").ok()?;
+/// Looks up an environment variable, the lookup the config path functions
+/// use outside of tests.
+pub fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
 
+/// Returns `XDG_CONFIG_HOME` when it is set, whether or not the directory exists.
+fn get_xdg_config_dir(env: fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let dir = env("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty())?;
     let path = PathBuf::from(dir);
-    if path.exists() && path.is_dir() {
-        return Some(path);
+    if !path.is_dir() {
+        tracing::warn!(
+            "XDG_CONFIG_HOME is set to {:?}, which is not a directory",
+            path
+        );
+    }
+
+    Some(path)
+}
+
+/// Directories that are searched for `citymux/config.kdl`, in order. A set
+/// `XDG_CONFIG_HOME`, read through `env`, replaces the defaults, which are
+/// `~/.config` and, on macOS, `~/Library/Application Support`.
+pub fn get_config_dirs(env: fn(&str) -> Option<String>) -> Vec<PathBuf> {
+    if let Some(xdg_config_dir) = get_xdg_config_dir(env) {
+        return vec![xdg_config_dir];
+    }
+    let mut dirs = Vec::new();
+    if let Some(home_dir) = dirs::home_dir() {
+        dirs.push(home_dir.join(".config"));
+    }
+    if cfg!(target_os = "macos") {
+        if let Some(config_dir) = dirs::config_dir() {
+            dirs.push(config_dir);
+        }
     }
 
-    None
+    dirs
 }
 
-fn get_home_config_dir() -> Option<PathBuf> {
-    let home_dir = dirs::home_dir()?;
-    let path = home_dir.join(".config");
+/// Path of the config file that is used, if there is one.
+pub fn get_config_file() -> Option<PathBuf> {
+    get_config_file_with(env_var)
+}
 
-    Some(path)
+/// Like `get_config_file`, with the environment read through `env`.
+pub fn get_config_file_with(env: fn(&str) -> Option<String>) -> Option<PathBuf> {
+    get_config_dirs(env)
+        .into_iter()
+        .map(|dir| dir.join("citymux").join("config.kdl"))
+        .find(|path| path.is_file())
 }
 
-fn get_config_dir() -> Option<PathBuf> {
-    get_xdg_config_dir().or_else(|| get_home_config_dir())
+/// Reads the config file. Without a file the defaults are used, a file that
+/// can't be read or parsed is an error.
+pub fn load_config() -> anyhow::Result<Config> {
+    let Some(config_file) = get_config_file() else {
+        return Ok(get_default_config());
+    };
+    let contents = std::fs::read_to_string(&config_file)
+        .with_context(|| format!("Could not read config file {:?}", config_file))?;
+
+    parse_config(&contents).with_context(|| format!("Invalid config file {:?}", config_file))
+}
+
+/// An error about a node of the config file, with the byte offset the node
+/// starts at so that `error_line` can show its line.
+#[derive(Debug)]
+pub struct NodeError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl std::error::Error for NodeError {}
+
+impl std::fmt::Display for NodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
 }
 
-fn get_config_optional() -> Option<Config> {
+fn node_error(node: &kdl::KdlNode, error: anyhow::Error) -> anyhow::Error {
+    NodeError {
+        offset: node.span().offset(),
+        message: format!("{:#}", error),
+    }
+    .into()
+}
+
+/// Points an error about the top-level key `name` at its node.
+fn key_error(document: &kdl::KdlDocument, name: &str, error: anyhow::Error) -> anyhow::Error {
+    match find_node(document, name) {
+        Some(node) => node_error(node, error),
+        None => error,
+    }
+}
+
+/// Finds the line of `contents` that an error points at, from the span of a
+/// KDL syntax error or of the node of a `NodeError`. Returns its number,
+/// counting from 1, and its text.
+pub fn error_line<'a>(contents: &'a str, error: &anyhow::Error) -> Option<(usize, &'a str)> {
+    let offset = error.chain().find_map(|error| {
+        if let Some(error) = error.downcast_ref::<kdl::KdlError>() {
+            return error
+                .diagnostics
+                .first()
+                .map(|diagnostic| diagnostic.span.offset());
+        }
+        error.downcast_ref::<NodeError>().map(|error| error.offset)
+    })?;
+    let index = contents.get(..offset)?.matches('\n').count();
+
+    Some((index + 1, contents.lines().nth(index).unwrap_or_default()))
+}
+
+/// Validates the config file without starting citymux, printing what is
+/// wrong with it. Returns whether it is valid.
+pub fn check_config() -> bool {
+    let Some(config_file) = get_config_file() else {
+        println!("No config file found, the defaults are used");
+        return true;
+    };
+    let contents = match std::fs::read_to_string(&config_file) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("Could not read config file {:?}: {}", config_file, err);
+            return false;
+        }
+    };
+    match parse_config(&contents) {
+        Ok(_) => {
+            println!("{}: ok", config_file.display());
+            true
+        }
+        Err(err) => {
+            eprintln!("{}: {:#}", config_file.display(), err);
+            if let Some((number, line)) = error_line(&contents, &err) {
+                eprintln!("  --> line {}", number);
+                eprintln!("   | {}", line);
+            }
+            false
+        }
+    }
+}
+
+/// Parses the contents of a config file, starting from the defaults.
+pub fn parse_config(contents: &str) -> anyhow::Result<Config> {
     let mut config = get_default_config();
-    let config_dir = get_config_dir()?;
-    let config_file = config_dir.join("citymux").join("config.kdl");
-    let contents = std::fs::read_to_string(config_file).ok()?;
-    let document = kdl::KdlDocument::parse_v2(&contents).ok()?;
-    let shell_node = document
+    let document = kdl::KdlDocument::parse_v2(contents)?;
+
+    if let Some(default_shell) = read_key(&document, "default_shell")? {
+        config.default_shell = default_shell;
+    }
+    if let Some(default_shell_args) = read_list(&document, "default_shell_args")? {
+        config.default_shell_args = default_shell_args;
+    }
+    if let Some(term) = read_key(&document, "term")? {
+        config.term = term;
+    }
+    if let Some(env) = read_map(&document, "env")? {
+        config.env = env;
+    }
+    if let Some(render_strategy) = read_key::<String>(&document, "render_strategy")? {
+        config.render_strategy = RenderStrategy::from_name(&render_strategy).ok_or_else(|| {
+            let error = anyhow::format_err!(
+                "`render_strategy` expects \"double_buffer\" or \"direct\", got {:?}",
+                render_strategy
+            );
+            key_error(&document, "render_strategy", error)
+        })?;
+    }
+    if let Some(use_alternate_screen) = read_key(&document, "use_alternate_screen")? {
+        config.use_alternate_screen = use_alternate_screen;
+    }
+    if let Some(scroll_lines) = read_key(&document, "scroll_lines")? {
+        config.scroll_lines = scroll_lines;
+    }
+    if let Some(software_cursor) = read_key(&document, "software_cursor")? {
+        config.software_cursor = software_cursor;
+    }
+    if let Some(show_inactive_cursors) = read_key(&document, "show_inactive_cursors")? {
+        config.show_inactive_cursors = show_inactive_cursors;
+    }
+    if let Some(message_duration_ms) = read_key(&document, "message_duration_ms")? {
+        config.message_duration_ms = message_duration_ms;
+    }
+    if let Some(pane_gap) = read_key(&document, "pane_gap")? {
+        config.pane_gap = pane_gap;
+    }
+    if let Some(confirm_multiline_paste) = read_key(&document, "confirm_multiline_paste")? {
+        config.confirm_multiline_paste = confirm_multiline_paste;
+    }
+    if let Some(confirm_kill) = read_key(&document, "confirm_kill")? {
+        config.confirm_kill = confirm_kill;
+    }
+    if let Some(enable_mouse) = read_key(&document, "enable_mouse")? {
+        config.enable_mouse = enable_mouse;
+    }
+    if let Some(focus_click_swallow) = read_key(&document, "focus_click_swallow")? {
+        config.focus_click_swallow = focus_click_swallow;
+    }
+    if let Some(forward_bell) = read_key(&document, "forward_bell")? {
+        config.forward_bell = forward_bell;
+    }
+    if let Some(strict_shortcuts) = read_key(&document, "strict_shortcuts")? {
+        config.strict_shortcuts = strict_shortcuts;
+    }
+    if let Some(prefix) = read_key::<String>(&document, "prefix")? {
+        config.prefix = KeyBinding::parse(&prefix).ok_or_else(|| {
+            let error =
+                anyhow::format_err!("`prefix` expects a key like \"ctrl+b\", got {:?}", prefix);
+            key_error(&document, "prefix", error)
+        })?;
+    }
+    if let Some(prefix_timeout_ms) = read_key(&document, "prefix_timeout_ms")? {
+        config.prefix_timeout_ms = prefix_timeout_ms;
+    }
+    if let Some(max_fps) = read_key::<u32>(&document, "max_fps")? {
+        if max_fps == 0 {
+            let error = anyhow::format_err!("`max_fps` must be at least 1");
+            return Err(key_error(&document, "max_fps", error));
+        }
+        config.max_fps = max_fps;
+    }
+    if let Some(max_canvas_cells) = read_key::<usize>(&document, "max_canvas_cells")? {
+        if max_canvas_cells == 0 {
+            let error = anyhow::format_err!("`max_canvas_cells` must be at least 1");
+            return Err(key_error(&document, "max_canvas_cells", error));
+        }
+        config.max_canvas_cells = max_canvas_cells;
+    }
+    if let Some(active_indicator) = read_key::<String>(&document, "active_indicator")? {
+        config.active_indicator = ActiveIndicator::from_name(&active_indicator);
+    }
+    if let Some(border_style) = read_key::<String>(&document, "border_style")? {
+        config.border_style = match border_style.as_str() {
+            "auto" => detect_border_style_from_env(),
+            name => BorderStyle::from_name(name).ok_or_else(|| {
+                let error = anyhow::format_err!(
+                    "`border_style` expects \"auto\", \"ascii\", \"single\" or \"rounded\", got {:?}",
+                    border_style
+                );
+                key_error(&document, "border_style", error)
+            })?,
+        };
+    }
+    if let Some(color_depth) = read_key::<String>(&document, "color_depth")? {
+        config.color_depth = color_depth_from_name(&color_depth).ok_or_else(|| {
+            let error = anyhow::format_err!(
+                "`color_depth` expects \"truecolor\", \"256\" or \"16\", got {:?}",
+                color_depth
+            );
+            key_error(&document, "color_depth", error)
+        })?;
+    }
+    if let Some(exit_on_last_pane) = read_key(&document, "exit_on_last_pane")? {
+        config.exit_on_last_pane = exit_on_last_pane;
+    }
+
+    if let Some(status_bar) = read_key(&document, "status_bar")? {
+        config.status_bar = status_bar;
+    }
+    if let Some(status_right_command) = read_key(&document, "status_right_command")? {
+        config.status_right_command = Some(status_right_command);
+    }
+    if let Some(status_interval_ms) = read_key(&document, "status_interval_ms")? {
+        config.status_interval_ms = status_interval_ms;
+    }
+
+    if let Some(keypad_enter) = read_key(&document, "keypad_enter")? {
+        config.keypad_enter = keypad_enter;
+    }
+    if let Some(win32_input) = read_key(&document, "win32_input")? {
+        config.win32_input = win32_input;
+    }
+    if let Some(enhanced_keyboard) = read_key(&document, "enhanced_keyboard")? {
+        config.enhanced_keyboard = enhanced_keyboard;
+    }
+    if let Some(title_format) = read_key(&document, "title_format")? {
+        config.title_format = title_format;
+    }
+    if let Some(word_separators) = read_key(&document, "word_separators")? {
+        config.word_separators = word_separators;
+    }
+    if let Some(copy_command) = read_key(&document, "copy_command")? {
+        config.copy_command = Some(copy_command);
+    }
+    if let Some(start_zoomed) = read_key(&document, "start_zoomed")? {
+        config.start_zoomed = start_zoomed;
+    }
+    if let Some(split_before) = read_key(&document, "split_before")? {
+        config.split_before = split_before;
+    }
+
+    config.launchers = parse_launchers(&document);
+    config.mouse_bindings = parse_mouse_bindings(&document)?;
+    config.send_strings = parse_key_bindings(&document)?;
+    check_prefix_keys(&config.launchers, &config.send_strings)?;
+    if let Some(hooks) = find_node(&document, "hooks").and_then(|node| node.children()) {
+        config.hooks = parse_hooks(hooks)?;
+    }
+
+    if let Some(default_split_ratio) = parse_split_ratio(&document, config.default_split_ratio) {
+        config.default_split_ratio = default_split_ratio;
+    }
+
+    Ok(config)
+}
+
+/// A type that a config value can be read as.
+pub trait ConfigValue: Sized {
+    /// Describes the expected value in error messages.
+    const EXPECTED: &'static str;
+
+    fn from_kdl(value: &kdl::KdlValue) -> Option<Self>;
+}
+
+impl ConfigValue for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_kdl(value: &kdl::KdlValue) -> Option<Self> {
+        value.as_string().map(str::to_string)
+    }
+}
+
+impl ConfigValue for bool {
+    const EXPECTED: &'static str = "#true or #false";
+
+    fn from_kdl(value: &kdl::KdlValue) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+macro_rules! integer_config_value {
+    ($($t:ty),*) => {
+        $(impl ConfigValue for $t {
+            const EXPECTED: &'static str = concat!("an integer that fits in ", stringify!($t));
+
+            fn from_kdl(value: &kdl::KdlValue) -> Option<Self> {
+                <$t>::try_from(value.as_integer()?).ok()
+            }
+        })*
+    };
+}
+
+integer_config_value!(u16, u32, u64, usize);
+
+fn find_node<'a>(document: &'a kdl::KdlDocument, name: &str) -> Option<&'a kdl::KdlNode> {
+    document
         .nodes()
         .iter()
-        .find(|e| e.name().to_string() == "default_shell");
-    if let Some(shell_node) = shell_node {
-        let shell = shell_node.entries().first();
-        if let Some(shell) = shell {
-            let shell = shell.value().as_string();
-            if let Some(shell) = shell {
-                config.default_shell = shell.to_string();
+        .find(|e| e.name().to_string() == name)
+}
+
+fn convert<T: ConfigValue>(name: &str, value: &kdl::KdlValue) -> anyhow::Result<T> {
+    T::from_kdl(value)
+        .ok_or_else(|| anyhow::format_err!("`{}` expects {}, got {}", name, T::EXPECTED, value))
+}
+
+/// Reads the first value of a top-level key, `None` if the key is absent.
+pub fn read_key<T: ConfigValue>(
+    document: &kdl::KdlDocument,
+    name: &str,
+) -> anyhow::Result<Option<T>> {
+    let Some(node) = find_node(document, name) else {
+        return Ok(None);
+    };
+    let Some(entry) = node.entries().first() else {
+        return Ok(None);
+    };
+
+    convert(name, entry.value())
+        .map(Some)
+        .map_err(|error| node_error(node, error))
+}
+
+/// Reads all values of a top-level key, like `default_shell_args "-l" "-i"`.
+pub fn read_list<T: ConfigValue>(
+    document: &kdl::KdlDocument,
+    name: &str,
+) -> anyhow::Result<Option<Vec<T>>> {
+    let Some(node) = find_node(document, name) else {
+        return Ok(None);
+    };
+
+    node.entries()
+        .iter()
+        .map(|entry| convert(name, entry.value()))
+        .collect::<anyhow::Result<Vec<T>>>()
+        .map(Some)
+        .map_err(|error| node_error(node, error))
+}
+
+/// Reads a block of `NAME value` children, like `env { EDITOR "vim"; }`.
+pub fn read_map<T: ConfigValue>(
+    document: &kdl::KdlDocument,
+    name: &str,
+) -> anyhow::Result<Option<HashMap<String, T>>> {
+    let Some(node) = find_node(document, name) else {
+        return Ok(None);
+    };
+    let Some(children) = node.children() else {
+        return Ok(Some(HashMap::new()));
+    };
+
+    children
+        .nodes()
+        .iter()
+        .map(|child| {
+            let key = child.name().value().to_string();
+            let Some(entry) = child.entries().first() else {
+                let error = anyhow::format_err!("`{}.{}` has no value", name, key);
+                return Err(node_error(child, error));
+            };
+            let value = convert(&format!("{}.{}", name, key), entry.value())
+                .map_err(|error| node_error(child, error))?;
+
+            Ok((key, value))
+        })
+        .collect::<anyhow::Result<HashMap<String, T>>>()
+        .map(Some)
+}
+
+/// Reads a block like `mousebindings { middle "new_pane"; right "break_pane" area="title" }`,
+/// where each child is named after a button and holds the action to run.
+pub fn parse_mouse_bindings(document: &kdl::KdlDocument) -> anyhow::Result<Vec<MouseBinding>> {
+    let Some(bindings) = find_node(document, "mousebindings").and_then(|node| node.children())
+    else {
+        return Ok(Vec::new());
+    };
+
+    bindings
+        .nodes()
+        .iter()
+        .map(|node| parse_mouse_binding(node).map_err(|error| node_error(node, error)))
+        .collect()
+}
+
+fn parse_mouse_binding(node: &kdl::KdlNode) -> anyhow::Result<MouseBinding> {
+    let name = node.name().value();
+    let button = match name {
+        "left" => MouseButton::Left,
+        "middle" => MouseButton::Middle,
+        "right" => MouseButton::Right,
+        _ => {
+            return Err(anyhow::format_err!(
+                "`mousebindings` expects \"left\", \"middle\" or \"right\", got {:?}",
+                name
+            ))
+        }
+    };
+    let action = node
+        .entries()
+        .iter()
+        .find(|entry| entry.name().is_none())
+        .and_then(|entry| entry.value().as_string());
+    let action = action.and_then(Action::from_name).ok_or_else(|| {
+        anyhow::format_err!(
+            "`mousebindings.{}` expects an action name, got {:?}",
+            name,
+            action
+        )
+    })?;
+    let area = node
+        .entries()
+        .iter()
+        .find(|entry| entry.name().is_some_and(|name| name.value() == "area"))
+        .map(|entry| match entry.value().as_string() {
+            Some("title") => Ok(MouseArea::Title),
+            Some("content") => Ok(MouseArea::Content),
+            value => Err(anyhow::format_err!(
+                "`mousebindings.{}` area expects \"title\" or \"content\", got {:?}",
+                name,
+                value
+            )),
+        })
+        .transpose()?;
+
+    Ok(MouseBinding {
+        button,
+        area,
+        action,
+    })
+}
+
+/// Reads the children of the `hooks` block, each naming an event and holding
+/// the command to run on it.
+pub fn parse_hooks(hooks: &kdl::KdlDocument) -> anyhow::Result<Hooks> {
+    let mut parsed = Hooks::default();
+    for node in hooks.nodes() {
+        let name = node.name().value();
+        let command = node
+            .entries()
+            .iter()
+            .find(|entry| entry.name().is_none())
+            .and_then(|entry| entry.value().as_string())
+            .ok_or_else(|| {
+                node_error(
+                    node,
+                    anyhow::format_err!("`hooks.{}` expects a command", name),
+                )
+            })?
+            .to_string();
+        let hook = match name {
+            "pane_created" => &mut parsed.pane_created,
+            "pane_focused" => &mut parsed.pane_focused,
+            "pane_closed" => &mut parsed.pane_closed,
+            _ => {
+                let error = anyhow::format_err!(
+                "`hooks` expects \"pane_created\", \"pane_focused\" or \"pane_closed\", got {:?}",
+                name
+            );
+                return Err(node_error(node, error));
             }
         };
+        *hook = Some(command);
+    }
+
+    Ok(parsed)
+}
+
+/// Reads a block like `keybindings { t "send-string" "clear\n" }`, where each
+/// child is named after the key pressed after the prefix. `send-string` types
+/// its literal into the active pane as KDL reads it, so control characters are
+/// written with KDL's escapes, like `"\u{1b}[A"`.
+pub fn parse_key_bindings(document: &kdl::KdlDocument) -> anyhow::Result<Vec<SendString>> {
+    let Some(bindings) = find_node(document, "keybindings").and_then(|node| node.children()) else {
+        return Ok(Vec::new());
     };
 
-    Some(config)
+    bindings
+        .nodes()
+        .iter()
+        .map(|node| parse_key_binding(node).map_err(|error| node_error(node, error)))
+        .collect()
 }
 
-pub fn get_config() -> Config {
-    get_config_optional().unwrap_or(get_default_config())
+fn parse_key_binding(node: &kdl::KdlNode) -> anyhow::Result<SendString> {
+    let name = node.name().value();
+    let mut chars = name.chars();
+    let key = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => {
+            return Err(anyhow::format_err!(
+                "`keybindings` expects a single character key, got {:?}",
+                name
+            ))
+        }
+    };
+    let mut values = node
+        .entries()
+        .iter()
+        .filter(|entry| entry.name().is_none())
+        .map(|entry| entry.value().as_string());
+    match values.next().flatten() {
+        Some("send-string") => {}
+        action => {
+            return Err(anyhow::format_err!(
+                "`keybindings.{}` expects \"send-string\", got {:?}",
+                name,
+                action
+            ))
+        }
+    }
+    let literal = values
+        .next()
+        .flatten()
+        .ok_or_else(|| anyhow::format_err!("`keybindings.{}` expects the string to send", name))?;
+
+    Ok(SendString {
+        key,
+        bytes: literal.as_bytes().to_vec(),
+    })
+}
+
+/// Rejects launcher and `keybindings` keys that would shadow a built in prefix
+/// binding or each other, since only the first of them could ever be used.
+pub fn check_prefix_keys(
+    launchers: &[Launcher],
+    send_strings: &[SendString],
+) -> anyhow::Result<()> {
+    let launcher_keys = launchers
+        .iter()
+        .filter_map(|launcher| Some((format!("launchers.{}", launcher.name), launcher.key?)));
+    let send_string_keys = send_strings
+        .iter()
+        .map(|send_string| (format!("keybindings.{}", send_string.key), send_string.key));
+    let mut seen: Vec<(String, KeyCode)> = Vec::new();
+    for (name, key) in launcher_keys.chain(send_string_keys) {
+        if let Some(action) = builtin_prefix_action(key) {
+            return Err(anyhow::format_err!(
+                "`{}` uses the key {}, which is already bound to {:?}",
+                name,
+                key,
+                action
+            ));
+        }
+        if let Some((other, _)) = seen.iter().find(|(_, other_key)| *other_key == key) {
+            return Err(anyhow::format_err!(
+                "`{}` uses the key {}, which is already bound by `{}`",
+                name,
+                key,
+                other
+            ));
+        }
+        seen.push((name, key));
+    }
+
+    Ok(())
+}
+
+/// Reads a block like `launchers { lazygit "lazygit" key="g"; top "htop" }`,
+/// where each child is named after the launcher and holds its command.
+pub fn parse_launchers(document: &kdl::KdlDocument) -> Vec<Launcher> {
+    let Some(launchers) = document
+        .nodes()
+        .iter()
+        .find(|e| e.name().to_string() == "launchers")
+        .and_then(|node| node.children())
+    else {
+        return Vec::new();
+    };
+
+    launchers
+        .nodes()
+        .iter()
+        .filter_map(|node| {
+            let command = node
+                .entries()
+                .iter()
+                .find(|entry| entry.name().is_none())?
+                .value()
+                .as_string()?;
+            let key = node
+                .entries()
+                .iter()
+                .find(|entry| entry.name().is_some_and(|name| name.value() == "key"))
+                .and_then(|entry| entry.value().as_string())
+                .and_then(|key| {
+                    let mut chars = key.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Some(KeyCode::Char(c)),
+                        _ => None,
+                    }
+                });
+
+            let tint = node
+                .entries()
+                .iter()
+                .find(|entry| entry.name().is_some_and(|name| name.value() == "tint"))
+                .and_then(|entry| entry.value().as_string())
+                .and_then(parse_hex_color);
+
+            Some(Launcher {
+                name: node.name().value().to_string(),
+                command: command.to_string(),
+                key,
+                tint,
+            })
+        })
+        .collect()
+}
+
+/// Reads `default_split_ratio 0.3`, optionally overridden per direction with
+/// `horizontal=` and `vertical=` properties. Ratios outside of (0, 1) are ignored.
+pub fn parse_split_ratio(document: &kdl::KdlDocument, default: SplitRatio) -> Option<SplitRatio> {
+    let node = document
+        .nodes()
+        .iter()
+        .find(|e| e.name().to_string() == "default_split_ratio")?;
+    let is_valid = |ratio: &f64| *ratio > 0.0 && *ratio < 1.0;
+    let ratio_of = |name: Option<&str>| {
+        node.entries()
+            .iter()
+            .find(|entry| entry.name().map(|name| name.value()) == name)
+            .and_then(|entry| entry.value().as_float())
+            .filter(is_valid)
+    };
+    let mut ratio = match ratio_of(None) {
+        Some(ratio) => SplitRatio::new(ratio),
+        None => default,
+    };
+    if let Some(horizontal) = ratio_of(Some("horizontal")) {
+        ratio.horizontal = horizontal;
+    }
+    if let Some(vertical) = ratio_of(Some("vertical")) {
+        ratio.vertical = vertical;
+    }
+
+    Some(ratio)
 }
@@ -0,0 +1,317 @@
+use std::path::PathBuf;
+
+use renterm::color::Color;
+
+use crate::{
+    config::{env_var, parse_hex_color},
+    draw::{find_process_by_id, trigger_draw},
+    input::{clear_history, reject_read_only_pane, write_to_pane},
+    layout::layout_diagram,
+    state::StateContainer,
+};
+
+/// A command for scripting citymux, given as a line of text like
+/// `send-raw 2 \e[31m`, `clear-history`, `tint 2 #203040` or `print-layout`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ControlCommand {
+    /// Writes bytes to a pane's input as if they were typed.
+    SendRaw { span_id: usize, bytes: Vec<u8> },
+    /// Empties the scrollback of a pane, or of the active one.
+    ClearHistory { span_id: Option<usize> },
+    /// Sets or, with `None`, removes the background tint of a pane.
+    Tint {
+        span_id: usize,
+        color: Option<Color>,
+    },
+    /// Replies with the layout drawn as ASCII boxes, see `layout_diagram`.
+    PrintLayout,
+    /// Replies with a line per pane in layout order: its id, the PID of its
+    /// process, its size and its command.
+    ListPanes,
+}
+
+/// Decodes `\e`, `\xNN`, `\n`, `\r`, `\t` and `\\` escapes. Input prefixed
+/// with `hex:` is read as hex digit pairs instead, like `hex:1b 5b 41`.
+pub fn parse_escaped(input: &str) -> anyhow::Result<Vec<u8>> {
+    if let Some(hex) = input.strip_prefix("hex:") {
+        let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err(anyhow::format_err!("Odd number of hex digits in {:?}", hex));
+        }
+        return digits
+            .chunks(2)
+            .map(|pair| {
+                let pair: String = pair.iter().collect();
+                u8::from_str_radix(&pair, 16)
+                    .map_err(|_| anyhow::format_err!("Invalid hex byte {:?}", pair))
+            })
+            .collect();
+    }
+
+    let mut bytes = Vec::new();
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('e') => bytes.push(0x1b),
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let byte = (digits.len() == 2)
+                    .then(|| u8::from_str_radix(&digits, 16).ok())
+                    .flatten()
+                    .ok_or_else(|| anyhow::format_err!("Invalid escape \\x{}", digits))?;
+                bytes.push(byte);
+            }
+            Some(other) => return Err(anyhow::format_err!("Unknown escape \\{}", other)),
+            None => return Err(anyhow::format_err!("Trailing backslash in {:?}", input)),
+        }
+    }
+
+    Ok(bytes)
+}
+
+pub fn parse_control_command(line: &str) -> anyhow::Result<ControlCommand> {
+    let line = line.trim_start();
+    let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+    match name {
+        "send-raw" => {
+            let rest = rest.trim_start();
+            let (span_id, data) = rest.split_once(' ').unwrap_or((rest, ""));
+            let span_id = span_id.parse().map_err(|_| {
+                anyhow::format_err!("send-raw expects a pane id, got {:?}", span_id)
+            })?;
+
+            Ok(ControlCommand::SendRaw {
+                span_id,
+                bytes: parse_escaped(data)?,
+            })
+        }
+        "clear-history" => {
+            let span_id = match rest.trim() {
+                "" => None,
+                span_id => Some(span_id.parse().map_err(|_| {
+                    anyhow::format_err!("clear-history expects a pane id, got {:?}", span_id)
+                })?),
+            };
+
+            Ok(ControlCommand::ClearHistory { span_id })
+        }
+        "tint" => {
+            let (span_id, color) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+            let span_id = span_id
+                .parse()
+                .map_err(|_| anyhow::format_err!("tint expects a pane id, got {:?}", span_id))?;
+            let color = match color.trim() {
+                "none" => None,
+                color => Some(parse_hex_color(color).ok_or_else(|| {
+                    anyhow::format_err!(
+                        "tint expects a color like #203040 or none, got {:?}",
+                        color
+                    )
+                })?),
+            };
+
+            Ok(ControlCommand::Tint { span_id, color })
+        }
+        "print-layout" => Ok(ControlCommand::PrintLayout),
+        "list-panes" => Ok(ControlCommand::ListPanes),
+        _ => Err(anyhow::format_err!("Unknown command: {:?}", name)),
+    }
+}
+
+/// Runs a command, returning the output of commands that print something.
+pub async fn run_control_command(
+    state_container: &StateContainer,
+    command: ControlCommand,
+) -> anyhow::Result<Option<String>> {
+    match command {
+        ControlCommand::SendRaw { span_id, bytes } => {
+            if reject_read_only_pane(state_container, span_id).await {
+                return Err(anyhow::format_err!("Pane {} is read-only", span_id));
+            }
+            write_to_pane(state_container, span_id, &bytes, true).await?;
+
+            Ok(None)
+        }
+        ControlCommand::ClearHistory { span_id } => {
+            let span_id = span_id.unwrap_or_else(|| {
+                state_container
+                    .active_id
+                    .load(std::sync::atomic::Ordering::Relaxed)
+            });
+            clear_history(state_container, span_id).await?;
+
+            Ok(None)
+        }
+        ControlCommand::Tint { span_id, color } => {
+            let Some(process) = find_process_by_id(state_container.clone(), span_id).await else {
+                return Err(anyhow::format_err!("No process in pane: {}", span_id));
+            };
+            process.write().await.tint = color;
+            trigger_draw(state_container).await;
+
+            Ok(None)
+        }
+        ControlCommand::PrintLayout => {
+            let size = state_container.layout_size().await;
+            let root = state_container.root_node.read().await;
+            let Some(root) = root.as_ref() else {
+                return Err(anyhow::format_err!("No panes"));
+            };
+
+            Ok(Some(layout_diagram(root, size)))
+        }
+        ControlCommand::ListPanes => {
+            let mut output = String::new();
+            for process in state_container.ordered_processes().await {
+                let process = process.read().await;
+                let size = state_container
+                    .get_span_dimensions(process.span_id)
+                    .await
+                    .map(|rect| rect.size())
+                    .unwrap_or_default();
+                output.push_str(&format!(
+                    "{} {} {}x{} {}\n",
+                    process.span_id,
+                    process.pid,
+                    size.x,
+                    size.y,
+                    process.command.command_line()
+                ));
+            }
+
+            Ok(Some(output))
+        }
+    }
+}
+
+/// Runs a line received from a control client and returns the reply to send
+/// back: `ok`, preceded by the command's output if it has any, or `error: `
+/// followed by the reason.
+pub async fn handle_control_line(state_container: &StateContainer, line: &str) -> String {
+    let result = match parse_control_command(line) {
+        Ok(command) => run_control_command(state_container, command).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(None) => "ok".to_string(),
+        Ok(Some(output)) => format!("{}ok", output),
+        Err(err) => format!("error: {}", err),
+    }
+}
+
+/// Where this instance listens for control commands. Programs in panes find
+/// it in the `CITYMUX_SOCKET` environment variable.
+pub fn control_socket_path() -> PathBuf {
+    control_socket_dir(env_var).join(format!("{}.sock", std::process::id()))
+}
+
+/// The directory the control socket is created in, only accessible by this
+/// user: `citymux` in `XDG_RUNTIME_DIR`, read through `env`, or a directory
+/// named after the user id in the temporary directory.
+#[cfg(unix)]
+pub fn control_socket_dir(env: fn(&str) -> Option<String>) -> PathBuf {
+    match env("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("citymux"),
+        None => std::env::temp_dir().join(format!("citymux-{}", nix::unistd::getuid())),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn control_socket_dir(_env: fn(&str) -> Option<String>) -> PathBuf {
+    std::env::temp_dir()
+}
+
+/// Creates `dir` for the control socket with access for this user only. An
+/// existing directory is used only when it belongs to this user and nobody
+/// else can get into it, so other users can't swap the socket out.
+#[cfg(unix)]
+pub fn create_socket_dir(dir: &std::path::Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err.into()),
+    }
+    let metadata = std::fs::symlink_metadata(dir)?;
+    let private = metadata.is_dir()
+        && metadata.uid() == nix::unistd::getuid().as_raw()
+        && metadata.mode() & 0o077 == 0;
+    if !private {
+        return Err(anyhow::format_err!(
+            "{:?} is not a directory that only this user can access",
+            dir
+        ));
+    }
+
+    Ok(())
+}
+
+/// Binds the control socket, giving up with a message when that fails, as
+/// retrying wouldn't help.
+#[cfg(unix)]
+fn bind_control_socket(path: &std::path::Path) -> anyhow::Result<tokio::net::UnixListener> {
+    if let Some(dir) = path.parent() {
+        create_socket_dir(dir)?;
+    }
+    let _ignored = std::fs::remove_file(path);
+
+    Ok(tokio::net::UnixListener::bind(path)?)
+}
+
+#[cfg(unix)]
+pub async fn control_loop(state_container: StateContainer) -> anyhow::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let path = control_socket_path();
+    let listener = match bind_control_socket(&path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::error!("Could not listen on {:?}: {:?}", path, err);
+            state_container
+                .flash("Control socket unavailable, see the log")
+                .await;
+            // Returning would only have `handle_loop` try again right away.
+            return std::future::pending().await;
+        }
+    };
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                tracing::warn!("Could not accept a control connection: {:?}", err);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let state_container = state_container.clone();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let reply = handle_control_line(&state_container, &line).await;
+                if writer
+                    .write_all(format!("{}\n", reply).as_bytes())
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn control_loop(_state_container: StateContainer) -> anyhow::Result<()> {
+    std::future::pending().await
+}
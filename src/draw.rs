@@ -1,41 +1,47 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use renterm::{
-    cell::Cell, color::Color, rect::Rect, style::Style, surface::Surface, text::DrawableStr,
+    canvas::Canvas,
+    cell::Cell,
+    color::{Color, ColorDepth},
+    rect::Rect,
+    style::Style,
+    surface::Surface,
+    text::{text_width, DrawableStr},
     vector::Vector2,
 };
 use tokio::{
     io::AsyncWriteExt,
-    sync::RwLock,
-    time::MissedTickBehavior,
+    sync::{Mutex, Notify, RwLock},
+    time::{Instant, MissedTickBehavior},
 };
 
 use crate::{
-    escape_codes::{MoveCursor, ResetStyle, SetCursorVisibility},
-    layout::get_span_dimensions,
+    config::{ActiveIndicator, RenderStrategy},
+    escape_codes::{MoveCursor, ResetStyle, SetCursorStyle, SetCursorVisibility, SetHyperlink},
+    layout::{get_pane_dimensions, pane_order},
+    picker::{draw_pane_picker, filter_entries, pane_entries},
     size::update_size,
     span::{Node, NodeData},
-    state::{Process, StateContainer},
+    state::{layout_size, Process, StateContainer},
+    term::CursorState,
 };
 
 pub async fn find_process_by_id(
     state_container: StateContainer,
     id: usize,
 ) -> Option<Arc<RwLock<Process>>> {
-    let processes = state_container.state().processes.read().await.clone();
-    for process in processes {
-        let process_inner = process.read().await;
-        if process_inner.span_id == id {
-            return Some(process.clone());
-        }
-    }
-
-    None
+    state_container
+        .find_process(|process| process.span_id == id)
+        .await
 }
 
 pub async fn draw_node_content(
     state_container: StateContainer,
-    node: &Node,
+    _node: &Node,
     process: Arc<RwLock<Process>>,
     output_canvas: &mut impl Surface,
 ) -> anyhow::Result<()> {
@@ -44,19 +50,125 @@ pub async fn draw_node_content(
     let mut terminal = process.terminal_info.lock().await;
     terminal.set_size(size.clone());
     {
-        let mut terminal = process.terminal.lock().await;
-        if terminal.size() != size {
-            terminal.set_size(size)?;
+        // Give the PTY the clamped size the screen actually has, so that the
+        // two agree and the PTY is only resized when that size changes.
+        let size = terminal.size();
+        let mut pty = process.terminal.lock().await;
+        if pty.size() != size {
+            pty.set_size(size)?;
         }
     }
     terminal.draw(output_canvas);
+    drop(terminal);
+    if !process.has_output.load(Ordering::Relaxed) {
+        draw_starting_placeholder(output_canvas, size);
+    }
+    if let Some(tint) = &process.tint {
+        apply_tint(output_canvas, tint);
+    }
 
     Ok(())
 }
 
+/// Shown in a pane until its program writes something.
+fn draw_starting_placeholder(canvas: &mut impl Surface, size: Vector2) {
+    let placeholder = "starting…";
+    let width = (text_width(placeholder) as i32).min(size.x);
+    let position = Vector2::new((size.x - width) / 2, size.y / 2);
+    let placeholder = DrawableStr::truncated(
+        placeholder,
+        Style::default().with_foreground_color(Color::new_one_byte(8)),
+        true,
+    );
+    canvas.draw_in(&placeholder, Rect::new(position, Vector2::new(width, 1)));
+}
+
+/// How far backgrounds that programs set are moved towards a pane's tint.
+const TINT_AMOUNT: f64 = 0.2;
+
+/// Tints the backgrounds of a pane's cells. Cells with the default
+/// background take the tint as it is.
+pub fn apply_tint(canvas: &mut impl Surface, tint: &Color) {
+    let size = canvas.size();
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let position = Vector2::new(x, y);
+            let mut cell = canvas.get_cell(position.clone());
+            let background = cell.style.background_color().mix(tint, TINT_AMOUNT);
+            cell.style = cell.style.with_background_color(background);
+            canvas.set_cell(position, cell);
+        }
+    }
+}
+
+/// What can be shown in a pane title.
+pub struct TitleFields {
+    pub id: usize,
+    pub title: String,
+    pub command: String,
+    pub pid: u32,
+    /// Position of the pane on screen, counting from 1.
+    pub index: usize,
+}
+
+/// Fills in the `{id}`, `{title}`, `{command}`, `{pid}` and `{index}`
+/// placeholders of a title format. Anything else is kept as written.
+pub fn format_title(format: &str, fields: &TitleFields) -> String {
+    let mut title = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        title.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let value = match &rest[1..end] {
+            "id" => fields.id.to_string(),
+            "title" => fields.title.clone(),
+            "command" => fields.command.clone(),
+            "pid" => fields.pid.to_string(),
+            "index" => fields.index.to_string(),
+            _ => rest[..=end].to_string(),
+        };
+        title.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    title.push_str(rest);
+
+    title
+}
+
+/// Titles are cut to this many columns before drawing, no border is wider.
+const MAX_TITLE_WIDTH: usize = 512;
+
+/// Makes a title set by a program safe to draw: control characters, which
+/// would reach the output as they are, are replaced with `?`, and overly long
+/// titles are cut short. Zero width characters count as one column here, so
+/// that a title made of them is cut short as well.
+pub fn sanitize_title(title: &str) -> String {
+    let mut sanitized = String::new();
+    let mut width = 0;
+    for c in title.chars() {
+        let c = if c.is_control() { '?' } else { c };
+        width += text_width(c.encode_utf8(&mut [0; 4])).max(1);
+        if width > MAX_TITLE_WIDTH {
+            break;
+        }
+        sanitized.push(c);
+    }
+
+    sanitized
+}
+
+/// The smallest pane that fits a border around at least one cell.
+const MIN_PANE_SIZE: Vector2 = Vector2 { x: 3, y: 3 };
+
+/// Draws `node` and the panes below it. `order` is the pane order of the
+/// whole layout, worked out once per frame, which pane numbers come from.
 pub async fn draw_node(
     state_container: StateContainer,
     root: &Node,
+    order: &[usize],
     node: &Node,
     canvas: &mut impl Surface,
 ) -> anyhow::Result<()> {
@@ -65,16 +177,21 @@ pub async fn draw_node(
             for child in &span.children {
                 let child_node = &child.node;
 
-                let future = draw_node(state_container.clone(), root, child_node, canvas);
+                let future = draw_node(state_container.clone(), root, order, child_node, canvas);
                 Box::pin(future).await?;
             }
         }
         NodeData::Void => {
-            let dimensions =
-                get_span_dimensions(root, node.id, Rect::new(Vector2::new(0, 0), canvas.size()));
+            let gap = state_container.config.read().await.pane_gap;
+            let dimensions = get_pane_dimensions(root, node.id, canvas.size(), gap);
             let Some(dimensions) = dimensions else {
                 return Err(anyhow::format_err!("Could not find dimensions of span"));
             };
+            if dimensions.size().x < MIN_PANE_SIZE.x || dimensions.size().y < MIN_PANE_SIZE.y {
+                // Too small for the border around a screen, which happens
+                // briefly while resizing with many panes.
+                return Ok(());
+            }
             let parent_canvas = canvas;
             let mut canvas = parent_canvas.to_sub_view(dimensions);
 
@@ -93,8 +210,9 @@ pub async fn draw_node(
             } else {
                 inactive_border_style
             };
-            let vertical_bar = Cell::new_styled("│", border_style.clone());
-            let horizontal_bar = Cell::new_styled("─", border_style.clone());
+            let glyphs = { state_container.config.read().await.border_style.glyphs() };
+            let vertical_bar = Cell::new_styled(glyphs.vertical, border_style.clone());
+            let horizontal_bar = Cell::new_styled(glyphs.horizontal, border_style.clone());
             for y in 0..canvas.size().y {
                 let left = Vector2::new(0, y);
                 let right = Vector2::new(canvas.size().x - 1, y);
@@ -107,13 +225,13 @@ pub async fn draw_node(
                 canvas.set_cell(top, horizontal_bar.clone());
                 canvas.set_cell(bottom, horizontal_bar.clone());
             }
-            let top_left = Cell::new_styled("┌", border_style.clone());
+            let top_left = Cell::new_styled(glyphs.top_left, border_style.clone());
             canvas.set_cell(Vector2::new(0, 0), top_left);
-            let top_right = Cell::new_styled("┐", border_style.clone());
+            let top_right = Cell::new_styled(glyphs.top_right, border_style.clone());
             canvas.set_cell(Vector2::new(canvas.size().x - 1, 0), top_right);
-            let bottom_left = Cell::new_styled("└", border_style.clone());
+            let bottom_left = Cell::new_styled(glyphs.bottom_left, border_style.clone());
             canvas.set_cell(Vector2::new(0, canvas.size().y - 1), bottom_left);
-            let bottom_right = Cell::new_styled("┘", border_style.clone());
+            let bottom_right = Cell::new_styled(glyphs.bottom_right, border_style.clone());
             canvas.set_cell(
                 Vector2::new(canvas.size().x - 1, canvas.size().y - 1),
                 bottom_right,
@@ -123,14 +241,41 @@ pub async fn draw_node(
             if let Some(process) = process {
                 {
                     let process = process.read().await;
-                    let terminal_info = process.terminal_info.lock().await;
-                    let title = format!("[{}]", terminal_info.title());
-                    let title = DrawableStr::new(
-                        &title,
-                        Style::default()
-                            .with_background_color(highlight_color.clone())
-                            .with_foreground_color(Color::new_one_byte(0)),
-                    );
+                    let title = process.terminal_info.lock().await.title();
+                    let index = order
+                        .iter()
+                        .position(|id| *id == node.id)
+                        .unwrap_or_default();
+                    let fields = TitleFields {
+                        id: node.id,
+                        title,
+                        command: process.command.command_line(),
+                        pid: process.pid,
+                        index: index + 1,
+                    };
+                    let title_format = { state_container.config.read().await.title_format.clone() };
+                    let title = sanitize_title(&format_title(&title_format, &fields));
+                    let title = if process.read_only {
+                        format!("{} (read-only)", title)
+                    } else {
+                        title
+                    };
+                    let mut title_style = Style::default()
+                        .with_background_color(highlight_color.clone())
+                        .with_foreground_color(Color::new_one_byte(0));
+                    let active_indicator =
+                        { state_container.config.read().await.active_indicator.clone() };
+                    let title = match active_indicator {
+                        ActiveIndicator::Marker(marker) if is_active => {
+                            format!("{} {}", marker, title)
+                        }
+                        ActiveIndicator::Reverse if is_active => {
+                            title_style = inverted_style(&title_style);
+                            title
+                        }
+                        _ => title,
+                    };
+                    let title = DrawableStr::truncated(&title, title_style, true);
                     canvas.draw_in(
                         &title,
                         Rect::new(Vector2::new(1, 0), Vector2::new(canvas.size().x - 2, 1)),
@@ -150,168 +295,535 @@ pub async fn draw_node(
     Ok(())
 }
 
-async fn draw_inner(state_container: StateContainer) -> anyhow::Result<()> {
-    let stdout = state_container.state().stdout.clone();
-    let mut stdout = stdout.lock().await;
-
+/// Returns the screen position of the active pane's cursor, if it is visible.
+pub async fn active_cursor_position(
+    state_container: StateContainer,
+    size: Vector2,
+) -> Option<Vector2> {
+    let active_id = state_container
+        .state()
+        .active_id
+        .load(std::sync::atomic::Ordering::Relaxed);
+    let process = find_process_by_id(state_container.clone(), active_id).await?;
+    let process = process.read().await;
+    let terminal = process.terminal_info.lock().await;
+    if !terminal.is_cursor_visible() {
+        return None;
+    }
     let state = state_container.state();
+    let gap = state.config.read().await.pane_gap;
+    let root = state.root_node.read().await;
+    let span = get_pane_dimensions(root.as_ref()?, process.span_id, size, gap)?;
 
-    let size: Vector2 = state.size.read().await.to_owned();
-    let last_canvas = state.get_last_canvas();
-    let last_canvas = last_canvas.lock().await;
-    let new_canvas = state.get_current_canvas();
-    let mut new_canvas = new_canvas.lock().await;
-    new_canvas.set_size(size.clone());
+    Some(span.position() + terminal.cursor_position() + Vector2::new(1, 1))
+}
+
+/// Screen positions of the visible cursors of the panes other than the active one.
+/// Panes busy with their output are skipped rather than waited for, their
+/// cursor is left out of this frame.
+pub async fn inactive_cursor_positions(
+    state_container: &StateContainer,
+    size: Vector2,
+) -> Vec<Vector2> {
+    let active_id = state_container
+        .active_id
+        .load(std::sync::atomic::Ordering::Relaxed);
+    let gap = state_container.config.read().await.pane_gap;
+    let root = state_container.root_node.read().await.clone();
+    let Some(root) = root else {
+        return Vec::new();
+    };
+    let mut positions = Vec::new();
+    for span_id in root.leaf_ids() {
+        if span_id == active_id {
+            continue;
+        }
+        let Some(process) = find_process_by_id(state_container.clone(), span_id).await else {
+            continue;
+        };
+        let Some(span) = get_pane_dimensions(&root, span_id, size.clone(), gap) else {
+            continue;
+        };
+        let process = process.read().await;
+        let Ok(terminal) = process.terminal_info.try_lock() else {
+            continue;
+        };
+        if terminal.is_cursor_visible() {
+            positions.push(span.position() + terminal.cursor_position() + Vector2::new(1, 1));
+        }
+    }
+
+    positions
+}
+
+/// Returns the cursor style requested by the active pane.
+pub async fn active_cursor_state(state_container: StateContainer) -> CursorState {
+    let Some(terminal_info) = state_container.state().active_terminal_info().await else {
+        return CursorState::default();
+    };
+    let terminal_info = terminal_info.lock().await;
+
+    terminal_info.cursor_state()
+}
+
+/// Swaps the colors of a style, substituting concrete colors for the defaults
+/// so that the result is visible on any background. Reverse video cells are
+/// shown the right way round instead.
+pub fn inverted_style(style: &Style) -> Style {
+    if style.is_inverse() {
+        return style.with_inverse(false);
+    }
+    let foreground = match style.background_color() {
+        color if color == Color::default() => Color::new_one_byte(0),
+        color => color,
+    };
+    let background = match style.foreground_color() {
+        color if color == Color::default() => Color::new_one_byte(7),
+        color => color,
+    };
+    style
+        .with_foreground_color(foreground)
+        .with_background_color(background)
+}
+
+/// Overlays a message centered on the bottom row of the canvas.
+fn draw_message(canvas: &mut Canvas, message: &str) {
+    let size = canvas.size();
+    let message = format!(" {} ", message);
+    let width = (text_width(&message) as i32).min(size.x);
+    let position = Vector2::new((size.x - width) / 2, size.y - 1);
+    let message = DrawableStr::new(
+        &message,
+        Style::default()
+            .with_background_color(Color::new_one_byte(8 + 6))
+            .with_foreground_color(Color::new_one_byte(0)),
+    );
+    canvas.draw_in(&message, Rect::new(position, Vector2::new(width, 1)));
+}
+
+/// Whether the layout has at least one pane with a process running in it.
+async fn has_live_panes(state_container: &StateContainer) -> bool {
+    let leaf_ids = {
+        let root = state_container.root_node.read().await;
+        match root.as_ref() {
+            Some(root) => root.leaf_ids(),
+            None => return false,
+        }
+    };
+    for id in leaf_ids {
+        if find_process_by_id(state_container.clone(), id)
+            .await
+            .is_some()
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Shown instead of the layout while there are no panes.
+fn draw_splash(canvas: &mut Canvas) {
+    let size = canvas.size();
+    let hint = "No panes, press prefix+n to open one";
+    let width = (text_width(hint) as i32).min(size.x);
+    let position = Vector2::new((size.x - width) / 2, size.y / 2);
+    let hint = DrawableStr::truncated(
+        hint,
+        Style::default().with_foreground_color(Color::new_one_byte(8)),
+        true,
+    );
+    canvas.draw_in(&hint, Rect::new(position, Vector2::new(width, 1)));
+}
+
+/// Fills the bottom row with the status bar, with `right` aligned to its end.
+fn draw_status_bar(canvas: &mut Canvas, right: Option<&str>) {
+    let size = canvas.size();
+    let style = Style::default()
+        .with_background_color(Color::new_one_byte(8 + 6))
+        .with_foreground_color(Color::new_one_byte(0));
+    let y = size.y - 1;
+    for x in 0..size.x {
+        canvas.set_cell(Vector2::new(x, y), Cell::new_styled(" ", style.clone()));
+    }
+    if let Some(right) = right {
+        let right = format!("{} ", right);
+        let width = (text_width(&right) as i32).min(size.x);
+        let right = DrawableStr::new(&right, style);
+        canvas.draw_in(
+            &right,
+            Rect::new(Vector2::new(size.x - width, y), Vector2::new(width, 1)),
+        );
+    }
+}
+
+/// Labels every pane with its id and dimensions, and marks the corners of the
+/// sub-view its content is drawn into.
+fn draw_layout_debug(canvas: &mut Canvas, root: &Node, size: Vector2, gap: u16) {
+    let style = Style::default()
+        .with_background_color(Color::new_one_byte(3))
+        .with_foreground_color(Color::new_one_byte(0));
+    let corner = Cell::new_styled("+", style.clone());
+    for id in pane_order(root, size.clone(), gap) {
+        let Some(rect) = get_pane_dimensions(root, id, size.clone(), gap) else {
+            continue;
+        };
+        let position = rect.position();
+        let pane_size = rect.size();
+        let label = format!(
+            "#{} {},{} {}x{}",
+            id, position.x, position.y, pane_size.x, pane_size.y
+        );
+        let label = DrawableStr::new(&label, style.clone());
+        let label_position = Vector2::new(position.x + 1, position.y + pane_size.y - 1);
+        canvas.draw_in(
+            &label,
+            Rect::new(label_position, Vector2::new(pane_size.x - 2, 1)),
+        );
+
+        let content_start = position.clone() + Vector2::new(1, 1);
+        let content_end = position + pane_size - Vector2::new(2, 2);
+        for x in [content_start.x, content_end.x] {
+            for y in [content_start.y, content_end.y] {
+                canvas.set_cell(Vector2::new(x, y), corner.clone());
+            }
+        }
+    }
+}
+
+/// Replaces colors the outer terminal can't show with the closest ones it can.
+fn downsample_canvas(canvas: &mut Canvas, depth: ColorDepth) {
+    let size = canvas.size();
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let position = Vector2::new(x, y);
+            let mut cell = canvas.get_cell(position.clone());
+            let style = cell.style.downsample(depth);
+            if style != cell.style {
+                cell.style = style;
+                canvas.set_cell(position, cell);
+            }
+        }
+    }
+}
+
+/// A composed screen, see `StateContainer::compose_frame`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub canvas: Canvas,
+    /// Position and style of the active pane's cursor, `None` while it's hidden.
+    pub cursor: Option<(Vector2, CursorState)>,
+}
 
+/// Draws the current layout into `canvas`, resizing it to `size`. The
+/// canvas is cleared first, so that one can be reused from frame to frame.
+pub async fn compose(
+    state_container: StateContainer,
+    canvas: &mut Canvas,
+    size: Vector2,
+) -> anyhow::Result<()> {
+    let state = state_container.state();
+    let max_canvas_cells = state.config.read().await.max_canvas_cells;
+    canvas.set_max_cells(max_canvas_cells);
+    canvas.set_size(size);
+    canvas.clear();
+    let size = canvas.size();
+
+    let gap = state.config.read().await.pane_gap;
+    let status_bar_height = state.status_bar_height().await;
+    let layout_size = layout_size(size.clone(), status_bar_height);
     {
-        let state = state_container.state();
         let root = state.root_node.read().await;
         let root = root.as_ref();
         if let Some(root) = root {
-            let mut view = new_canvas.to_view();
-            let future = draw_node(state_container.clone(), root, root, &mut view);
+            let order = pane_order(root, layout_size.clone(), gap);
+            let mut view = canvas.to_sub_view(Rect::new(Vector2::new(0, 0), layout_size.clone()));
+            let future = draw_node(state_container.clone(), root, &order, root, &mut view);
             Box::pin(future).await?;
         }
     }
+    if !has_live_panes(&state_container).await {
+        canvas.clear();
+        draw_splash(canvas);
+    }
+    if status_bar_height > 0 {
+        let status_right = state.status_right.read().await;
+        draw_status_bar(canvas, status_right.as_deref());
+    }
 
-    let mut to_write: Vec<u8> = Vec::new();
-    to_write.extend(Into::<&[u8]>::into(ResetStyle::default()));
-    to_write.extend(Into::<&[u8]>::into(SetCursorVisibility::new(false)));
-    {
-        let mut last_style = Style::default();
+    let message = match state.paste_prompt().await {
+        Some(prompt) => Some(prompt),
+        None => match state.quit_prompt().await {
+            Some(prompt) => Some(prompt),
+            None => match state.current_message().await {
+                Some(message) => Some(message),
+                None => state.mode_prompt().await,
+            },
+        },
+    };
+    let debug_layout = state
+        .debug_layout
+        .load(std::sync::atomic::Ordering::Relaxed);
+    if debug_layout {
+        let root = state.root_node.read().await;
+        if let Some(root) = root.as_ref() {
+            draw_layout_debug(canvas, root, layout_size.clone(), gap);
+        }
+    }
 
-        if last_canvas.ne(&new_canvas) {
-            for y in 0..new_canvas.size().y {
-                to_write.extend(&Into::<Vec<u8>>::into(MoveCursor::new(y, 0)));
-                for x in 0..new_canvas.size().x {
-                    let cell = new_canvas.get_cell((x, y).into());
+    let show_inactive_cursors = { state.config.read().await.show_inactive_cursors };
+    if show_inactive_cursors {
+        for position in inactive_cursor_positions(&state_container, layout_size.clone()).await {
+            let mut cell = canvas.get_cell(position.clone());
+            cell.style = inverted_style(&cell.style).with_dim(true);
+            canvas.set_cell(position, cell);
+        }
+    }
 
-                    to_write.extend(format!("\x1b[{};{}H", y + 1, x + 1).as_bytes());
+    let pane_picker = { state.pane_picker.lock().await.clone() };
+    if let Some(pane_picker) = pane_picker {
+        let entries = filter_entries(&pane_picker, pane_entries(&state_container).await);
+        draw_pane_picker(canvas, &pane_picker, &entries);
+    }
 
-                    if cell.style != last_style {
-                        to_write.extend(Into::<&[u8]>::into(ResetStyle::default()));
-                        to_write.extend(&Into::<Vec<u8>>::into(cell.style.clone()));
-                        last_style = cell.style.clone();
-                    }
+    if let Some(message) = message {
+        draw_message(canvas, &message);
+    }
 
-                    to_write.extend(cell.value.to_string().as_bytes());
-                }
-                to_write.extend("\r".as_bytes());
-            }
-            to_write.extend(Into::<&[u8]>::into(ResetStyle::default()));
-            state.swap_canvas();
+    let color_depth = { state.config.read().await.color_depth };
+    if color_depth != ColorDepth::TrueColor {
+        downsample_canvas(canvas, color_depth);
+    }
+
+    let software_cursor = { state.config.read().await.software_cursor };
+    if software_cursor {
+        if let Some(position) = active_cursor_position(state_container.clone(), layout_size).await {
+            let mut cell = canvas.get_cell(position.clone());
+            cell.style = inverted_style(&cell.style);
+            canvas.set_cell(position, cell);
         }
     }
 
-    let mut cursor_position = Vector2::new(0, 0);
-    let active_id = state_container
-        .state()
-        .active_id
-        .load(std::sync::atomic::Ordering::Relaxed);
-    let active_process: Option<Arc<RwLock<Process>>> =
-        find_process_by_id(state_container.clone(), active_id).await;
-    if let Some(ref process) = active_process {
-        let process = process.read().await;
-        let terminal = process.terminal_info.lock().await;
-        cursor_position = terminal.cursor_position();
+    Ok(())
+}
+
+async fn draw_inner(state_container: StateContainer) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let stdout = state_container.state().stdout.clone();
+    let mut stdout = stdout.lock().await;
+
+    let state = state_container.state();
+
+    let last_canvas = state.get_last_canvas();
+    let last_canvas = last_canvas.lock().await;
+    let new_canvas = state.get_current_canvas();
+    let mut new_canvas = new_canvas.lock().await;
+    let size = state.size.read().await.to_owned();
+    let cursor = state_container.compose_into(&mut new_canvas, size).await?;
+
+    let (render_strategy, software_cursor) = {
+        let config = state.config.read().await;
+        (config.render_strategy, config.software_cursor)
+    };
+    let last_canvas = match render_strategy {
+        RenderStrategy::DoubleBuffer => Some(&*last_canvas),
+        RenderStrategy::Direct => None,
+    };
+
+    let mut to_write: Vec<u8> = Vec::new();
+    to_write.extend(Into::<&[u8]>::into(ResetStyle::default()));
+    to_write.extend(Into::<&[u8]>::into(SetCursorVisibility::new(false)));
+    let changed_cells = encode_canvas(last_canvas, &new_canvas, &mut to_write);
+    if changed_cells > 0 {
+        state.swap_canvas();
     }
 
-    {
-        if let Some(ref process) = active_process {
-            let process = process.read().await;
-            let terminal = process.terminal_info.lock().await;
-            if terminal.is_cursor_visible() {
-                let state = state_container.state();
-                let root = state.root_node.read().await;
-                let root = root.as_ref();
-                if let Some(root) = root {
-                    let span = get_span_dimensions(
-                        root,
-                        process.span_id,
-                        Rect::new(Vector2::new(0, 0), size.clone()),
-                    );
-                    if let Some(span) = span {
-                        to_write.extend(&Into::<Vec<u8>>::into(MoveCursor::from(
-                            span.position() + cursor_position + Vector2::new(1, 1),
-                        )));
-                        to_write.extend(Into::<&[u8]>::into(SetCursorVisibility::new(true)));
-                    }
-                }
-            }
+    if !software_cursor {
+        if let Some((position, cursor_state)) = cursor {
+            to_write.extend(&Into::<Vec<u8>>::into(MoveCursor::from(position)));
+            to_write.extend(&Into::<Vec<u8>>::into(SetCursorStyle::from(cursor_state)));
+            to_write.extend(Into::<&[u8]>::into(SetCursorVisibility::new(true)));
         }
     }
     stdout.write(&to_write).await?;
     stdout.flush().await?;
 
+    if state.args.debug_metrics {
+        let duration = started.elapsed();
+        let mut metrics = state.render_metrics.lock().await;
+        metrics.record(duration, to_write.len(), changed_cells);
+        tracing::trace!(
+            "Frame took {:?}, wrote {} bytes, {} cells changed (average {:?}, {:.0} bytes, {:.0} cells)",
+            duration,
+            to_write.len(),
+            changed_cells,
+            metrics.average_duration(),
+            metrics.average_bytes(),
+            metrics.average_changed_cells()
+        );
+    }
+
     Ok(())
 }
 
+/// Appends the escape sequences needed to turn `last_canvas` into `new_canvas`
+/// to `output`, returning the number of cells that were emitted.
+///
+/// Cells are compared one by one, so unchanged cells are skipped without a
+/// separate whole-canvas comparison. When there is no previous frame, or its
+/// size differs, every cell is emitted. Runs of cells sharing a hyperlink are
+/// wrapped in OSC 8 sequences.
+pub fn encode_canvas(
+    last_canvas: Option<&Canvas>,
+    new_canvas: &Canvas,
+    output: &mut Vec<u8>,
+) -> usize {
+    let size = new_canvas.size();
+    let last_canvas = last_canvas.filter(|last_canvas| last_canvas.size() == size);
+
+    let mut changed_cells = 0;
+    let mut last_style: Option<Style> = None;
+    let mut next_position: Option<Vector2> = None;
+    let mut open_hyperlink: Option<String> = None;
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let position = Vector2::new(x, y);
+            let cell = new_canvas.get_cell(position.clone());
+            if let Some(last_canvas) = last_canvas {
+                if last_canvas.get_cell(position.clone()) == cell {
+                    continue;
+                }
+            }
+
+            if next_position.as_ref() != Some(&position) {
+                output.extend(&Into::<Vec<u8>>::into(MoveCursor::from(position.clone())));
+            }
+            if last_style.as_ref() != Some(&cell.style) {
+                output.extend(Into::<&[u8]>::into(ResetStyle::default()));
+                output.extend(&Into::<Vec<u8>>::into(cell.style.clone()));
+                last_style = Some(cell.style.clone());
+            }
+            if open_hyperlink != cell.hyperlink {
+                output.extend(&Into::<Vec<u8>>::into(SetHyperlink::new(
+                    cell.hyperlink.clone(),
+                )));
+                open_hyperlink = cell.hyperlink.clone();
+            }
+            output.extend(cell.value.to_string().as_bytes());
+
+            next_position = Some(Vector2::new(x + 1, y));
+            changed_cells += 1;
+        }
+    }
+    if open_hyperlink.is_some() {
+        output.extend(&Into::<Vec<u8>>::into(SetHyperlink::new(None)));
+    }
+    if changed_cells > 0 {
+        output.extend(Into::<&[u8]>::into(ResetStyle::default()));
+    }
+
+    changed_cells
+}
+
 pub async fn draw(state_container: StateContainer) -> anyhow::Result<()> {
-    let _ = state_container.state().draw_lock.lock().await;
+    let draw_lock = state_container.draw_lock.clone();
+    let _draw_lock = draw_lock.lock().await;
     draw_inner(state_container).await
 }
 
+/// A pending draw request. Requests made before the draw loop gets to them
+/// are merged, and a request made while a frame is being drawn causes one
+/// more frame, so the latest state always ends up on screen.
 #[derive(Default)]
-pub struct DrawMessage {
-    _private: (),
+pub struct DrawSignal {
+    dirty: AtomicBool,
+    notify: Notify,
 }
 
-pub async fn trigger_draw(state: &StateContainer) {
-    let draw_channel = { state.draw_channel.lock().await.clone() };
-    let Some(ref draw_channel) = draw_channel else {
-        tracing::warn!("No draw channel");
-        return;
-    };
-    let _ = draw_channel.send(DrawMessage::default()).await;
+impl DrawSignal {
+    pub fn request(&self) {
+        self.dirty.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+    /// Clears the pending request, returning whether there was one.
+    pub fn take(&self) -> bool {
+        self.dirty.swap(false, Ordering::AcqRel)
+    }
+    /// Waits until a draw is requested and clears the request.
+    pub async fn wait(&self) {
+        while !self.take() {
+            self.notify.notified().await;
+        }
+    }
 }
 
-async fn channel_draw_loop(state_container: StateContainer) -> anyhow::Result<()> {
-    let mut rx: tokio::sync::mpsc::Receiver<DrawMessage> = {
-        let state = state_container.state();
-        let mut draw_channel = state.draw_channel.lock().await;
-        let (tx, rx) = tokio::sync::mpsc::channel(1);
-        *draw_channel = Some(tx);
-
-        rx
-    };
+/// Keeps draws at least `1 / max_fps` seconds apart.
+pub struct FrameLimiter {
+    interval: std::time::Duration,
+    last_frame: Option<Instant>,
+}
 
-    update_size(state_container.clone()).await?;
-    draw(state_container.clone()).await?;
-    loop {
-        rx.recv().await;
-        {
-            if state_container.state().draw_lock.try_lock().is_err() {
-                continue;
-            }
+impl FrameLimiter {
+    pub fn new(max_fps: u32) -> Self {
+        FrameLimiter {
+            interval: std::time::Duration::from_secs(1) / max_fps.max(1),
+            last_frame: None,
+        }
+    }
+    /// Waits for the next frame slot and claims it.
+    pub async fn wait(&mut self) {
+        if let Some(last_frame) = self.last_frame {
+            tokio::time::sleep_until(last_frame + self.interval).await;
         }
-        update_size(state_container.clone()).await?;
-        draw(state_container.clone()).await?;
+        self.last_frame = Some(Instant::now());
     }
 }
 
-pub async fn timeout_draw_loop(state_container: StateContainer) -> anyhow::Result<()> {
-    let mut interval = tokio::time::interval(std::time::Duration::from_millis(500));
+/// Requests a draw. Requests made while one is already pending are merged
+/// into it, so they are drawn together in the next frame.
+pub async fn trigger_draw(state: &StateContainer) {
+    state.draw_signal.request();
+}
+
+/// Calls `draw_frame` each time a draw is requested on `signal`, and every
+/// `fallback_interval` in case the screen changed without a request. Frames
+/// are drawn one at a time, spaced out by `frame_limiter`.
+pub async fn run_draw_loop<F, R>(
+    signal: Arc<DrawSignal>,
+    fallback_interval: std::time::Duration,
+    frame_limiter: Arc<Mutex<FrameLimiter>>,
+    mut draw_frame: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut() -> R,
+    R: std::future::Future<Output = anyhow::Result<()>>,
+{
+    let mut interval = tokio::time::interval(fallback_interval);
     interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
     loop {
-        interval.tick().await;
-        {
-            if state_container.state().draw_lock.try_lock().is_err() {
-                continue;
+        tokio::select! {
+            _ = signal.wait() => {}
+            _ = interval.tick() => {
+                signal.take();
             }
         }
-        update_size(state_container.clone()).await?;
-        draw(state_container.clone()).await?;
+        frame_limiter.lock().await.wait().await;
+        draw_frame().await?;
     }
 }
 
 pub async fn draw_loop(state_container: StateContainer) -> anyhow::Result<()> {
-    let results = tokio::join!(
-        channel_draw_loop(state_container.clone()),
-        timeout_draw_loop(state_container)
-    );
-
-    results.0?;
-    results.1?;
-
-    Ok(())
+    let signal = state_container.draw_signal.clone();
+    let frame_limiter = state_container.frame_limiter.clone();
+
+    run_draw_loop(
+        signal,
+        std::time::Duration::from_millis(500),
+        frame_limiter,
+        || async {
+            update_size(state_container.clone()).await?;
+            draw(state_container.clone()).await
+        },
+    )
+    .await
 }
@@ -1,5 +1,6 @@
 pub const CSI_FINAL_BYTES: &str = r"@[\]^_`{|}~";
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CsiSequence {
     content: Vec<u8>,
 }
@@ -26,6 +27,7 @@ impl<T: Into<String>> From<T> for CsiSequence {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OscSequence {
     content: Vec<u8>,
 }
@@ -51,3 +53,131 @@ impl<T: Into<String>> From<T> for OscSequence {
         }
     }
 }
+
+/// A piece of a terminal output stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalCommand {
+    /// Text, including control characters and escape sequences other than
+    /// CSI and OSC.
+    String(String),
+    /// A CSI sequence, with the content following `ESC [` up to and including
+    /// the final byte.
+    Csi(CsiSequence),
+    /// An OSC sequence, with the content between `ESC ]` and the terminator.
+    Osc(OscSequence),
+}
+
+impl From<CsiSequence> for TerminalCommand {
+    fn from(value: CsiSequence) -> Self {
+        TerminalCommand::Csi(value)
+    }
+}
+
+impl From<OscSequence> for TerminalCommand {
+    fn from(value: OscSequence) -> Self {
+        TerminalCommand::Osc(value)
+    }
+}
+
+/// Builds the command a byte slice holds: a complete CSI or OSC sequence
+/// becomes that sequence, and anything else, including several commands or a
+/// cut off sequence, is kept as text. Like `parse`, only tests use this.
+#[cfg(test)]
+impl From<&[u8]> for TerminalCommand {
+    fn from(bytes: &[u8]) -> Self {
+        let mut commands = TerminalCommand::parse(bytes);
+        if commands.len() == 1 {
+            return commands.remove(0);
+        }
+
+        TerminalCommand::String(String::from_utf8_lossy(bytes).to_string())
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> From<&[u8; N]> for TerminalCommand {
+    fn from(bytes: &[u8; N]) -> Self {
+        TerminalCommand::from(&bytes[..])
+    }
+}
+
+#[cfg(test)]
+fn is_csi_final_byte(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || CSI_FINAL_BYTES.as_bytes().contains(&byte)
+}
+
+/// Returns the length of the OSC content starting at `bytes` and the length
+/// of its terminator, either BEL or `ESC \\`.
+#[cfg(test)]
+fn find_osc_end(bytes: &[u8]) -> Option<(usize, usize)> {
+    bytes
+        .iter()
+        .enumerate()
+        .find_map(|(index, byte)| match byte {
+            0x07 => Some((index, 1)),
+            0x1b if bytes.get(index + 1) == Some(&b'\\') => Some((index, 2)),
+            _ => None,
+        })
+}
+
+impl TerminalCommand {
+    pub fn string(value: impl Into<String>) -> Self {
+        TerminalCommand::String(value.into())
+    }
+    pub fn csi(value: impl Into<String>) -> Self {
+        TerminalCommand::Csi(CsiSequence::from(value))
+    }
+    pub fn osc(value: impl Into<String>) -> Self {
+        TerminalCommand::Osc(OscSequence::from(value))
+    }
+    /// Splits a raw output stream into text runs, CSI and OSC sequences.
+    /// Sequences that are cut off at the end of `bytes` are kept as text.
+    /// Pane output goes through `scan::OutputScanner`, so only tests use this.
+    #[cfg(test)]
+    pub fn parse(bytes: &[u8]) -> Vec<TerminalCommand> {
+        let mut commands = Vec::new();
+        let mut text: Vec<u8> = Vec::new();
+        let flush_text = |text: &mut Vec<u8>, commands: &mut Vec<TerminalCommand>| {
+            if !text.is_empty() {
+                let value = String::from_utf8_lossy(text).to_string();
+                commands.push(TerminalCommand::String(value));
+                text.clear();
+            }
+        };
+
+        let mut index = 0;
+        while index < bytes.len() {
+            let rest = &bytes[index..];
+            match rest {
+                [0x1b, b'[', content @ ..] => {
+                    let Some(end) = content.iter().position(|byte| is_csi_final_byte(*byte)) else {
+                        text.extend_from_slice(rest);
+                        break;
+                    };
+                    flush_text(&mut text, &mut commands);
+                    let sequence = CsiSequence::new(&content[..=end]);
+                    commands.push(sequence.into());
+                    index += 2 + end + 1;
+                }
+                [0x1b, b']', content @ ..] => {
+                    let Some((end, terminator_len)) = find_osc_end(content) else {
+                        text.extend_from_slice(rest);
+                        break;
+                    };
+                    flush_text(&mut text, &mut commands);
+                    let sequence = OscSequence::new(&content[..end]);
+                    commands.push(sequence.into());
+                    index += 2 + end + terminator_len;
+                }
+                [byte, ..] => {
+                    text.push(*byte);
+                    index += 1;
+                }
+                [] => break,
+            }
+        }
+        flush_text(&mut text, &mut commands);
+
+        commands
+    }
+}
@@ -1,5 +1,7 @@
 use renterm::vector::Vector2;
 
+use crate::term::CursorState;
+
 pub struct MoveCursor {
     y: i32,
     x: i32,
@@ -27,12 +29,38 @@ impl From<MoveCursor> for Vec<u8> {
     }
 }
 
+pub struct SetScrollRegion {
+    region: Option<(i32, i32)>,
+}
+
+impl SetScrollRegion {
+    pub fn new(top: i32, bottom: i32) -> Self {
+        SetScrollRegion {
+            region: Some((top, bottom)),
+        }
+    }
+    pub fn reset() -> Self {
+        SetScrollRegion { region: None }
+    }
+}
+
+impl From<SetScrollRegion> for Vec<u8> {
+    fn from(val: SetScrollRegion) -> Self {
+        let string = match val.region {
+            Some((top, bottom)) => format!("\x1b[{};{}r", top + 1, bottom + 1),
+            None => "\x1b[r".to_string(),
+        };
+        string.as_bytes().to_owned()
+    }
+}
+
+/// Erases the visible screen and moves the cursor to the top-left corner.
 pub struct ClearScreen {
     _private: (),
 }
 impl From<ClearScreen> for &[u8] {
     fn from(_: ClearScreen) -> Self {
-        "\x1b[3J\x1b[H\x1b[2J".as_bytes()
+        "\x1b[2J\x1b[H".as_bytes()
     }
 }
 
@@ -42,6 +70,43 @@ impl ClearScreen {
     }
 }
 
+/// Opens an OSC 8 hyperlink to `uri`, or closes the open one when `None`.
+pub struct SetHyperlink {
+    uri: Option<String>,
+}
+
+impl SetHyperlink {
+    pub fn new(uri: Option<String>) -> Self {
+        SetHyperlink { uri }
+    }
+}
+
+impl From<SetHyperlink> for Vec<u8> {
+    fn from(val: SetHyperlink) -> Self {
+        let string = format!("\x1b]8;;{}\x1b\\", val.uri.unwrap_or_default());
+        string.as_bytes().to_owned()
+    }
+}
+
+/// Sets the host clipboard with OSC 52.
+pub struct SetClipboard {
+    text: String,
+}
+
+impl SetClipboard {
+    pub fn new(text: impl Into<String>) -> Self {
+        SetClipboard { text: text.into() }
+    }
+}
+
+impl From<SetClipboard> for Vec<u8> {
+    fn from(val: SetClipboard) -> Self {
+        let encoded = data_encoding::BASE64.encode(val.text.as_bytes());
+        let string = format!("\x1b]52;c;{}\x07", encoded);
+        string.as_bytes().to_owned()
+    }
+}
+
 pub struct SetAlternateScreenBuffer {
     is_enabled: bool,
 }
@@ -159,6 +224,31 @@ impl From<SetCursorVisibility> for &[u8] {
     }
 }
 
+/// DECSCUSR, selects the cursor shape and whether it blinks.
+pub struct SetCursorStyle {
+    value: u16,
+}
+
+impl SetCursorStyle {
+    pub fn new(value: u16) -> Self {
+        SetCursorStyle { value }
+    }
+}
+
+impl From<CursorState> for SetCursorStyle {
+    fn from(state: CursorState) -> Self {
+        SetCursorStyle {
+            value: state.to_decscusr(),
+        }
+    }
+}
+
+impl From<SetCursorStyle> for Vec<u8> {
+    fn from(val: SetCursorStyle) -> Self {
+        format!("\x1b[{} q", val.value).into_bytes()
+    }
+}
+
 #[derive(Default)]
 pub struct ResetStyle {
     _private: (),
@@ -243,6 +333,11 @@ impl From<SgrMouseHandling> for &[u8] {
     }
 }
 
+/// Toggles the win32-input-mode of Windows Terminal and conhost, where key
+/// events are reported with their full `INPUT_RECORD` data.
+///
+/// Only meaningful on Windows. Other terminals ignore the private mode, so it
+/// is not emitted there.
 pub struct SetWin32InputMode {
     is_enabled: bool,
 }
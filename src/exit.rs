@@ -1,33 +1,109 @@
-use std::io::Write;
+use std::{
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use crossterm::{
     event::{
         DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, PopKeyboardEnhancementFlags,
     },
-    execute,
+    Command,
 };
 
 use crate::escape_codes::{
-    AllMotionTracking, ResetStyle, SetAlternateScreenBuffer, SetCursorVisibility,
-    SetWin32InputMode, SgrMouseHandling,
+    AllMotionTracking, MoveCursor, ResetStyle, SetAlternateScreenBuffer, SetCursorStyle,
+    SetCursorVisibility, SetScrollRegion, SetWin32InputMode, SgrMouseHandling,
 };
 
+static IS_ALTERNATE_SCREEN_IN_USE: AtomicBool = AtomicBool::new(true);
+static IS_WIN32_INPUT_IN_USE: AtomicBool = AtomicBool::new(false);
+static IS_ENHANCED_KEYBOARD_IN_USE: AtomicBool = AtomicBool::new(false);
+
+/// Records whether `init_screen` switched to the alternate screen, so that
+/// exiting only leaves it when it was entered.
+pub fn set_alternate_screen_in_use(value: bool) {
+    IS_ALTERNATE_SCREEN_IN_USE.store(value, Ordering::Relaxed);
+}
+
+/// Records whether `init_screen` turned on win32-input-mode and the kitty
+/// keyboard protocol, so that exiting only turns off what was turned on.
+pub fn set_input_modes_in_use(win32_input: bool, enhanced_keyboard: bool) {
+    IS_WIN32_INPUT_IN_USE.store(win32_input, Ordering::Relaxed);
+    IS_ENHANCED_KEYBOARD_IN_USE.store(enhanced_keyboard, Ordering::Relaxed);
+}
+
+/// The modes `init_screen` turned on, which exiting turns off again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenModes {
+    pub alternate_screen: bool,
+    pub win32_input: bool,
+    pub enhanced_keyboard: bool,
+}
+
+impl ScreenModes {
+    /// The modes recorded with `set_alternate_screen_in_use` and `set_input_modes_in_use`.
+    pub fn in_use() -> Self {
+        ScreenModes {
+            alternate_screen: IS_ALTERNATE_SCREEN_IN_USE.load(Ordering::Relaxed),
+            win32_input: IS_WIN32_INPUT_IN_USE.load(Ordering::Relaxed),
+            enhanced_keyboard: IS_ENHANCED_KEYBOARD_IN_USE.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Writes the sequences that return the host terminal to its normal screen.
+///
+/// Only small, fixed sequences are written, so this is safe to call from the panic hook.
+/// Mouse reporting is always turned off, as it can be enabled at runtime even
+/// when `Config::enable_mouse` is false, and so are bracketed paste and focus
+/// reports, which `enable_terminal_modes` always turns on.
+pub fn write_restore_sequence(output: &mut impl Write, modes: ScreenModes) -> std::io::Result<()> {
+    let mut sequence = String::new();
+    let _ignored = DisableBracketedPaste.write_ansi(&mut sequence);
+    let _ignored = DisableFocusChange.write_ansi(&mut sequence);
+    let _ignored = DisableMouseCapture.write_ansi(&mut sequence);
+    output.write_all(sequence.as_bytes())?;
+    output.write_all(AllMotionTracking::new(false).into())?;
+    output.write_all(SgrMouseHandling::new(false).into())?;
+    if modes.win32_input {
+        output.write_all(SetWin32InputMode::new(false).into())?;
+    }
+    if modes.enhanced_keyboard {
+        let mut sequence = String::new();
+        let _ignored = PopKeyboardEnhancementFlags.write_ansi(&mut sequence);
+        output.write_all(sequence.as_bytes())?;
+    }
+    output.write_all(ResetStyle::default().into())?;
+    output.write_all(&Into::<Vec<u8>>::into(SetScrollRegion::reset()))?;
+    if modes.alternate_screen {
+        output.write_all(SetAlternateScreenBuffer::new(false).into())?;
+    } else {
+        // The last frame stays in the scrollback, put the shell prompt below it.
+        let rows = crossterm::terminal::size()
+            .map(|(_, rows)| rows)
+            .unwrap_or(1);
+        output.write_all(&Into::<Vec<u8>>::into(MoveCursor::new(
+            i32::from(rows) - 1,
+            0,
+        )))?;
+        output.write_all("\r\n".as_bytes())?;
+    }
+    output.write_all(&Into::<Vec<u8>>::into(SetCursorStyle::new(0)))?;
+    output.write_all(SetCursorVisibility::new(true).into())?;
+    output.flush()
+}
+
+/// Restores the host terminal after a panic, without going through the
+/// crossterm command machinery.
+pub fn restore_after_panic() {
+    let _ignored = crossterm::terminal::disable_raw_mode();
+    let _ignored = write_restore_sequence(&mut std::io::stdout(), ScreenModes::in_use());
+}
+
 fn exit_helper(status_code: i32) -> Result<(), Box<dyn std::error::Error>> {
     let _ignored = crossterm::terminal::disable_raw_mode();
-    let _ignored = execute!(
-        std::io::stdout(),
-        DisableBracketedPaste,
-        DisableFocusChange,
-        DisableMouseCapture,
-    );
-
-    let _ignored = execute!(std::io::stdout(), PopKeyboardEnhancementFlags,);
-
-    let mut stdout = std::io::stdout();
-    let _ignored = stdout.write(ResetStyle::default().into());
-    let _ignored = stdout.write(SetAlternateScreenBuffer::new(false).into());
-    let _ignored = stdout.write(SetCursorVisibility::new(true).into());
-    let _ignored = stdout.flush();
+    let _ignored = write_restore_sequence(&mut std::io::stdout(), ScreenModes::in_use());
+    let _ignored = std::fs::remove_file(crate::control::control_socket_path());
 
     std::process::exit(status_code);
 }
@@ -0,0 +1,90 @@
+use std::process::Stdio;
+
+use crate::{draw::find_process_by_id, process::shell_command, state::StateContainer};
+
+/// Pane events that `Config::hooks` can run a command on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HookEvent {
+    Created,
+    Focused,
+    Closed,
+}
+
+/// Shell commands run on pane events, like tmux hooks. Each gets the pane id
+/// and title in `CITYMUX_PANE_ID` and `CITYMUX_PANE_TITLE`, and outside of
+/// Windows also as `$1` and `$2`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Hooks {
+    pub pane_created: Option<String>,
+    pub pane_focused: Option<String>,
+    pub pane_closed: Option<String>,
+}
+
+impl Hooks {
+    pub fn command(&self, event: HookEvent) -> Option<&str> {
+        match event {
+            HookEvent::Created => self.pane_created.as_deref(),
+            HookEvent::Focused => self.pane_focused.as_deref(),
+            HookEvent::Closed => self.pane_closed.as_deref(),
+        }
+    }
+}
+
+/// The title a pane is passed to hooks with, its command if it has none or
+/// its terminal is busy, so that a hook never waits for the pane's output.
+pub async fn hook_title(state_container: &StateContainer, span_id: usize) -> String {
+    let Some(process) = find_process_by_id(state_container.clone(), span_id).await else {
+        return String::new();
+    };
+    let process = process.read().await;
+    let title = match process.terminal_info.try_lock() {
+        Ok(terminal_info) => terminal_info.title(),
+        Err(_) => String::new(),
+    };
+    if title.is_empty() {
+        process.command.command_line()
+    } else {
+        title
+    }
+}
+
+fn hook_command(command: &str, span_id: usize, title: &str) -> tokio::process::Command {
+    let mut process = shell_command(command);
+    if !cfg!(target_os = "windows") {
+        process.arg("citymux").arg(span_id.to_string()).arg(title);
+    }
+    process
+        .env("CITYMUX_PANE_ID", span_id.to_string())
+        .env("CITYMUX_PANE_TITLE", title)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    process
+}
+
+/// Runs the hook configured for `event` without waiting for it, logging
+/// commands that can't be started or fail.
+pub async fn run_hook(
+    state_container: &StateContainer,
+    event: HookEvent,
+    span_id: usize,
+    title: String,
+) {
+    let command = {
+        let config = state_container.config.read().await;
+        config.hooks.command(event).map(str::to_string)
+    };
+    let Some(command) = command else {
+        return;
+    };
+    tracing::debug!("Running {:?} hook for pane {}", event, span_id);
+    let mut process = hook_command(&command, span_id, &title);
+    tokio::spawn(async move {
+        match process.status().await {
+            Ok(status) if status.success() => {}
+            Ok(status) => tracing::warn!("{:?} hook failed: {:?}", event, status),
+            Err(err) => tracing::warn!("Could not run {:?} hook: {:?}", event, err),
+        }
+    });
+}
@@ -1,30 +1,134 @@
-use crossterm::event::{
-    Event, EventStream, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind,
+use crossterm::{
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyCode, KeyEvent,
+        KeyEventState, KeyModifiers, MouseButton, MouseEventKind,
+    },
+    Command,
 };
 use futures::StreamExt;
 use renterm::{scalar::Scalar, vector::Vector2};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tokio::io::AsyncWriteExt;
 
 use crate::{
-    draw::trigger_draw,
-    spawn::{create_process, kill_active_span},
+    clipboard::copy_to_clipboard,
+    draw::{find_process_by_id, trigger_draw},
+    escape_codes::{AllMotionTracking, SgrMouseHandling},
+    keybind::{prefix_action, Action, KeyBinding, MouseArea},
+    main_pane::toggle_main_pane,
+    mode::{dispatch_key, dispatch_mouse, InputMode},
+    picker::{handle_picker_key, open_pane_picker},
+    signal::send_signal,
+    size::resize_panes,
+    span::{equalize_spans, SpanDirection},
+    spawn::{create_process, create_process_before, kill_active_span, launch, reopen_closed_pane},
+    startup::suspend,
     state::StateContainer,
     term::{MouseProtocolEncoding, MouseProtocolMode},
+    virtual_process::open_help_pane,
+    window::{break_pane, join_pane, next_window},
+    zoom::toggle_zoom,
 };
 
+/// Returns whether the active pane is read-only, telling the user so if it is.
+async fn reject_read_only(state_container: &StateContainer) -> bool {
+    let active_id = state_container
+        .active_id
+        .load(std::sync::atomic::Ordering::Relaxed);
+
+    reject_read_only_pane(state_container, active_id).await
+}
+
+/// Like `reject_read_only`, for the pane `span_id`.
+pub async fn reject_read_only_pane(state_container: &StateContainer, span_id: usize) -> bool {
+    let Some(process) = find_process_by_id(state_container.clone(), span_id).await else {
+        return false;
+    };
+    let read_only = process.read().await.read_only;
+    if read_only {
+        state_container.flash("Pane is read-only").await;
+    }
+
+    read_only
+}
+
 pub async fn write_input(
     state_container: StateContainer,
     data: &[u8],
     flush: bool,
 ) -> anyhow::Result<()> {
-    let active_process = state_container.state().active_process().await;
-    if let Some(active_process) = active_process {
-        let process = active_process.read().await;
-        let mut stdin = process.stdin.lock().await;
-        stdin.write(data).await?;
-        if flush {
-            stdin.flush().await?;
-        }
+    let active_id = state_container
+        .active_id
+        .load(std::sync::atomic::Ordering::Relaxed);
+    if find_process_by_id(state_container.clone(), active_id)
+        .await
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    write_to_pane(&state_container, active_id, data, flush).await
+}
+
+/// Empties the scrollback of the process in a pane.
+pub async fn clear_history(state_container: &StateContainer, span_id: usize) -> anyhow::Result<()> {
+    let Some(process) = find_process_by_id(state_container.clone(), span_id).await else {
+        return Err(anyhow::format_err!("No process in pane: {}", span_id));
+    };
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.clear_scrollback();
+    }
+    trigger_draw(state_container).await;
+
+    Ok(())
+}
+
+/// Evens out the sizes of the panes in spans laid out in `direction`.
+pub async fn equalize(
+    state_container: &StateContainer,
+    direction: SpanDirection,
+) -> anyhow::Result<()> {
+    let changed = {
+        let mut root = state_container.root_node.write().await;
+        root.as_mut()
+            .is_some_and(|root| equalize_spans(root, direction))
+    };
+    if changed {
+        resize_panes(state_container).await?;
+        trigger_draw(state_container).await;
+    }
+
+    Ok(())
+}
+
+/// Shows or hides the status bar, resizing the panes to the new layout height.
+pub async fn toggle_status_bar(state_container: &StateContainer) -> anyhow::Result<()> {
+    state_container
+        .status_bar_visible
+        .fetch_xor(true, std::sync::atomic::Ordering::Relaxed);
+    resize_panes(state_container).await?;
+    trigger_draw(state_container).await;
+
+    Ok(())
+}
+
+/// Writes to the input of the process in a pane.
+pub async fn write_to_pane(
+    state_container: &StateContainer,
+    span_id: usize,
+    data: &[u8],
+    flush: bool,
+) -> anyhow::Result<()> {
+    let Some(process) = find_process_by_id(state_container.clone(), span_id).await else {
+        return Err(anyhow::format_err!("No process in pane: {}", span_id));
+    };
+    let process = process.read().await;
+    let mut stdin = process.stdin.lock().await;
+    stdin.write_all(data).await?;
+    if flush {
+        stdin.flush().await?;
     }
 
     Ok(())
@@ -33,6 +137,8 @@ pub async fn write_input(
 #[derive(Clone, Debug)]
 struct KeyEventConversionOptions {
     pub is_application_keypad_mode_enabled: bool,
+    /// Send Enter as the keypad Enter, `ESC O M`, in application keypad mode.
+    pub is_keypad_enter_enabled: bool,
     _private: (),
 }
 
@@ -41,17 +147,48 @@ impl KeyEventConversionOptions {
         self.is_application_keypad_mode_enabled = is_enabled;
         self
     }
+    pub fn with_keypad_enter(mut self, is_enabled: bool) -> Self {
+        self.is_keypad_enter_enabled = is_enabled;
+        self
+    }
 }
 
 impl Default for KeyEventConversionOptions {
     fn default() -> Self {
         Self {
             is_application_keypad_mode_enabled: false,
+            is_keypad_enter_enabled: false,
             _private: (),
         }
     }
 }
 
+/// The application keypad sequence of a key on the numeric keypad.
+fn keypad_char_sequence(char: char) -> Option<&'static str> {
+    let sequence = match char {
+        '0' => "\x1bOp",
+        '1' => "\x1bOq",
+        '2' => "\x1bOr",
+        '3' => "\x1bOs",
+        '4' => "\x1bOt",
+        '5' => "\x1bOu",
+        '6' => "\x1bOv",
+        '7' => "\x1bOw",
+        '8' => "\x1bOx",
+        '9' => "\x1bOy",
+        '*' => "\x1bOj",
+        '+' => "\x1bOk",
+        ',' => "\x1bOl",
+        '-' => "\x1bOm",
+        '.' => "\x1bOn",
+        '/' => "\x1bOo",
+        '=' => "\x1bOX",
+        _ => return None,
+    };
+
+    Some(sequence)
+}
+
 fn key_event_to_bytes(event: KeyEvent, options: KeyEventConversionOptions) -> Vec<u8> {
     let mut bytes = Vec::new();
     if event.kind == crossterm::event::KeyEventKind::Press
@@ -62,7 +199,11 @@ fn key_event_to_bytes(event: KeyEvent, options: KeyEventConversionOptions) -> Ve
                 bytes.push(0x7f);
             }
             KeyCode::Enter => {
-                if options.is_application_keypad_mode_enabled {
+                // The main Enter key submits lines with `\r` even in application
+                // keypad mode, only the one on the keypad has its own sequence.
+                let is_keypad =
+                    event.state.contains(KeyEventState::KEYPAD) || options.is_keypad_enter_enabled;
+                if options.is_application_keypad_mode_enabled && is_keypad {
                     bytes.extend_from_slice("\x1bOM".as_bytes());
                 } else {
                     bytes.push(b'\r');
@@ -150,7 +291,14 @@ fn key_event_to_bytes(event: KeyEvent, options: KeyEventConversionOptions) -> Ve
             }
             KeyCode::F(_value) => {}
             KeyCode::Char(char) => {
-                if event.modifiers.intersects(KeyModifiers::CONTROL) && char.is_ascii_alphabetic() {
+                let keypad_sequence = keypad_char_sequence(char)
+                    .filter(|_| options.is_application_keypad_mode_enabled)
+                    .filter(|_| event.state.contains(KeyEventState::KEYPAD));
+                if let Some(sequence) = keypad_sequence {
+                    bytes.extend_from_slice(sequence.as_bytes());
+                } else if event.modifiers.intersects(KeyModifiers::CONTROL)
+                    && char.is_ascii_alphabetic()
+                {
                     let char = char.to_ascii_uppercase();
                     bytes.push(char as u8 - 'A' as u8 + 1);
                 } else if event.modifiers.intersects(KeyModifiers::ALT)
@@ -185,52 +333,84 @@ fn key_event_to_bytes(event: KeyEvent, options: KeyEventConversionOptions) -> Ve
     bytes
 }
 
+/// Length of the overlap of two ranges given by their start and length.
+fn overlap(start: i32, len: i32, other_start: i32, other_len: i32) -> i32 {
+    (start + len).min(other_start + other_len) - start.max(other_start)
+}
+
+/// Focuses the pane next to the active one in `direction`. When several
+/// panes share that edge, the one that was active most recently wins, then
+/// the one sharing most of the edge.
 async fn handle_navigation(state: &StateContainer, direction: Vector2) -> anyhow::Result<()> {
-    let processess = state.processes.read().await;
+    let processess = state.ordered_processes().await;
     let current_process = state.active_process().await;
     let Some(current_process) = current_process else {
         return Ok(());
     };
-    let current_process = current_process.read().await;
-    let current_dimensions = state.get_span_dimensions(current_process.span_id).await;
-    let Some(current_dimensions) = current_dimensions else {
+    let current_span_id = current_process.read().await.span_id;
+    let current_dimensions = state.get_span_dimensions(current_span_id).await;
+    let Some(current) = current_dimensions else {
         return Ok(());
     };
-    let position: Vector2 = match direction.signnum().into() {
-        (-1, 0) => (
-            current_dimensions.position().x - 1,
-            current_dimensions.position().y + current_dimensions.size().y / 2,
-        ),
-        (1, 0) => (
-            current_dimensions.position().x + current_dimensions.size().x + 1,
-            current_dimensions.position().y + current_dimensions.size().y / 2,
-        ),
-        (0, -1) => (
-            current_dimensions.position().x + current_dimensions.size().x / 2,
-            current_dimensions.position().y - 1,
-        ),
-        (0, 1) => (
-            current_dimensions.position().x + current_dimensions.size().x / 2,
-            current_dimensions.position().y + current_dimensions.size().y + 1,
-        ),
-        _ => current_dimensions.position().into(),
-    }
-    .into();
-
-    tracing::debug!("dim: {:?} position: {:?}", current_dimensions, position);
+    let gap = i32::from(state.config.read().await.pane_gap);
+    let (position, size) = (current.position(), current.size());
+    // The column or row just past the edge, and whether it is a column.
+    let (probe, vertical_edge) = match direction.signnum().into() {
+        (-1, 0) => (position.x - 1 - gap, true),
+        (1, 0) => (position.x + size.x + 1 + gap, true),
+        (0, -1) => (position.y - 1 - gap, false),
+        (0, 1) => (position.y + size.y + 1 + gap, false),
+        _ => return Ok(()),
+    };
 
+    tracing::debug!("dim: {:?} probe: {:?}", current, probe);
+
+    let last_active = match state.last_active.lock() {
+        Ok(last_active) => last_active.clone(),
+        Err(err) => {
+            tracing::error!(
+                "Navigating without knowing when panes were focused: {}",
+                err
+            );
+            HashMap::new()
+        }
+    };
+    let mut best: Option<(Option<Instant>, i32, usize)> = None;
     for process in processess.iter() {
-        let process = process.clone();
-        let process = process.read().await;
-        let rect = state.get_span_dimensions(process.span_id).await;
-        let Some(rect) = rect else {
+        let span_id = process.read().await.span_id;
+        if span_id == current_span_id {
+            continue;
+        }
+        let Some(rect) = state.get_span_dimensions(span_id).await else {
             continue;
         };
-        if rect.contains(position.clone()) {
-            state.set_active_span(process.span_id);
-            break;
+        let (rect_position, rect_size) = (rect.position(), rect.size());
+        let (crosses_probe, shared) = if vertical_edge {
+            (
+                (rect_position.x..rect_position.x + rect_size.x).contains(&probe),
+                overlap(rect_position.y, rect_size.y, position.y, size.y),
+            )
+        } else {
+            (
+                (rect_position.y..rect_position.y + rect_size.y).contains(&probe),
+                overlap(rect_position.x, rect_size.x, position.x, size.x),
+            )
+        };
+        if !crosses_probe || shared <= 0 {
+            continue;
+        }
+        let candidate = (last_active.get(&span_id).copied(), shared, span_id);
+        let better = match &best {
+            Some((active, shared, _)) => (candidate.0, candidate.1) > (*active, *shared),
+            None => true,
+        };
+        if better {
+            best = Some(candidate);
         }
     }
+    if let Some((_, _, span_id)) = best {
+        state.set_active_span(span_id);
+    }
 
     Ok(())
 }
@@ -239,43 +419,34 @@ async fn handle_shortcuts(
     state_container: &StateContainer,
     event: KeyEvent,
 ) -> anyhow::Result<bool> {
-    if event.code == KeyCode::Char('q')
-        && event.modifiers.intersects(KeyModifiers::ALT)
-        && event.kind == crossterm::event::KeyEventKind::Press
-    {
+    let strict = { state_container.config.read().await.strict_shortcuts };
+    let pressed = |code| {
+        let binding = KeyBinding::new(code, KeyModifiers::ALT);
+        if strict {
+            binding.matches(&event)
+        } else {
+            binding.matches_loosely(&event)
+        }
+    };
+    if pressed(KeyCode::Char('q')) {
         kill_active_span(state_container.clone()).await?;
         return Ok(true);
-    } else if event.code == KeyCode::Char('n')
-        && event.modifiers.intersects(KeyModifiers::ALT)
-        && event.kind == crossterm::event::KeyEventKind::Press
-    {
+    } else if pressed(KeyCode::Char('n')) {
         create_process(state_container.clone()).await?;
         return Ok(true);
-    } else if event.code == KeyCode::Left
-        && event.modifiers.intersects(KeyModifiers::ALT)
-        && event.kind == crossterm::event::KeyEventKind::Press
-    {
+    } else if pressed(KeyCode::Left) {
         return handle_navigation(state_container, Vector2::new(-1, 0))
             .await
             .map(|_| true);
-    } else if event.code == KeyCode::Right
-        && event.modifiers.intersects(KeyModifiers::ALT)
-        && event.kind == crossterm::event::KeyEventKind::Press
-    {
+    } else if pressed(KeyCode::Right) {
         return handle_navigation(state_container, Vector2::new(1, 0))
             .await
             .map(|_| true);
-    } else if event.code == KeyCode::Up
-        && event.modifiers.intersects(KeyModifiers::ALT)
-        && event.kind == crossterm::event::KeyEventKind::Press
-    {
+    } else if pressed(KeyCode::Up) {
         return handle_navigation(state_container, Vector2::new(0, -1))
             .await
             .map(|_| true);
-    } else if event.code == KeyCode::Down
-        && event.modifiers.intersects(KeyModifiers::ALT)
-        && event.kind == crossterm::event::KeyEventKind::Press
-    {
+    } else if pressed(KeyCode::Down) {
         return handle_navigation(state_container, Vector2::new(0, 1))
             .await
             .map(|_| true);
@@ -284,22 +455,314 @@ async fn handle_shortcuts(
     Ok(false)
 }
 
-async fn handle_key_event(state_container: StateContainer, event: KeyEvent) -> anyhow::Result<()> {
+pub async fn run_action(state_container: &StateContainer, action: Action) -> anyhow::Result<()> {
+    match action {
+        Action::ToggleMouse => {
+            let enabled = !state_container
+                .mouse_enabled
+                .load(std::sync::atomic::Ordering::Relaxed);
+            set_mouse_capture(state_container, enabled).await?;
+            let message = if enabled {
+                "Mouse captured"
+            } else {
+                "Mouse released to terminal"
+            };
+            state_container.flash(message).await;
+        }
+        Action::ToggleReadOnly => {
+            let Some(process) = state_container.active_process().await else {
+                return Ok(());
+            };
+            let read_only = {
+                let mut process = process.write().await;
+                process.read_only = !process.read_only;
+                process.read_only
+            };
+            let message = if read_only {
+                "Pane is read-only"
+            } else {
+                "Pane is writable"
+            };
+            state_container.flash(message).await;
+        }
+        Action::ToggleDebugLayout => {
+            state_container
+                .debug_layout
+                .fetch_xor(true, std::sync::atomic::Ordering::Relaxed);
+        }
+        Action::BreakPane => {
+            break_pane(state_container).await?;
+        }
+        Action::JoinPane => {
+            join_pane(state_container).await?;
+        }
+        Action::NextWindow => {
+            next_window(state_container).await;
+        }
+        Action::NewPane => {
+            create_process(state_container.clone()).await?;
+        }
+        Action::NewPaneBefore => {
+            create_process_before(state_container.clone()).await?;
+        }
+        Action::InterruptPane => {
+            let active_id = state_container
+                .active_id
+                .load(std::sync::atomic::Ordering::Relaxed);
+            if let Err(err) = send_signal(state_container, active_id, "INT").await {
+                tracing::warn!("Could not interrupt pane: {:?}", err);
+                state_container.flash("Could not interrupt pane").await;
+            }
+        }
+        Action::ToggleMainPane => {
+            toggle_main_pane(state_container).await;
+        }
+        Action::UndoClose => {
+            reopen_closed_pane(state_container).await?;
+        }
+        Action::Quit => {
+            let confirm_kill = { state_container.config.read().await.confirm_kill };
+            let panes = { state_container.processes.read().await.len() };
+            if confirm_kill && panes > 1 {
+                state_container.set_input_mode(InputMode::ConfirmQuit).await;
+                trigger_draw(state_container).await;
+            } else {
+                state_container.shutdown.notify_one();
+            }
+        }
+        Action::Suspend => {
+            suspend(state_container).await?;
+        }
+        Action::PickPane => {
+            open_pane_picker(state_container).await;
+        }
+        Action::ResizeMode => {
+            state_container.set_input_mode(InputMode::Resize).await;
+            trigger_draw(state_container).await;
+        }
+        Action::ToggleZoom => {
+            toggle_zoom(state_container).await?;
+        }
+        Action::EqualizeHorizontal => {
+            equalize(state_container, SpanDirection::Horizontal).await?;
+        }
+        Action::EqualizeVertical => {
+            equalize(state_container, SpanDirection::Vertical).await?;
+        }
+        Action::Redraw => {
+            state_container.invalidate().await;
+            trigger_draw(state_container).await;
+        }
+        Action::ToggleStatusBar => {
+            toggle_status_bar(state_container).await?;
+        }
+        Action::ShowHelp => {
+            open_help_pane(state_container).await?;
+        }
+        Action::ClearHistory => {
+            let active_id = state_container
+                .active_id
+                .load(std::sync::atomic::Ordering::Relaxed);
+            clear_history(state_container, active_id).await?;
+            state_container.flash("History cleared").await;
+        }
+        Action::Launch(index) => {
+            let launcher = {
+                state_container
+                    .config
+                    .read()
+                    .await
+                    .launchers
+                    .get(index)
+                    .cloned()
+            };
+            if let Some(launcher) = launcher {
+                launch(state_container.clone(), &launcher).await?;
+            }
+        }
+        Action::SendString(index) => {
+            let bytes = {
+                state_container
+                    .config
+                    .read()
+                    .await
+                    .send_strings
+                    .get(index)
+                    .map(|send_string| send_string.bytes.clone())
+            };
+            if let Some(bytes) = bytes {
+                if !reject_read_only(state_container).await {
+                    write_input(state_container.clone(), &bytes, true).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the prefix key and the key following it, returns whether the
+/// event was consumed.
+async fn handle_prefix(state_container: &StateContainer, event: KeyEvent) -> anyhow::Result<bool> {
+    if event.kind != crossterm::event::KeyEventKind::Press {
+        return Ok(false);
+    }
+    let (prefix, timeout_ms) = {
+        let config = state_container.config.read().await;
+        (config.prefix.clone(), config.prefix_timeout_ms)
+    };
+    let pending_prefix = state_container
+        .pending_prefix
+        .swap(false, std::sync::atomic::Ordering::Relaxed);
+    if pending_prefix {
+        // Pressing the prefix twice sends it to the pane, for programs that use it too.
+        if prefix.matches(&event) {
+            forward_key(state_container, event).await?;
+            return Ok(true);
+        }
+        let action = {
+            let config = state_container.config.read().await;
+            prefix_action(&event, &config.launchers, &config.send_strings)
+        };
+        if let Some(action) = action {
+            run_action(state_container, action).await?;
+        }
+        return Ok(true);
+    }
+    if prefix.matches(&event) {
+        state_container
+            .pending_prefix
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        if timeout_ms > 0 {
+            expire_prefix(state_container.clone(), Duration::from_millis(timeout_ms));
+        }
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Clears the pending prefix once `timeout` elapses, unless a key was pressed
+/// after it or the prefix was pressed again in the meantime.
+fn expire_prefix(state_container: StateContainer, timeout: Duration) {
+    let press = state_container
+        .prefix_presses
+        .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        + 1;
+    tokio::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        let latest = state_container
+            .prefix_presses
+            .load(std::sync::atomic::Ordering::Relaxed);
+        if latest == press {
+            state_container
+                .pending_prefix
+                .store(false, std::sync::atomic::Ordering::Relaxed);
+        }
+    });
+}
+
+/// Sends pasted text to the active pane, wrapping it in bracketed paste
+/// markers when the pane asked for them.
+async fn write_paste(state_container: StateContainer, text: &str) -> anyhow::Result<()> {
+    let bracketed_paste_mode = match state_container.state().active_terminal_info().await {
+        Some(terminal_info) => terminal_info.lock().await.bracketed_paste_mode(),
+        None => false,
+    };
+    let text = text.replace("\r\n", "\r").replace('\n', "\r");
+    let mut data = Vec::new();
+    if bracketed_paste_mode {
+        data.extend_from_slice(b"\x1b[200~");
+    }
+    data.extend_from_slice(text.as_bytes());
+    if bracketed_paste_mode {
+        data.extend_from_slice(b"\x1b[201~");
+    }
+    write_input(state_container, &data, true).await
+}
+
+/// Forwards a paste, holding multiline pastes back until the user confirms
+/// them when `Config::confirm_multiline_paste` is set.
+pub async fn handle_paste(state_container: StateContainer, text: String) -> anyhow::Result<()> {
+    let confirm_multiline_paste = state_container.config.read().await.confirm_multiline_paste;
+    if reject_read_only(&state_container).await {
+        return Ok(());
+    }
+    if confirm_multiline_paste && text.contains('\n') {
+        let mut pending_paste = state_container.pending_paste.lock().await;
+        *pending_paste = Some(text);
+        return Ok(());
+    }
+
+    write_paste(state_container, &text).await
+}
+
+/// Answers the paste confirmation prompt, returns whether a paste was pending.
+async fn handle_paste_confirmation(
+    state_container: &StateContainer,
+    event: KeyEvent,
+) -> anyhow::Result<bool> {
+    if event.kind != crossterm::event::KeyEventKind::Press {
+        return Ok(state_container.pending_paste.lock().await.is_some());
+    }
+    let pending_paste = { state_container.pending_paste.lock().await.take() };
+    let Some(text) = pending_paste else {
+        return Ok(false);
+    };
+    if matches!(event.code, KeyCode::Char('y') | KeyCode::Enter) {
+        write_paste(state_container.clone(), &text).await?;
+    } else {
+        state_container.flash("Paste cancelled").await;
+    }
+
+    Ok(true)
+}
+
+pub async fn handle_key_event(
+    state_container: StateContainer,
+    event: KeyEvent,
+) -> anyhow::Result<()> {
+    if handle_paste_confirmation(&state_container, event).await? {
+        return Ok(());
+    }
+    if dispatch_key(&state_container, event).await? {
+        return Ok(());
+    }
+    if handle_picker_key(&state_container, event).await? {
+        return Ok(());
+    }
+    if handle_prefix(&state_container, event).await? {
+        return Ok(());
+    }
     if handle_shortcuts(&state_container, event).await? == true {
         return Ok(());
     }
 
+    forward_key(&state_container, event).await
+}
+
+/// Writes a key to the active pane, encoded the way the pane expects it.
+async fn forward_key(state_container: &StateContainer, event: KeyEvent) -> anyhow::Result<()> {
+    if event.kind == crossterm::event::KeyEventKind::Press
+        && reject_read_only(state_container).await
+    {
+        return Ok(());
+    }
+
+    let keypad_enter = { state_container.config.read().await.keypad_enter };
     let data = key_event_to_bytes(
         event,
-        KeyEventConversionOptions::default().with_application_keypad_mode(
-            state_container
-                .state()
-                .application_keypad_mode()
-                .await
-                .unwrap_or(false),
-        ),
+        KeyEventConversionOptions::default()
+            .with_application_keypad_mode(
+                state_container
+                    .state()
+                    .application_keypad_mode()
+                    .await
+                    .unwrap_or(false),
+            )
+            .with_keypad_enter(keypad_enter),
     );
-    write_input(state_container, &data, true).await?;
+    write_input(state_container.clone(), &data, true).await?;
 
     Ok(())
 }
@@ -325,10 +788,147 @@ async fn has_mouse_press(state_container: &StateContainer) -> bool {
     map.iter().any(|(_, value)| *value)
 }
 
-async fn handle_mouse_event(
+/// Returns the escape codes that turn the host terminal's mouse reporting on or off.
+pub fn mouse_capture_sequence(enabled: bool) -> Vec<u8> {
+    let mut sequence = String::new();
+    let _ignored = if enabled {
+        EnableMouseCapture.write_ansi(&mut sequence)
+    } else {
+        DisableMouseCapture.write_ansi(&mut sequence)
+    };
+    let mut sequence = sequence.into_bytes();
+    sequence.extend(Into::<&[u8]>::into(AllMotionTracking::new(enabled)));
+    sequence.extend(Into::<&[u8]>::into(SgrMouseHandling::new(enabled)));
+
+    sequence
+}
+
+/// Starts or stops capturing the mouse. While stopped, mouse events are left
+/// to the host terminal, so text can be selected natively. Panes keep their
+/// own mouse modes, which apply again once capturing resumes.
+pub async fn set_mouse_capture(state: &StateContainer, enabled: bool) -> anyhow::Result<()> {
+    state
+        .mouse_enabled
+        .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    // Releases may happen while the host terminal owns the mouse.
+    state.current_mouse_buttons.write().await.clear();
+    let stdout = state.stdout.clone();
+    let mut stdout = stdout.lock().await;
+    stdout.write_all(&mouse_capture_sequence(enabled)).await?;
+    stdout.flush().await?;
+
+    Ok(())
+}
+
+/// Encodes a mouse coordinate as a character, or NUL if it is negative or
+/// not a valid code point.
+fn coordinate_to_char(value: i32) -> char {
+    u32::try_from(value)
+        .ok()
+        .and_then(char::from_u32)
+        .unwrap_or_default()
+}
+
+/// The pane under `position` and the part of it that was hit.
+async fn pane_area_at(state: &StateContainer, position: Vector2) -> Option<(usize, MouseArea)> {
+    for id in state.ordered_pane_ids().await {
+        let Some(rect) = state.get_span_dimensions(id).await else {
+            continue;
+        };
+        if rect.contains(position.clone()) {
+            let area = if position.y == rect.position().y {
+                MouseArea::Title
+            } else {
+                MouseArea::Content
+            };
+            return Some((id, area));
+        }
+    }
+
+    None
+}
+
+/// Runs the action bound to a mouse button when it is pressed. Returns
+/// whether the event belongs to a binding and shouldn't be forwarded.
+async fn handle_mouse_binding(
+    state: &StateContainer,
+    event: &crossterm::event::MouseEvent,
+    position: Vector2,
+) -> anyhow::Result<bool> {
+    let button = match event.kind {
+        MouseEventKind::Down(button)
+        | MouseEventKind::Up(button)
+        | MouseEventKind::Drag(button) => button,
+        _ => return Ok(false),
+    };
+    let Some((span_id, area)) = pane_area_at(state, position).await else {
+        return Ok(false);
+    };
+    let action = {
+        let config = state.config.read().await;
+        config
+            .mouse_bindings
+            .iter()
+            .find(|binding| binding.matches(button, area))
+            .map(|binding| binding.action)
+    };
+    let Some(action) = action else {
+        return Ok(false);
+    };
+    if let MouseEventKind::Down(_) = event.kind {
+        state.set_active_span(span_id);
+        run_action(state, action).await?;
+    }
+
+    Ok(true)
+}
+
+/// Longest time between two clicks at the same place for them to count as
+/// a double click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Records a left click, returning whether it completes a double click.
+async fn register_click(state: &StateContainer, position: Vector2) -> bool {
+    let mut last_click = state.last_click.lock().await;
+    let now = Instant::now();
+    let is_double_click = last_click.as_ref().is_some_and(|(last_position, at)| {
+        *last_position == position && now.duration_since(*at) <= DOUBLE_CLICK_INTERVAL
+    });
+    *last_click = if is_double_click {
+        None
+    } else {
+        Some((position, now))
+    };
+
+    is_double_click
+}
+
+/// Whether `event` drags or releases the button of a swallowed focus click,
+/// which the program in the pane never saw pressed. A new press ends the
+/// swallowing, in case the release got lost.
+async fn is_swallowed(state: &StateContainer, event: &crossterm::event::MouseEvent) -> bool {
+    let mut swallowed_button = state.swallowed_button.lock().await;
+    match event.kind {
+        MouseEventKind::Down(_) => {
+            *swallowed_button = None;
+            false
+        }
+        MouseEventKind::Drag(button) => *swallowed_button == Some(button),
+        MouseEventKind::Up(button) if *swallowed_button == Some(button) => {
+            *swallowed_button = None;
+            true
+        }
+        _ => false,
+    }
+}
+
+pub async fn handle_mouse_event(
     state: &StateContainer,
     event: crossterm::event::MouseEvent,
 ) -> anyhow::Result<()> {
+    if dispatch_mouse(state, event).await {
+        return Ok(());
+    }
     let position: Vector2 = Vector2::new(event.column, event.row);
     let button = match event.kind {
         MouseEventKind::Down(button) => map_button_to_int(button),
@@ -338,7 +938,7 @@ async fn handle_mouse_event(
         MouseEventKind::ScrollDown => 65,
         _ => 0,
     };
-    let _is_scroll = [64, 65].contains(&button);
+    let is_scroll = [64, 65].contains(&button);
     let is_release = if let MouseEventKind::Up(_) = event.kind {
         true
     } else {
@@ -367,7 +967,15 @@ async fn handle_mouse_event(
         *mouse_position = position.clone();
     }
 
-    let processess = state.processes.read().await;
+    if handle_mouse_binding(state, &event, position.clone()).await? {
+        return Ok(());
+    }
+    if is_swallowed(state, &event).await {
+        return Ok(());
+    }
+
+    let mut selected_text = None;
+    let processess = state.ordered_processes().await;
     for process in processess.iter() {
         let process = process.clone();
         let process = process.read().await;
@@ -377,12 +985,49 @@ async fn handle_mouse_event(
         };
         if rect.contains(position.clone()) {
             let shifted_position = position.clone() - rect.position();
-            let terminal_info = process.terminal_info.lock().await;
+            let mut terminal_info = process.terminal_info.lock().await;
             let mouse_mode = terminal_info.mouse_protocol_mode();
+            let mut focus_click = false;
             if is_press {
+                let active_id = state.active_id.load(std::sync::atomic::Ordering::Relaxed);
+                focus_click = active_id != process.span_id;
                 state.set_active_span(process.span_id);
             }
+            let is_left_press = event.kind == MouseEventKind::Down(MouseButton::Left);
+            if is_left_press && mouse_mode == MouseProtocolMode::None {
+                let column = u16::try_from(shifted_position.x - 1);
+                let row = u16::try_from(shifted_position.y - 1);
+                terminal_info.clear_selection();
+                if let (Ok(column), Ok(row)) = (column, row) {
+                    if register_click(state, position.clone()).await {
+                        let separators = { state.config.read().await.word_separators.clone() };
+                        if terminal_info.select_word(row, column, &separators) {
+                            selected_text = terminal_info.selected_text();
+                        }
+                    }
+                }
+                break;
+            }
+            if is_scroll && mouse_mode == MouseProtocolMode::None {
+                let lines = if event.modifiers.intersects(KeyModifiers::SHIFT) {
+                    rect.size().y as isize
+                } else {
+                    state.config.read().await.scroll_lines as isize
+                };
+                let direction = if button == 64 { 1 } else { -1 };
+                terminal_info.scroll_by(lines * direction);
+                break;
+            }
             let mut should_write = false;
+            if process.read_only {
+                break;
+            }
+            if focus_click && state.config.read().await.focus_click_swallow {
+                if let MouseEventKind::Down(button) = event.kind {
+                    *state.swallowed_button.lock().await = Some(button);
+                }
+                break;
+            }
             match mouse_mode {
                 MouseProtocolMode::None => {}
                 MouseProtocolMode::Press => {
@@ -415,10 +1060,10 @@ async fn handle_mouse_event(
                         let button = 3;
                         let data = format!(
                             "\x1b[M{}{}{}",
-                            char::from_u32((button + LEGACY_MOUSE_MODE_OFFSET) as u32)
+                            char::from_u32(u32::from(button + LEGACY_MOUSE_MODE_OFFSET))
                                 .unwrap_or_default(),
-                            char::from_u32(shifted_position.x as u32).unwrap_or_default(),
-                            char::from_u32(shifted_position.y as u32).unwrap_or_default()
+                            coordinate_to_char(shifted_position.x),
+                            coordinate_to_char(shifted_position.y)
                         );
                         let data = data.as_bytes();
                         let mut stdin = process.stdin.lock().await;
@@ -440,9 +1085,9 @@ async fn handle_mouse_event(
                         let command = if is_release { 'm' } else { 'M' };
                         let data = format!(
                             "\x1b[<{};{};{}{}",
-                            char::from_u32(button as u32).unwrap_or_default(),
-                            char::from_u32(shifted_position.x as u32).unwrap_or_default(),
-                            char::from_u32(shifted_position.y as u32).unwrap_or_default(),
+                            char::from_u32(u32::from(button)).unwrap_or_default(),
+                            coordinate_to_char(shifted_position.x),
+                            coordinate_to_char(shifted_position.y),
                             command
                         );
                         let data = data.as_bytes();
@@ -455,6 +1100,12 @@ async fn handle_mouse_event(
             break;
         }
     }
+    if let Some(text) = selected_text {
+        if let Err(err) = copy_to_clipboard(state, &text).await {
+            tracing::warn!("Could not copy the selection: {:?}", err);
+            state.flash(format!("Copy failed: {:#}", err)).await;
+        }
+    }
 
     Ok(())
 }
@@ -468,11 +1119,21 @@ pub async fn handle_stdin(state: StateContainer) -> anyhow::Result<()> {
                 handle_key_event(state.to_owned(), key).await?;
                 trigger_draw(&state).await;
             }
+            if let Some(Ok(Event::Paste(ref text))) = maybe_event {
+                handle_paste(state.to_owned(), text.clone()).await?;
+                trigger_draw(&state).await;
+            }
             if let Some(Ok(Event::Resize(x, y))) = maybe_event {
                 state.set_size((x, y)).await;
                 trigger_draw(&state).await;
             }
             if let Some(Ok(Event::Mouse(event))) = maybe_event {
+                if !state
+                    .mouse_enabled
+                    .load(std::sync::atomic::Ordering::Relaxed)
+                {
+                    continue;
+                }
                 state.set_mouse_position((event.column, event.row)).await;
                 handle_mouse_event(&state, event).await?;
                 trigger_draw(&state).await;
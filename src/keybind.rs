@@ -0,0 +1,321 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton};
+
+use crate::config::{Launcher, SendString};
+
+/// A key together with the modifiers that have to be held for it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        KeyBinding { code, modifiers }
+    }
+    /// Parses bindings like `ctrl+b` or `alt+shift+x`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = value.split('+').collect();
+        let key = parts.pop()?;
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next()?, chars.next()) {
+            (c, None) => KeyCode::Char(c),
+            _ => return None,
+        };
+
+        Some(KeyBinding { code, modifiers })
+    }
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        event.kind == KeyEventKind::Press
+            && event.code == self.code
+            && event.modifiers == self.modifiers
+    }
+    /// Like `matches`, but other modifiers may be held as well.
+    pub fn matches_loosely(&self, event: &KeyEvent) -> bool {
+        event.kind == KeyEventKind::Press
+            && event.code == self.code
+            && event.modifiers.contains(self.modifiers)
+    }
+}
+
+/// Writes the binding the way `KeyBinding::parse` reads it.
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(c) => write!(f, "{}", c),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Something citymux does in response to a key pressed after the prefix.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Action {
+    ToggleMouse,
+    ToggleReadOnly,
+    /// See `--debug-layout`.
+    ToggleDebugLayout,
+    BreakPane,
+    JoinPane,
+    NextWindow,
+    NewPane,
+    /// Opens a new pane before the active one, see `Config::split_before`.
+    NewPaneBefore,
+    /// Sends SIGINT to the active pane's process.
+    InterruptPane,
+    /// Reopens the most recently closed pane where it was.
+    UndoClose,
+    /// Makes the active pane the main pane, see `main_pane`.
+    ToggleMainPane,
+    /// Empties the active pane's scrollback.
+    ClearHistory,
+    /// Opens a list of the panes to pick one to focus.
+    PickPane,
+    /// Enters `InputMode::Resize`.
+    ResizeMode,
+    /// Shows the active pane alone or brings the layout back, see `zoom`.
+    ToggleZoom,
+    /// Gives panes side by side the same width, see `span::equalize_spans`.
+    EqualizeHorizontal,
+    /// Gives stacked panes the same height.
+    EqualizeVertical,
+    /// Draws every cell of the screen again.
+    Redraw,
+    /// Shows or hides the status bar.
+    ToggleStatusBar,
+    /// Opens a pane listing the key bindings.
+    ShowHelp,
+    /// Closes every pane and exits, after asking when `Config::confirm_kill` is set.
+    Quit,
+    /// Stops citymux and returns to the shell it was started from, like Ctrl-Z
+    /// does for other programs. Ctrl-Z itself goes to the program in the pane.
+    Suspend,
+    /// Opens the launcher at this index of `Config::launchers` in a new pane.
+    Launch(usize),
+    /// Types the string at this index of `Config::send_strings` into the active pane.
+    SendString(usize),
+}
+
+impl Action {
+    /// Parses the names actions are bound by in the config, like `new_pane`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "toggle_mouse" => Some(Action::ToggleMouse),
+            "toggle_read_only" => Some(Action::ToggleReadOnly),
+            "break_pane" => Some(Action::BreakPane),
+            "join_pane" => Some(Action::JoinPane),
+            "next_window" => Some(Action::NextWindow),
+            "new_pane" => Some(Action::NewPane),
+            "new_pane_before" => Some(Action::NewPaneBefore),
+            "interrupt_pane" => Some(Action::InterruptPane),
+            "undo_close" => Some(Action::UndoClose),
+            "toggle_main_pane" => Some(Action::ToggleMainPane),
+            "clear_history" => Some(Action::ClearHistory),
+            "pick_pane" => Some(Action::PickPane),
+            "resize_mode" => Some(Action::ResizeMode),
+            "toggle_zoom" => Some(Action::ToggleZoom),
+            "equalize_horizontal" => Some(Action::EqualizeHorizontal),
+            "equalize_vertical" => Some(Action::EqualizeVertical),
+            "redraw" => Some(Action::Redraw),
+            "toggle_status_bar" => Some(Action::ToggleStatusBar),
+            "quit" => Some(Action::Quit),
+            "suspend" => Some(Action::Suspend),
+            "show_help" => Some(Action::ShowHelp),
+            _ => None,
+        }
+    }
+}
+
+/// The part of a pane a click landed on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MouseArea {
+    /// The top border, where the title is drawn.
+    Title,
+    Content,
+}
+
+/// An action run when a mouse button is pressed over a pane. Clicks of a
+/// bound button aren't forwarded to the program in the pane.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MouseBinding {
+    pub button: MouseButton,
+    /// Only clicks on this part of a pane, or anywhere with `None`.
+    pub area: Option<MouseArea>,
+    pub action: Action,
+}
+
+impl MouseBinding {
+    pub fn matches(&self, button: MouseButton, area: MouseArea) -> bool {
+        self.button == button && self.area.is_none_or(|bound| bound == area)
+    }
+}
+
+/// Looks up the action bound to the key pressed after the prefix. Launcher
+/// keys, then sent strings, take precedence over the built in bindings.
+pub fn prefix_action(
+    event: &KeyEvent,
+    launchers: &[Launcher],
+    send_strings: &[SendString],
+) -> Option<Action> {
+    let launcher = launchers
+        .iter()
+        .position(|launcher| launcher.key == Some(event.code));
+    if let Some(index) = launcher {
+        return Some(Action::Launch(index));
+    }
+    let send_string = send_strings
+        .iter()
+        .position(|send_string| send_string.key == event.code);
+    if let Some(index) = send_string {
+        return Some(Action::SendString(index));
+    }
+
+    builtin_prefix_action(event.code)
+}
+
+/// The built in bindings after the prefix, in the order the help pane lists
+/// them.
+const PREFIX_BINDINGS: &[(KeyCode, Action, &str)] = &[
+    (KeyCode::Char('n'), Action::NewPane, "Open a new pane"),
+    (
+        KeyCode::Char('N'),
+        Action::NewPaneBefore,
+        "Open a new pane before this one",
+    ),
+    (
+        KeyCode::Char('u'),
+        Action::UndoClose,
+        "Reopen the last closed pane",
+    ),
+    (
+        KeyCode::Char('!'),
+        Action::BreakPane,
+        "Move the pane to a new window",
+    ),
+    (
+        KeyCode::Char('J'),
+        Action::JoinPane,
+        "Move the pane back from a window",
+    ),
+    (KeyCode::Tab, Action::NextWindow, "Show the next window"),
+    (KeyCode::Char('w'), Action::PickPane, "Pick a pane to focus"),
+    (
+        KeyCode::Char('R'),
+        Action::ResizeMode,
+        "Resize the pane with the arrow keys",
+    ),
+    (
+        KeyCode::Char('z'),
+        Action::ToggleZoom,
+        "Zoom the pane or restore the layout",
+    ),
+    (
+        KeyCode::Char('|'),
+        Action::EqualizeHorizontal,
+        "Even out the widths of panes side by side",
+    ),
+    (
+        KeyCode::Char('_'),
+        Action::EqualizeVertical,
+        "Even out the heights of stacked panes",
+    ),
+    (
+        KeyCode::Char('M'),
+        Action::ToggleMainPane,
+        "Toggle the main pane",
+    ),
+    (
+        KeyCode::Char('I'),
+        Action::InterruptPane,
+        "Interrupt the pane's program",
+    ),
+    (
+        KeyCode::Char('r'),
+        Action::ToggleReadOnly,
+        "Toggle read-only",
+    ),
+    (
+        KeyCode::Char('m'),
+        Action::ToggleMouse,
+        "Toggle mouse capture",
+    ),
+    (
+        KeyCode::Char('C'),
+        Action::ClearHistory,
+        "Clear the pane's history",
+    ),
+    (
+        KeyCode::Char('b'),
+        Action::ToggleStatusBar,
+        "Toggle the status bar",
+    ),
+    (KeyCode::Char('l'), Action::Redraw, "Redraw the screen"),
+    (
+        KeyCode::Char('D'),
+        Action::ToggleDebugLayout,
+        "Toggle the layout debug view",
+    ),
+    (KeyCode::Char('Q'), Action::Quit, "Quit"),
+    (KeyCode::Char('Z'), Action::Suspend, "Suspend citymux"),
+    (KeyCode::Char('?'), Action::ShowHelp, "Show this help"),
+];
+
+/// The action a key pressed after the prefix maps to without any launchers or
+/// sent strings configured.
+pub fn builtin_prefix_action(code: KeyCode) -> Option<Action> {
+    PREFIX_BINDINGS
+        .iter()
+        .find(|(key, _, _)| *key == code)
+        .map(|(_, action, _)| *action)
+}
+
+/// Lists the bindings after the prefix, launchers and sent strings included.
+pub fn help_text(
+    prefix: &KeyBinding,
+    launchers: &[Launcher],
+    send_strings: &[SendString],
+) -> String {
+    let mut text = format!("Keys after the prefix, {}:\r\n\r\n", prefix);
+    for (code, action, description) in PREFIX_BINDINGS {
+        if prefix_action(&KeyEvent::from(*code), launchers, send_strings) != Some(*action) {
+            continue;
+        }
+        let key = match code {
+            KeyCode::Char(c) => c.to_string(),
+            code => format!("{:?}", code),
+        };
+        text.push_str(&format!("  {:<4} {}\r\n", key, description));
+    }
+    text.push_str(&format!("  {:<4} Send {} to the pane\r\n", prefix, prefix));
+    for launcher in launchers {
+        if let Some(KeyCode::Char(key)) = launcher.key {
+            text.push_str(&format!("  {:<4} Launch {}\r\n", key, launcher.name));
+        }
+    }
+    for send_string in send_strings {
+        if let KeyCode::Char(key) = send_string.key {
+            let literal = String::from_utf8_lossy(&send_string.bytes);
+            text.push_str(&format!("  {:<4} Send {:?}\r\n", key, literal));
+        }
+    }
+
+    text
+}
@@ -95,3 +95,101 @@ pub fn get_span_dimensions(
 
     None
 }
+
+/// Returns the dimensions of a pane when `gap` empty cells are left between
+/// panes and around the edge of the screen.
+pub fn get_pane_dimensions(
+    root: &Node,
+    span_id: usize,
+    size: Vector2,
+    gap: impl Into<i32>,
+) -> Option<Rect> {
+    let gap: i32 = gap.into();
+    let gap = Vector2::new(gap, gap);
+    let outer = Rect::new(gap.clone(), size - gap.clone());
+    let rect = get_span_dimensions(root, span_id, outer)?;
+
+    Some(Rect::new(rect.position(), rect.size() - gap))
+}
+
+/// The canonical order of the panes in a layout: top to bottom, then left to
+/// right, by where they are on screen. Panes are numbered, listed and cycled
+/// through in this order.
+pub fn pane_order(root: &Node, size: Vector2, gap: impl Into<i32>) -> Vec<usize> {
+    let gap: i32 = gap.into();
+    let mut panes: Vec<(usize, Vector2)> = root
+        .leaf_ids()
+        .into_iter()
+        .map(|id| {
+            let position = get_pane_dimensions(root, id, size.clone(), gap)
+                .map(|rect| rect.position())
+                .unwrap_or_default();
+            (id, position)
+        })
+        .collect();
+    panes.sort_by_key(|(_, position)| (position.y, position.x));
+
+    panes.into_iter().map(|(id, _)| id).collect()
+}
+
+/// Draws the panes of a layout `size` cells large as ASCII boxes sharing
+/// their borders, each labelled with its id and size. Meant for bug reports,
+/// so it only depends on the tree and not on what the panes show.
+pub fn layout_diagram(root: &Node, size: Vector2) -> String {
+    let width = size.x.max(0) as usize + 1;
+    let height = size.y.max(0) as usize + 1;
+    let mut grid = vec![vec![' '; width]; height];
+    let rects: Vec<(usize, Rect)> = root
+        .leaf_ids()
+        .into_iter()
+        .filter_map(|id| {
+            let rect = get_span_dimensions(root, id, Rect::new(Vector2::null(), size.clone()))?;
+            Some((id, rect))
+        })
+        .collect();
+
+    // A rect reaching past the grid, as a span child with a negative size
+    // gives, is clamped to it, so a bad tree can't panic the control handler.
+    let clamp = |value: i32, max: usize| value.clamp(0, max as i32) as usize;
+    let rects: Vec<(usize, usize, usize, usize, usize)> = rects
+        .into_iter()
+        .map(|(id, rect)| {
+            let x = clamp(rect.position().x, width - 1);
+            let y = clamp(rect.position().y, height - 1);
+            let right = clamp(rect.position().x + rect.size().x, width - 1);
+            let bottom = clamp(rect.position().y + rect.size().y, height - 1);
+            (id, x, y, right.max(x) - x, bottom.max(y) - y)
+        })
+        .collect();
+    let mut set = |column: usize, row: usize, c: char| {
+        if let Some(cell) = grid.get_mut(row).and_then(|row| row.get_mut(column)) {
+            *cell = c;
+        }
+    };
+
+    for &(_, x, y, w, h) in &rects {
+        for column in x..=x + w {
+            set(column, y, '-');
+            set(column, y + h, '-');
+        }
+        for row in y..=y + h {
+            set(x, row, '|');
+            set(x + w, row, '|');
+        }
+    }
+    for &(id, x, y, w, h) in &rects {
+        for (column, row) in [(x, y), (x + w, y), (x, y + h), (x + w, y + h)] {
+            set(column, row, '+');
+        }
+        let labels = [id.to_string(), format!("{}x{}", w, h)];
+        for (line, label) in labels.iter().enumerate().take(h.saturating_sub(1)) {
+            for (offset, c) in label.chars().take(w.saturating_sub(1)).enumerate() {
+                set(x + 1 + offset, y + 1 + line, c);
+            }
+        }
+    }
+
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>() + "\n")
+        .collect()
+}
@@ -1,29 +1,44 @@
 use std::{env, fs::OpenOptions};
 
 use args::CliArgs;
-use config::get_config;
+use config::{check_config, get_default_config, load_config};
 use data_encoding::BASE32HEX_NOPAD;
 use error::trace_error;
-use exit::exit;
+use exit::{exit, restore_after_panic};
 use startup::run_application;
 use state::{State, StateContainer};
 use tokio::io::{self};
 use tty::TtyParameters;
 
+#[cfg(test)]
+mod test;
+
 mod args;
+mod clipboard;
 mod config;
+mod control;
 mod draw;
 mod encoding;
 mod error;
 mod escape_codes;
 mod exit;
+mod hooks;
 mod input;
+mod keybind;
 mod layout;
+mod main_pane;
+mod metrics;
+mod mode;
+mod picker;
 mod process;
+mod project;
+mod scan;
+mod signal;
 mod size;
 mod span;
 mod spawn;
 mod startup;
+mod status;
 mod state;
 mod term;
 mod terminal;
@@ -32,8 +47,18 @@ mod tty;
 mod tty_windows;
 #[cfg(unix)]
 mod tty_unix;
+mod virtual_process;
+mod window;
+mod zoom;
 
 async fn run_multiplexer() -> anyhow::Result<()> {
+    // The hook is process wide, so it also covers panics inside tokio worker threads.
+    std::panic::set_hook(Box::new(move |info| {
+        restore_after_panic();
+        tracing::error!("Panic at {:?}: {:?}", info.location(), info.payload());
+        std::process::exit(1);
+    }));
+
     let args = CliArgs::parse();
 
     if args.enable_logging && args.log_file.is_some() {
@@ -57,16 +82,27 @@ async fn run_multiplexer() -> anyhow::Result<()> {
         tracing::subscriber::set_global_default(subscriber)?;
     }
 
+    if args.check_config {
+        std::process::exit(if check_config() { 0 } else { 1 });
+    }
+
     tracing::info!("Starting up");
-    let config = get_config();
+    let (config, config_error) = match load_config() {
+        Ok(config) => (config, None),
+        Err(err) if args.strict_config => return Err(err),
+        Err(err) => {
+            tracing::error!("Using the default config: {:?}", err);
+            (get_default_config(), Some(err))
+        }
+    };
     tracing::debug!("Current config: {:?}", config);
 
-    std::panic::set_hook(Box::new(move |info| {
-        tracing::error!("Panic at {:?}: {:?}", info.location(), info.payload());
-        exit(1);
-    }));
-
     let state_container = StateContainer::new(State::new(args, config, io::stdin(), io::stdout()));
+    if let Some(err) = config_error {
+        state_container
+            .flash(format!("Config error, using defaults: {:#}", err))
+            .await;
+    }
     if let Err(e) = run_application(state_container).await {
         trace_error("in application", &e);
         exit(1);
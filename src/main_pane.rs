@@ -0,0 +1,213 @@
+use std::sync::atomic::Ordering;
+
+use crate::{
+    span::{take_node, Node, NodeData, Span, SpanChild, SpanDirection},
+    state::StateContainer,
+    zoom::unzoom,
+};
+
+/// Share of the width kept by the main pane.
+const MAIN_PANE_SIZE: f64 = 0.6;
+
+/// Builds a layout with the main pane on the left and the other panes stacked
+/// on top of each other to its right. Ids for new spans come from `next_id`.
+pub fn main_pane_layout(
+    main_id: usize,
+    stack_ids: &[usize],
+    mut next_id: impl FnMut() -> usize,
+) -> Node {
+    let main = Node::new(main_id, NodeData::Void);
+    let stack = match stack_ids {
+        [] => return main,
+        [id] => Node::new(*id, NodeData::Void),
+        ids => {
+            let mut span = Span::new(SpanDirection::Vertical);
+            for id in ids {
+                span.children
+                    .push(SpanChild::new(Node::new(*id, NodeData::Void)));
+            }
+            Node::new(next_id(), NodeData::Span(span))
+        }
+    };
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(main).with_size(MAIN_PANE_SIZE));
+    span.children
+        .push(SpanChild::new(stack).with_size(1.0 - MAIN_PANE_SIZE));
+
+    Node::new(next_id(), NodeData::Span(span))
+}
+
+/// Copies the sizes of `previous` over to `layout` where it already had the
+/// main pane layout: the split between the main pane and the stack, and the
+/// size of every pane still in the stack. Panes new to the stack get the
+/// average size of the others.
+fn keep_main_pane_sizes(previous: &Node, layout: &mut Node, main_id: usize) {
+    let (NodeData::Span(previous), NodeData::Span(span)) = (&previous.data, &mut layout.data)
+    else {
+        return;
+    };
+    let [previous_main, previous_stack] = previous.children.as_slice() else {
+        return;
+    };
+    if previous.direction != SpanDirection::Horizontal || previous_main.node.id != main_id {
+        return;
+    }
+    for (child, previous) in span.children.iter_mut().zip(&previous.children) {
+        child.size = previous.size;
+    }
+
+    let (NodeData::Span(previous_stack), Some(SpanChild { node: stack, .. })) =
+        (&previous_stack.node.data, span.children.get_mut(1))
+    else {
+        return;
+    };
+    let NodeData::Span(stack) = &mut stack.data else {
+        return;
+    };
+    let kept = |id: usize| {
+        previous_stack
+            .children
+            .iter()
+            .find(|child| child.node.id == id && matches!(child.node.data, NodeData::Void))
+            .map(|child| child.size)
+    };
+    let kept_sizes: Vec<f64> = stack
+        .children
+        .iter()
+        .filter_map(|child| kept(child.node.id))
+        .collect();
+    if kept_sizes.is_empty() {
+        return;
+    }
+    let average = kept_sizes.iter().sum::<f64>() / kept_sizes.len() as f64;
+    for child in &mut stack.children {
+        child.size = kept(child.node.id).unwrap_or(average);
+    }
+}
+
+/// Brings `previous` in step with the panes in `leaf_ids`: panes closed since
+/// are dropped from it, and panes opened since are added to the end of its
+/// top span. Ids for new spans come from `next_id`.
+pub fn restored_layout(
+    mut previous: Option<Node>,
+    leaf_ids: &[usize],
+    mut next_id: impl FnMut() -> usize,
+) -> Option<Node> {
+    let previous_ids = previous.as_ref().map(Node::leaf_ids).unwrap_or_default();
+    for id in previous_ids.iter().filter(|id| !leaf_ids.contains(id)) {
+        take_node(&mut previous, *id);
+    }
+    for id in leaf_ids.iter().filter(|id| !previous_ids.contains(id)) {
+        let node = Node::new(*id, NodeData::Void);
+        let Some(mut root) = previous.take() else {
+            previous = Some(node);
+            continue;
+        };
+        if !matches!(root.data, NodeData::Span(_)) {
+            let mut span = Span::new(SpanDirection::Horizontal);
+            span.children.push(SpanChild::new(root));
+            root = Node::new(next_id(), NodeData::Span(span));
+        }
+        if let NodeData::Span(span) = &mut root.data {
+            let size = span.children.iter().map(|child| child.size).sum::<f64>()
+                / span.children.len().max(1) as f64;
+            span.children.push(SpanChild::new(node).with_size(size));
+        }
+        previous = Some(root);
+    }
+
+    previous
+}
+
+/// The main pane, if one is set and it is part of the layout on screen.
+pub async fn current_main_pane(state_container: &StateContainer) -> Option<usize> {
+    let main_id = (*state_container.main_pane.read().await)?;
+    let root = state_container.root_node.read().await;
+    root.as_ref()?
+        .leaf_ids()
+        .contains(&main_id)
+        .then_some(main_id)
+}
+
+/// Lays the current layout out around the main pane, with `new_ids` added to
+/// the end of the stack. Sizes the user gave the panes are kept where the
+/// layout already was built around the main pane.
+async fn relayout(state_container: &StateContainer, main_id: usize, new_ids: &[usize]) {
+    let mut root = state_container.root_node.write().await;
+    let mut stack_ids: Vec<usize> = root
+        .as_ref()
+        .map(Node::leaf_ids)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|id| *id != main_id)
+        .collect();
+    stack_ids.extend(new_ids);
+    let next_id = || {
+        state_container
+            .span_id_counter
+            .fetch_add(1, Ordering::Relaxed)
+            + 1
+    };
+    let mut layout = main_pane_layout(main_id, &stack_ids, next_id);
+    if let Some(previous) = root.as_ref() {
+        keep_main_pane_sizes(previous, &mut layout, main_id);
+    }
+    *root = Some(layout);
+}
+
+/// Adds the panes of `node` to the bottom of the stack and makes the first
+/// of them active, returning its id.
+pub async fn push_to_stack(
+    state_container: &StateContainer,
+    main_id: usize,
+    node: Node,
+) -> anyhow::Result<usize> {
+    let new_ids = node.leaf_ids();
+    let Some(&new_id) = new_ids.first() else {
+        return Err(anyhow::format_err!("Node {} has no panes", node.id));
+    };
+    relayout(state_container, main_id, &new_ids).await;
+    state_container.set_active_span(new_id);
+
+    Ok(new_id)
+}
+
+/// Puts back the layout saved when the main pane was set, with the panes
+/// opened and closed since then added and removed.
+async fn restore_layout(state_container: &StateContainer) {
+    let Some(previous) = state_container.layout_before_main_pane.lock().await.take() else {
+        return;
+    };
+    let mut root = state_container.root_node.write().await;
+    let leaf_ids = root.as_ref().map(Node::leaf_ids).unwrap_or_default();
+    let next_id = || {
+        state_container
+            .span_id_counter
+            .fetch_add(1, Ordering::Relaxed)
+            + 1
+    };
+    *root = restored_layout(Some(previous), &leaf_ids, next_id);
+}
+
+/// Makes the active pane the main pane, or clears the main pane when it
+/// already is. The layout from before is kept in
+/// `State::layout_before_main_pane` and restored when it is cleared.
+pub async fn toggle_main_pane(state_container: &StateContainer) {
+    unzoom(state_container).await;
+    let active_id = state_container.active_id.load(Ordering::Relaxed);
+    let main_id = current_main_pane(state_container).await;
+    if main_id == Some(active_id) {
+        *state_container.main_pane.write().await = None;
+        restore_layout(state_container).await;
+        state_container.flash("Main pane cleared").await;
+        return;
+    }
+    if main_id.is_none() {
+        let root = state_container.root_node.read().await.clone();
+        *state_container.layout_before_main_pane.lock().await = root;
+    }
+    *state_container.main_pane.write().await = Some(active_id);
+    relayout(state_container, active_id, &[]).await;
+    state_container.flash("Pane is now the main pane").await;
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+/// Weight of the newest frame in the rolling averages.
+const SMOOTHING: f64 = 0.1;
+
+/// Rolling averages over the frames drawn, recorded with `--debug-metrics`.
+#[derive(Debug, Default, Clone)]
+pub struct RenderMetrics {
+    frames: u64,
+    average_duration: f64,
+    average_bytes: f64,
+    average_changed_cells: f64,
+}
+
+impl RenderMetrics {
+    pub fn record(&mut self, duration: Duration, bytes: usize, changed_cells: usize) {
+        let average = |average: f64, value: f64, frames: u64| {
+            if frames == 0 {
+                value
+            } else {
+                average + (value - average) * SMOOTHING
+            }
+        };
+        self.average_duration = average(self.average_duration, duration.as_secs_f64(), self.frames);
+        self.average_bytes = average(self.average_bytes, bytes as f64, self.frames);
+        self.average_changed_cells = average(
+            self.average_changed_cells,
+            changed_cells as f64,
+            self.frames,
+        );
+        self.frames += 1;
+    }
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+    pub fn average_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.average_duration)
+    }
+    pub fn average_bytes(&self) -> f64 {
+        self.average_bytes
+    }
+    pub fn average_changed_cells(&self) -> f64 {
+        self.average_changed_cells
+    }
+}
@@ -0,0 +1,123 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseEvent};
+
+use crate::{
+    draw::trigger_draw,
+    size::resize_panes,
+    span::{resize_pane, SpanDirection},
+    state::StateContainer,
+};
+
+/// Decides where input goes. In every mode but `Normal` keys and mouse
+/// events go to the mode first, see `dispatch_key` and `dispatch_mouse`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    /// Keys go to the active pane, apart from the prefix and shortcuts.
+    #[default]
+    Normal,
+    /// The arrow keys resize the active pane until Escape is pressed.
+    Resize,
+    /// Asks whether to close every pane and exit, see `Config::confirm_kill`.
+    ConfirmQuit,
+}
+
+/// What a mode did with an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeResponse {
+    /// The mode took the event without changing what is on screen.
+    Consumed,
+    /// The mode took the event and the screen has to be drawn again.
+    Redraw,
+    /// The event is handled as it would be in `InputMode::Normal`.
+    Ignored,
+}
+
+async fn respond(state_container: &StateContainer, response: ModeResponse) -> bool {
+    match response {
+        ModeResponse::Consumed => true,
+        ModeResponse::Redraw => {
+            trigger_draw(state_container).await;
+            true
+        }
+        ModeResponse::Ignored => false,
+    }
+}
+
+/// Hands a key to the current mode, returns whether the mode took it.
+pub async fn dispatch_key(
+    state_container: &StateContainer,
+    event: KeyEvent,
+) -> anyhow::Result<bool> {
+    let response = match state_container.input_mode().await {
+        InputMode::Normal => ModeResponse::Ignored,
+        InputMode::Resize => handle_resize_key(state_container, event).await?,
+        InputMode::ConfirmQuit => handle_quit_key(state_container, event).await,
+    };
+
+    Ok(respond(state_container, response).await)
+}
+
+/// Hands a mouse event to the current mode, returns whether the mode took
+/// it. None of the modes use the mouse, so it is ignored until they end.
+pub async fn dispatch_mouse(state_container: &StateContainer, _event: MouseEvent) -> bool {
+    let response = match state_container.input_mode().await {
+        InputMode::Normal => ModeResponse::Ignored,
+        InputMode::Resize | InputMode::ConfirmQuit => ModeResponse::Consumed,
+    };
+
+    respond(state_container, response).await
+}
+
+/// How much of its span the active pane grows or shrinks by per arrow key in
+/// resize mode.
+const RESIZE_STEP: f64 = 0.05;
+
+/// Right and Down grow the active pane, Left and Up shrink it, and Escape or
+/// Enter leave the mode.
+async fn handle_resize_key(
+    state_container: &StateContainer,
+    event: KeyEvent,
+) -> anyhow::Result<ModeResponse> {
+    if event.kind == KeyEventKind::Release {
+        return Ok(ModeResponse::Consumed);
+    }
+    let (direction, step) = match event.code {
+        KeyCode::Esc | KeyCode::Enter => {
+            state_container.set_input_mode(InputMode::Normal).await;
+            return Ok(ModeResponse::Redraw);
+        }
+        KeyCode::Left => (SpanDirection::Horizontal, -RESIZE_STEP),
+        KeyCode::Right => (SpanDirection::Horizontal, RESIZE_STEP),
+        KeyCode::Up => (SpanDirection::Vertical, -RESIZE_STEP),
+        KeyCode::Down => (SpanDirection::Vertical, RESIZE_STEP),
+        _ => return Ok(ModeResponse::Consumed),
+    };
+    let active_id = state_container
+        .active_id
+        .load(std::sync::atomic::Ordering::Relaxed);
+    let resized = {
+        let mut root = state_container.root_node.write().await;
+        root.as_mut()
+            .is_some_and(|root| resize_pane(root, active_id, direction, step))
+    };
+    if !resized {
+        return Ok(ModeResponse::Consumed);
+    }
+    resize_panes(state_container).await?;
+
+    Ok(ModeResponse::Redraw)
+}
+
+/// `y` or Enter quits, any other key cancels.
+async fn handle_quit_key(state_container: &StateContainer, event: KeyEvent) -> ModeResponse {
+    if event.kind != KeyEventKind::Press {
+        return ModeResponse::Consumed;
+    }
+    state_container.set_input_mode(InputMode::Normal).await;
+    if matches!(event.code, KeyCode::Char('y') | KeyCode::Enter) {
+        state_container.shutdown.notify_one();
+    } else {
+        state_container.flash("Quit cancelled").await;
+    }
+
+    ModeResponse::Redraw
+}
@@ -0,0 +1,161 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use renterm::{
+    canvas::Canvas, cell::Cell, color::Color, rect::Rect, style::Style, surface::Surface,
+    text::DrawableStr, vector::Vector2,
+};
+
+use crate::{
+    draw::{find_process_by_id, inverted_style, trigger_draw},
+    state::StateContainer,
+};
+
+/// The overlay opened with prefix + `w`, listing panes to focus.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PanePicker {
+    /// What was typed so far, panes whose label doesn't match it are hidden.
+    pub filter: String,
+    /// Index into the panes that match the filter.
+    pub selected: usize,
+}
+
+/// A pane as listed in the picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickerEntry {
+    pub span_id: usize,
+    pub label: String,
+}
+
+/// Whether the characters of `filter` appear in `label` in order, ignoring case.
+pub fn fuzzy_match(filter: &str, label: &str) -> bool {
+    let mut label = label.chars().flat_map(char::to_lowercase);
+    filter
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|wanted| label.any(|c| c == wanted))
+}
+
+/// The panes on screen, labelled with their title and command.
+pub async fn pane_entries(state_container: &StateContainer) -> Vec<PickerEntry> {
+    let leaf_ids = match state_container.root_node.read().await.as_ref() {
+        Some(root) => root.leaf_ids(),
+        None => return Vec::new(),
+    };
+    let mut entries = Vec::new();
+    for span_id in leaf_ids {
+        let Some(process) = find_process_by_id(state_container.clone(), span_id).await else {
+            continue;
+        };
+        let process = process.read().await;
+        let title = process.terminal_info.lock().await.title();
+        let command = process.command.command_line();
+        let label = if title.is_empty() || title == command {
+            format!("{}: {}", span_id, command)
+        } else {
+            format!("{}: {} ({})", span_id, title, command)
+        };
+        entries.push(PickerEntry { span_id, label });
+    }
+
+    entries
+}
+
+/// The entries matching the picker's filter.
+pub fn filter_entries(picker: &PanePicker, entries: Vec<PickerEntry>) -> Vec<PickerEntry> {
+    entries
+        .into_iter()
+        .filter(|entry| fuzzy_match(&picker.filter, &entry.label))
+        .collect()
+}
+
+pub async fn open_pane_picker(state_container: &StateContainer) {
+    *state_container.pane_picker.lock().await = Some(PanePicker::default());
+    trigger_draw(state_container).await;
+}
+
+/// Handles keys while the picker is open. Typing filters the panes, the
+/// arrow keys move the selection, Enter focuses the selected pane and
+/// Escape closes the picker. Returns whether the picker took the key.
+pub async fn handle_picker_key(
+    state_container: &StateContainer,
+    event: KeyEvent,
+) -> anyhow::Result<bool> {
+    let Some(mut picker) = state_container.pane_picker.lock().await.clone() else {
+        return Ok(false);
+    };
+    if event.kind != KeyEventKind::Press {
+        return Ok(true);
+    }
+    let entries = filter_entries(&picker, pane_entries(state_container).await);
+    let mut close = false;
+    match event.code {
+        KeyCode::Esc => close = true,
+        KeyCode::Enter => {
+            close = true;
+            match entries.get(picker.selected) {
+                Some(entry) => state_container.set_active_span(entry.span_id),
+                None => state_container.flash("No matching pane").await,
+            }
+        }
+        KeyCode::Backspace => {
+            picker.filter.pop();
+            picker.selected = 0;
+        }
+        KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+        KeyCode::Down => {
+            picker.selected = (picker.selected + 1).min(entries.len().saturating_sub(1));
+        }
+        KeyCode::Char(c) => {
+            picker.filter.push(c);
+            picker.selected = 0;
+        }
+        _ => {}
+    }
+    *state_container.pane_picker.lock().await = (!close).then_some(picker);
+    trigger_draw(state_container).await;
+
+    Ok(true)
+}
+
+/// Draws the picker as a box in the middle of the screen, the filter on its
+/// first row and the matching panes below it.
+pub fn draw_pane_picker(canvas: &mut Canvas, picker: &PanePicker, entries: &[PickerEntry]) {
+    let size = canvas.size();
+    let width = 40.min(size.x);
+    let rows = (entries.len().max(1) as i32 + 1).min(size.y);
+    let position = Vector2::new((size.x - width) / 2, (size.y - rows) / 2);
+    let style = Style::default()
+        .with_background_color(Color::new_one_byte(8 + 6))
+        .with_foreground_color(Color::new_one_byte(0));
+    for y in 0..rows {
+        for x in 0..width {
+            let cell = Cell::new_styled(" ", style.clone());
+            canvas.set_cell(position.clone() + Vector2::new(x, y), cell);
+        }
+    }
+    let row_rect = |row: i32| {
+        Rect::new(
+            position.clone() + Vector2::new(0, row),
+            Vector2::new(width, 1),
+        )
+    };
+    let filter = format!("> {}", picker.filter);
+    canvas.draw_in(
+        &DrawableStr::truncated(&filter, style.clone(), true),
+        row_rect(0),
+    );
+    if entries.is_empty() {
+        let empty = DrawableStr::truncated("  No matching panes", style, true);
+        canvas.draw_in(&empty, row_rect(1));
+        return;
+    }
+    for (index, entry) in entries.iter().enumerate().take((rows - 1).max(0) as usize) {
+        let label = format!("  {}", entry.label);
+        let entry_style = if index == picker.selected {
+            inverted_style(&style)
+        } else {
+            style.clone()
+        };
+        let label = DrawableStr::truncated(&label, entry_style, true);
+        canvas.draw_in(&label, row_rect(index as i32 + 1));
+    }
+}
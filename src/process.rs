@@ -2,8 +2,10 @@ use renterm::vector::Vector2;
 use std::fmt::Display;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use tokio::io::{AsyncReadExt, ReadBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, ReadBuf};
 
 use tokio::sync::{Mutex, RwLock};
 use tokio::task::JoinError;
@@ -12,10 +14,26 @@ use crate::draw::trigger_draw;
 use crate::spawn::kill_span;
 use crate::state::{Process, StateContainer};
 
+/// A command that runs `command` through the shell: `cmd /C` on Windows and
+/// `sh -c` elsewhere.
+pub fn shell_command(command: &str) -> tokio::process::Command {
+    let (shell, flag) = if cfg!(target_os = "windows") {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut process = tokio::process::Command::new(shell);
+    process.arg(flag).arg(command);
+
+    process
+}
+
 pub struct ProcessData {
     pub stdin: Box<dyn tokio::io::AsyncWrite + Unpin + Send + Sync>,
     pub stdout: Box<dyn tokio::io::AsyncRead + Unpin + Send + Sync>,
     pub terminal: Box<dyn TerminalLike>,
+    /// Id of the child process, as assigned by the OS.
+    pub pid: u32,
 }
 
 pub trait TerminalLike: Send + Sync {
@@ -42,6 +60,13 @@ impl From<Box<dyn std::error::Error + Send + Sync>> for TerminalError {
         TerminalError { error }
     }
 }
+impl From<String> for TerminalError {
+    fn from(message: String) -> Self {
+        TerminalError {
+            error: message.into(),
+        }
+    }
+}
 impl From<JoinError> for TerminalError {
     fn from(error: JoinError) -> Self {
         TerminalError {
@@ -62,6 +87,76 @@ impl Display for TerminalError {
     }
 }
 
+/// Holds back a multibyte UTF-8 character that was cut off at the end of a
+/// read, so that it reaches the parser in one piece along with the next read.
+#[derive(Debug, Default)]
+pub struct Utf8Carryover {
+    pending: Vec<u8>,
+}
+
+impl Utf8Carryover {
+    /// Returns the bytes that can be parsed now, keeping back a trailing
+    /// incomplete character. Invalid sequences are passed through as they are.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(bytes);
+        let split = incomplete_suffix_start(&data);
+        self.pending = data.split_off(split);
+
+        data
+    }
+}
+
+fn incomplete_suffix_start(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let index = bytes.len() - back;
+        let byte = bytes[index];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if back < needed { index } else { bytes.len() };
+    }
+
+    bytes.len()
+}
+
+/// Shortest time between two bells forwarded to the host terminal.
+const BELL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Rings the host terminal's bell for a bell in a background pane, so that it
+/// can mark the window as urgent, when `Config::forward_bell` is set. Returns
+/// whether the bell was forwarded, at most once per `BELL_INTERVAL`.
+pub async fn forward_bell(
+    state_container: &StateContainer,
+    span_id: usize,
+) -> anyhow::Result<bool> {
+    let forward_bell = { state_container.config.read().await.forward_bell };
+    let active_id = state_container.active_id.load(Ordering::Relaxed);
+    if !forward_bell || span_id == active_id {
+        return Ok(false);
+    }
+    {
+        let mut last_bell = state_container.last_forwarded_bell.lock().await;
+        let now = Instant::now();
+        if last_bell.is_some_and(|at| now.duration_since(at) < BELL_INTERVAL) {
+            return Ok(false);
+        }
+        *last_bell = Some(now);
+    }
+    let stdout = state_container.stdout.clone();
+    let mut stdout = stdout.lock().await;
+    stdout.write_all(b"\x07").await?;
+    stdout.flush().await?;
+
+    Ok(true)
+}
+
 pub async fn handle_process(
     state_container: StateContainer,
     process: Arc<RwLock<Process>>,
@@ -87,8 +182,29 @@ pub async fn handle_process(
             }
             {
                 let process = process.read().await;
-                let mut canvas = process.terminal_info.lock().await;
-                canvas.process(filled_buf);
+                let data = process.output_carryover.lock().await.feed(filled_buf);
+                let (replies, bell) = {
+                    let mut canvas = process.terminal_info.lock().await;
+                    canvas.process(&data);
+                    process.has_output.store(true, Ordering::Relaxed);
+                    (canvas.take_replies(), canvas.take_bell())
+                };
+                if bell {
+                    if let Err(err) = forward_bell(&state_container, process.span_id).await {
+                        tracing::debug!("Could not forward a bell: {:?}", err);
+                    }
+                }
+                if !replies.is_empty() {
+                    let mut stdin = process.stdin.lock().await;
+                    let result = async {
+                        stdin.write_all(&replies).await?;
+                        stdin.flush().await
+                    }
+                    .await;
+                    if let Err(err) = result {
+                        tracing::debug!("Could not reply to a query: {:?}", err);
+                    }
+                }
             }
             trigger_draw(&state_container).await;
         }
@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::{
+    config::split_command_line,
+    span::{take_node, Node, NodeData, Span, SpanChild, SpanDirection},
+    spawn::{create_process, spawn_in_pane},
+    state::{PaneCommand, StateContainer},
+};
+
+/// Name of the project file looked for in the directory citymux starts in
+/// and the ones above it, up to the home directory or the repository root.
+pub const PROJECT_FILE_NAME: &str = ".citymux.kdl";
+
+/// The layout declared in a project file.
+#[derive(Debug, Clone)]
+pub enum ProjectNode {
+    /// A pane running `command`, or the default shell without one.
+    Pane {
+        command: Option<String>,
+        title: Option<String>,
+    },
+    /// Panes side by side, each child with its share of the space.
+    Span {
+        direction: SpanDirection,
+        children: Vec<(f64, ProjectNode)>,
+    },
+}
+
+/// Walks up from `start` to the first directory holding a project file. The
+/// walk ends at `home` or at a directory with a `.git` in it, so that a file
+/// in some unrelated directory above them isn't opened by surprise.
+pub fn find_project_file(start: &Path, home: Option<&Path>) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let path = dir.join(PROJECT_FILE_NAME);
+        if path.is_file() {
+            return Some(path);
+        }
+        if Some(dir) == home || dir.join(".git").exists() {
+            break;
+        }
+    }
+
+    None
+}
+
+fn property<'a>(node: &'a kdl::KdlNode, name: &str) -> Option<&'a kdl::KdlValue> {
+    node.entries()
+        .iter()
+        .find(|entry| entry.name().is_some_and(|key| key.value() == name))
+        .map(|entry| entry.value())
+}
+
+fn parse_direction(node: &kdl::KdlNode) -> anyhow::Result<SpanDirection> {
+    match property(node, "direction").map(|value| value.as_string()) {
+        None | Some(Some("horizontal")) => Ok(SpanDirection::Horizontal),
+        Some(Some("vertical")) => Ok(SpanDirection::Vertical),
+        Some(value) => Err(anyhow::format_err!(
+            "`direction` expects \"horizontal\" or \"vertical\", got {:?}",
+            value
+        )),
+    }
+}
+
+fn parse_children(node: &kdl::KdlNode) -> anyhow::Result<Vec<(f64, ProjectNode)>> {
+    let Some(children) = node.children() else {
+        return Ok(Vec::new());
+    };
+
+    children
+        .nodes()
+        .iter()
+        .map(|child| {
+            let size = property(child, "size")
+                .and_then(|value| value.as_float().or(value.as_integer().map(|i| i as f64)))
+                .filter(|size| *size > 0.0)
+                .unwrap_or(1.0);
+            let parsed = match child.name().value() {
+                "pane" => ProjectNode::Pane {
+                    command: child
+                        .entries()
+                        .iter()
+                        .find(|entry| entry.name().is_none())
+                        .and_then(|entry| entry.value().as_string())
+                        .map(str::to_string),
+                    title: property(child, "title")
+                        .and_then(|value| value.as_string())
+                        .map(str::to_string),
+                },
+                "span" => ProjectNode::Span {
+                    direction: parse_direction(child)?,
+                    children: parse_children(child)?,
+                },
+                name => {
+                    return Err(anyhow::format_err!(
+                        "`layout` expects \"pane\" or \"span\", got {:?}",
+                        name
+                    ))
+                }
+            };
+
+            Ok((size, parsed))
+        })
+        .collect()
+}
+
+/// Reads a project file like
+/// `layout direction="vertical" { pane "nvim ."; span { pane "cargo watch"; pane } }`,
+/// where `layout` is the outermost span.
+pub fn parse_project(contents: &str) -> anyhow::Result<ProjectNode> {
+    let document = kdl::KdlDocument::parse_v2(contents)?;
+    let layout = document
+        .nodes()
+        .iter()
+        .find(|node| node.name().value() == "layout")
+        .ok_or_else(|| anyhow::format_err!("Project file has no `layout`"))?;
+    let mut children = parse_children(layout)?;
+    if children.is_empty() {
+        return Err(anyhow::format_err!("`layout` has no panes"));
+    }
+    if children.len() == 1 {
+        return Ok(children.remove(0).1);
+    }
+
+    Ok(ProjectNode::Span {
+        direction: parse_direction(layout)?,
+        children,
+    })
+}
+
+pub fn load_project(path: &Path) -> anyhow::Result<ProjectNode> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read project file {:?}", path))?;
+
+    parse_project(&contents).with_context(|| format!("Invalid project file {:?}", path))
+}
+
+async fn pane_command(
+    state_container: &StateContainer,
+    command: &Option<String>,
+    title: &Option<String>,
+) -> PaneCommand {
+    let mut parts = command
+        .iter()
+        .flat_map(|command| split_command_line(command));
+    match parts.next() {
+        Some(program) => PaneCommand {
+            program,
+            args: parts.collect(),
+            title: title.clone(),
+            tint: None,
+        },
+        None => {
+            let config = state_container.config.read().await;
+            PaneCommand {
+                program: config.default_shell.clone(),
+                args: config.default_shell_args.clone(),
+                title: title.clone(),
+                tint: None,
+            }
+        }
+    }
+}
+
+async fn build_node(
+    state_container: &StateContainer,
+    project: &ProjectNode,
+    commands: &mut Vec<(usize, PaneCommand)>,
+) -> Node {
+    let id = state_container
+        .span_id_counter
+        .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        + 1;
+    match project {
+        ProjectNode::Pane { command, title } => {
+            commands.push((id, pane_command(state_container, command, title).await));
+            Node::new(id, NodeData::Void)
+        }
+        ProjectNode::Span {
+            direction,
+            children,
+        } => {
+            let mut span = Span::new(*direction);
+            for (size, child) in children {
+                let child = Box::pin(build_node(state_container, child, commands)).await;
+                span.children.push(SpanChild::new(child).with_size(*size));
+            }
+            Node::new(id, NodeData::Span(span))
+        }
+    }
+}
+
+/// Replaces the layout with the project's and focuses its first pane.
+/// Returns the panes with the commands to start in them, in layout order.
+pub async fn open_project(
+    state_container: &StateContainer,
+    project: &ProjectNode,
+) -> Vec<(usize, PaneCommand)> {
+    let mut commands = Vec::new();
+    let root = build_node(state_container, project, &mut commands).await;
+    *state_container.root_node.write().await = Some(root);
+    if let Some((first_id, _)) = commands.first() {
+        state_container.set_active_span(*first_id);
+    }
+
+    commands
+}
+
+/// Starts the project's commands, leaving out the panes whose command can't
+/// be started. They are reported together, since only one message is shown.
+async fn spawn_project(state_container: &StateContainer, project: &ProjectNode) {
+    let mut failed = Vec::new();
+    for (span_id, command) in open_project(state_container, project).await {
+        let command_line = command.command_line();
+        if let Err(err) = spawn_in_pane(state_container.clone(), span_id, command).await {
+            tracing::error!(
+                "Could not start {:?} in pane {}: {:?}",
+                command_line,
+                span_id,
+                err
+            );
+            take_node(&mut *state_container.root_node.write().await, span_id);
+            failed.push(command_line);
+        }
+    }
+    if failed.is_empty() {
+        return;
+    }
+    let first_id = {
+        let root = state_container.root_node.read().await;
+        root.as_ref()
+            .and_then(|root| root.leaf_ids().first().copied())
+    };
+    if let Some(first_id) = first_id {
+        state_container.set_active_span(first_id);
+    }
+    state_container
+        .flash(format!("Could not start: {}", failed.join(", ")))
+        .await;
+}
+
+/// Points out a project file found above `start` without opening it, as its
+/// commands would otherwise run just by starting citymux in a directory
+/// someone else wrote.
+pub async fn point_out_project_file(state_container: &StateContainer, start: &Path) {
+    let home = dirs::home_dir();
+    if let Some(path) = find_project_file(start, home.as_deref()) {
+        tracing::info!("Not opening project file {:?} without --project", path);
+        state_container
+            .flash(format!("Found {}, open it with --project", path.display()))
+            .await;
+    }
+}
+
+/// Opens the project file given with `--project`, with a single shell when
+/// there is none or none of its panes could be started.
+pub async fn open_initial_panes(state_container: &StateContainer) -> anyhow::Result<()> {
+    if let Some(path) = state_container.args.project.clone().map(PathBuf::from) {
+        let project = load_project(&path)?;
+        tracing::info!("Opening project file {:?}", path);
+        spawn_project(state_container, &project).await;
+        if state_container.root_node.read().await.is_some() {
+            return Ok(());
+        }
+    } else if let Ok(dir) = std::env::current_dir() {
+        point_out_project_file(state_container, &dir).await;
+    }
+    create_process(state_container.clone()).await?;
+
+    Ok(())
+}
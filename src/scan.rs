@@ -0,0 +1,157 @@
+use renterm::color::Color;
+use vte::{Params, Perform};
+
+/// Something a pane's output stream did that vt100 doesn't keep track of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScanEvent {
+    /// An OSC 8 sequence opened a hyperlink, or closed it with `None`.
+    Hyperlink(Option<String>),
+    /// OSC 11 set the default background color, or OSC 111 reset it with `None`.
+    DefaultBackground(Option<Color>),
+    /// DECSCUSR selected a cursor style, `CSI n SP q`.
+    CursorStyle(u16),
+    /// `CSI ? 12 h` or `CSI ? 12 l` turned cursor blinking on or off.
+    CursorBlink(bool),
+    /// The program asked what the terminal is, primary DA: `CSI c` or `CSI 0 c`.
+    DeviceAttributes,
+    /// DSR asked whether the terminal is working, `CSI 5 n`.
+    StatusReport,
+    /// DSR asked where the cursor is, `CSI 6 n`.
+    CursorReport,
+    /// BEL outside of an escape sequence.
+    Bell,
+}
+
+/// Text handled by the parser while in the ground state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroundAction {
+    Print(char),
+    Execute(u8),
+}
+
+/// Watches pane output alongside vt100 and stops at the points where the
+/// caller needs to act, see `TerminalInfo::process`.
+#[derive(Default)]
+pub struct OutputScanner {
+    /// Whether printed text should be reported as ground actions.
+    pub track_text: bool,
+    pub event: Option<ScanEvent>,
+    pub actions: Vec<GroundAction>,
+    dispatched: bool,
+}
+
+impl OutputScanner {
+    pub fn new(track_text: bool) -> Self {
+        OutputScanner {
+            track_text,
+            ..Default::default()
+        }
+    }
+}
+
+fn parse_hyperlink(params: &[&[u8]]) -> Option<ScanEvent> {
+    let [b"8", _link_params, uri @ ..] = params else {
+        return None;
+    };
+    let uri = uri.join(&b';');
+    let uri = String::from_utf8_lossy(&uri).into_owned();
+    let uri = (!uri.is_empty()).then_some(uri);
+
+    Some(ScanEvent::Hyperlink(uri))
+}
+
+/// Parses an X11 color specification, either `rgb:r/g/b` with one to four
+/// hex digits per channel or `#rgb`/`#rrggbb`.
+fn parse_color(spec: &[u8]) -> Option<Color> {
+    let spec = std::str::from_utf8(spec).ok()?;
+    let channels: Vec<&str> = if let Some(rgb) = spec.strip_prefix("rgb:") {
+        rgb.split('/').collect()
+    } else if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() % 3 != 0 || !hex.is_ascii() {
+            return None;
+        }
+        let width = hex.len() / 3;
+        (0..3).map(|i| &hex[i * width..(i + 1) * width]).collect()
+    } else {
+        return None;
+    };
+    let [r, g, b] = channels[..] else {
+        return None;
+    };
+    let channel = |value: &str| -> Option<u8> {
+        if value.is_empty() || value.len() > 4 {
+            return None;
+        }
+        let max = (1u32 << (4 * value.len())) - 1;
+        let value = u32::from_str_radix(value, 16).ok()?;
+        Some((value * 255 / max) as u8)
+    };
+
+    Some(Color::new_rgb(channel(r)?, channel(g)?, channel(b)?))
+}
+
+fn parse_default_background(params: &[&[u8]]) -> Option<ScanEvent> {
+    match params {
+        [b"11", spec, ..] => Some(ScanEvent::DefaultBackground(Some(parse_color(spec)?))),
+        [b"111", ..] => Some(ScanEvent::DefaultBackground(None)),
+        _ => None,
+    }
+}
+
+impl Perform for OutputScanner {
+    fn print(&mut self, c: char) {
+        if self.track_text {
+            self.actions.push(GroundAction::Print(c));
+        }
+    }
+    fn execute(&mut self, byte: u8) {
+        if byte == 0x07 {
+            self.event = Some(ScanEvent::Bell);
+        }
+        if self.track_text {
+            self.actions.push(GroundAction::Execute(byte));
+        }
+    }
+    fn hook(&mut self, _params: &Params, _intermediates: &[u8], _ignore: bool, _action: char) {
+        self.dispatched = true;
+    }
+    fn unhook(&mut self) {
+        self.dispatched = true;
+    }
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        self.dispatched = true;
+        self.event = parse_hyperlink(params).or_else(|| parse_default_background(params));
+    }
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        self.dispatched = true;
+        if ignore {
+            return;
+        }
+        let mut params = params
+            .iter()
+            .map(|param| param.first().copied().unwrap_or(0));
+        self.event = match (intermediates, action) {
+            (b" ", 'q') => Some(ScanEvent::CursorStyle(params.next().unwrap_or(0))),
+            (b"?", 'h' | 'l') if params.any(|param| param == 12) => {
+                Some(ScanEvent::CursorBlink(action == 'h'))
+            }
+            (b"", 'c') if params.next().unwrap_or(0) == 0 => Some(ScanEvent::DeviceAttributes),
+            (b"", 'n') => match params.next() {
+                Some(5) => Some(ScanEvent::StatusReport),
+                Some(6) => Some(ScanEvent::CursorReport),
+                _ => None,
+            },
+            _ => None,
+        };
+    }
+    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {
+        self.dispatched = true;
+    }
+    fn terminated(&self) -> bool {
+        if self.event.is_some() {
+            return true;
+        }
+
+        self.track_text && (self.dispatched || !self.actions.is_empty())
+    }
+}
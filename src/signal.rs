@@ -0,0 +1,54 @@
+use crate::state::StateContainer;
+
+/// Maps names like `INT`, `SIGTERM` or `hup` to the signals that can be sent
+/// to a pane.
+#[cfg(unix)]
+pub fn parse_signal(name: &str) -> Option<nix::sys::signal::Signal> {
+    use nix::sys::signal::Signal;
+
+    let name = name.to_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    match name {
+        "INT" => Some(Signal::SIGINT),
+        "TERM" => Some(Signal::SIGTERM),
+        "HUP" => Some(Signal::SIGHUP),
+        "KILL" => Some(Signal::SIGKILL),
+        _ => None,
+    }
+}
+
+/// Sends a signal, given by name, to the process running in a pane.
+pub async fn send_signal(
+    state_container: &StateContainer,
+    span_id: usize,
+    signal: &str,
+) -> anyhow::Result<()> {
+    let Some(pid) = state_container.pid_of(span_id).await else {
+        return Err(anyhow::format_err!("No process in pane: {}", span_id));
+    };
+    // A PID of 0 would signal our own process group.
+    if pid == 0 {
+        return Err(anyhow::format_err!("Pane {} has no known PID", span_id));
+    }
+    tracing::debug!("Sending {} to pid {}", signal, pid);
+
+    send_signal_to_pid(pid, signal)
+}
+
+#[cfg(unix)]
+fn send_signal_to_pid(pid: u32, signal: &str) -> anyhow::Result<()> {
+    let Some(parsed) = parse_signal(signal) else {
+        return Err(anyhow::format_err!("Unknown signal: {}", signal));
+    };
+    let pid = nix::unistd::Pid::from_raw(i32::try_from(pid)?);
+    nix::sys::signal::kill(pid, parsed)?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_signal_to_pid(_pid: u32, _signal: &str) -> anyhow::Result<()> {
+    Err(anyhow::format_err!(
+        "Sending signals is not supported on this platform"
+    ))
+}
@@ -1,17 +1,108 @@
 use renterm::vector::Vector2;
 
-use crate::state::StateContainer;
+#[cfg(any(target_os = "windows", test))]
+use crate::process::TerminalError;
+use crate::{spawn::pane_content_size, state::StateContainer};
+
+/// Reads the size from the `COLUMNS` and `LINES` variables.
+fn size_from_env() -> Option<(u16, u16)> {
+    let read = |name: &str| {
+        std::env::var(name)
+            .ok()?
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|value| *value > 0)
+    };
+
+    Some((read("COLUMNS")?, read("LINES")?))
+}
+
+/// Asks the terminal for its size, falling back to `COLUMNS` and `LINES` when
+/// it can't tell or reports an empty size.
+pub fn terminal_size() -> Result<(u16, u16), anyhow::Error> {
+    match crossterm::terminal::size() {
+        Ok((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        result => match size_from_env() {
+            Some(size) => Ok(size),
+            None => Ok(result?),
+        },
+    }
+}
 
 pub async fn update_size(state_container: StateContainer) -> Result<Vector2, anyhow::Error> {
-    let (width, height) = crossterm::terminal::size()?;
+    let (width, height) = terminal_size()?;
     let size = state_container.state().size.clone();
-    let size = {
+    let (size, changed) = {
         let mut size = size.write().await;
-        size.y = height as i32;
-        size.x = width as i32;
+        let new_size = Vector2::new(width, height);
+        let changed = *size != new_size;
+        *size = new_size;
 
-        size.to_owned()
+        (size.to_owned(), changed)
     };
+    if changed {
+        resize_panes(&state_container).await?;
+    }
 
     Ok(size)
 }
+
+/// Resizes the PTY of every visible pane to fit the current layout, so that a
+/// host resize reaches each program once instead of frame by frame as the
+/// panes are drawn. Waits for a frame being drawn to finish, so the PTYs
+/// don't change size under it.
+pub async fn resize_panes(state_container: &StateContainer) -> anyhow::Result<()> {
+    let draw_lock = state_container.draw_lock.clone();
+    let _draw_lock = draw_lock.lock().await;
+    let leaf_ids = match state_container.root_node.read().await.as_ref() {
+        Some(root) => root.leaf_ids(),
+        None => return Ok(()),
+    };
+    let processes = { state_container.processes.read().await.clone() };
+    for process in processes {
+        let process = process.read().await;
+        if !leaf_ids.contains(&process.span_id) {
+            continue;
+        }
+        let content_size = pane_content_size(state_container, process.span_id).await;
+        let mut terminal_info = process.terminal_info.lock().await;
+        terminal_info.set_size(content_size);
+        let size = terminal_info.size();
+        let mut pty = process.terminal.lock().await;
+        if pty.size() != size {
+            pty.set_size(size)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts a pane size to the `i16` range used by console `COORD`s, clamping
+/// sizes that don't fit instead of letting them wrap.
+#[cfg(any(target_os = "windows", test))]
+pub fn clamp_console_size(size: Vector2) -> Vector2<i16> {
+    let clamped = size
+        .clone()
+        .saturating_cast::<i16>()
+        .max(Vector2::new(1i16, 1i16));
+    if Vector2::<i32>::new(clamped.x, clamped.y) != size {
+        tracing::warn!("Clamping console size {:?} to {:?}", size, clamped);
+    }
+
+    clamped
+}
+
+/// Checks a pane size before it is handed to ConPTY. Sizes with a negative
+/// dimension are rejected, anything else is clamped to at least 1x1.
+#[cfg(any(target_os = "windows", test))]
+pub fn validate_console_size(size: Vector2) -> Result<Vector2<i16>, TerminalError> {
+    if size.x < 0 || size.y < 0 {
+        return Err(TerminalError::from(format!(
+            "Invalid console size: {}x{}",
+            size.x, size.y
+        )));
+    }
+
+    Ok(clamp_console_size(size))
+}
@@ -2,7 +2,7 @@ use renterm::{rect::Rect, vector::Vector2};
 
 use crate::StateContainer;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SpanDirection {
     Horizontal,
     Vertical,
@@ -90,6 +90,24 @@ fn find_by_id_internal<'a>(
     None
 }
 
+fn find_internal<'a>(node: &'a Node, id: usize, path: &mut Vec<usize>) -> Option<&'a Node> {
+    if node.id == id {
+        return Some(node);
+    }
+    if let NodeData::Span(span) = &node.data {
+        path.push(node.id);
+        for child in &span.children {
+            let node = find_internal(&child.node, id, path);
+            if node.is_some() {
+                return node;
+            }
+        }
+        path.pop();
+    }
+
+    None
+}
+
 impl Node {
     pub fn new(id: usize, data: NodeData) -> Self {
         Self { id, data }
@@ -97,17 +115,125 @@ impl Node {
     pub fn id(&self) -> usize {
         self.id
     }
+    /// Returns the ids of the panes in this subtree, in layout order.
+    pub fn leaf_ids(&self) -> Vec<usize> {
+        match &self.data {
+            NodeData::Span(span) => span
+                .children
+                .iter()
+                .flat_map(|child| child.node.leaf_ids())
+                .collect(),
+            NodeData::Void => vec![self.id],
+        }
+    }
     pub fn find_by_id(&mut self, id: usize) -> Option<(&mut Node, Vec<usize>)> {
         let mut path = Vec::new();
         let result = find_by_id_internal(self, id, &mut path);
 
         result.map(|node| (node, path))
     }
+    /// Like `find_by_id`, for callers that only read the tree.
+    pub fn find(&self, id: usize) -> Option<(&Node, Vec<usize>)> {
+        let mut path = Vec::new();
+        let result = find_internal(self, id, &mut path);
+
+        result.map(|node| (node, path))
+    }
+}
+
+/// Detaches the node with `id` from the tree and returns it. Spans that are
+/// left with a single child are replaced by that child, and the root becomes
+/// `None` when the node was the whole tree.
+pub fn take_node(root: &mut Option<Node>, id: usize) -> Option<Node> {
+    let root_node = root.as_mut()?;
+    if root_node.id == id {
+        return root.take();
+    }
+    let (_, path) = root_node.find_by_id(id)?;
+    let parent_id = *path.last()?;
+    let (parent, _) = root_node.find_by_id(parent_id)?;
+    let NodeData::Span(span) = &mut parent.data else {
+        return None;
+    };
+    let index = span.children.iter().position(|child| child.node.id == id)?;
+    let child = span.children.remove(index);
+    if span.children.len() == 1 {
+        let only_child = span.children.remove(0);
+        *parent = only_child.node;
+    }
+
+    Some(child.node)
+}
+
+/// Moves the border between the pane `span_id` and its neighbor in the
+/// closest span laid out in `direction`, growing the pane by `step` of the
+/// span's size, or shrinking it when negative. Neither side gets smaller than
+/// `step`. Returns whether the layout changed.
+pub fn resize_pane(root: &mut Node, span_id: usize, direction: SpanDirection, step: f64) -> bool {
+    let Some((_, path)) = root.find_by_id(span_id) else {
+        return false;
+    };
+    let children: Vec<usize> = path.iter().skip(1).copied().chain([span_id]).collect();
+    for (ancestor_id, child_id) in path.iter().copied().zip(children).rev() {
+        let Some((ancestor, _)) = root.find_by_id(ancestor_id) else {
+            return false;
+        };
+        let NodeData::Span(span) = &mut ancestor.data else {
+            continue;
+        };
+        if span.direction != direction || span.children.len() < 2 {
+            continue;
+        }
+        let Some(index) = span
+            .children
+            .iter()
+            .position(|child| child.node.id == child_id)
+        else {
+            return false;
+        };
+        let neighbor = if index + 1 < span.children.len() {
+            index + 1
+        } else {
+            index - 1
+        };
+        let total: f64 = span.children.iter().map(|child| child.size).sum();
+        let minimum = total * step.abs();
+        let delta = (total * step)
+            .min(span.children[neighbor].size - minimum)
+            .max(minimum - span.children[index].size);
+        if delta.abs() < f64::EPSILON {
+            return false;
+        }
+        span.children[index].size += delta;
+        span.children[neighbor].size -= delta;
+
+        return true;
+    }
+
+    false
+}
+
+/// Gives every child of the spans laid out in `direction` the same size,
+/// leaving spans in the other direction as they are. Returns whether any
+/// size changed.
+pub fn equalize_spans(node: &mut Node, direction: SpanDirection) -> bool {
+    let NodeData::Span(span) = &mut node.data else {
+        return false;
+    };
+    let mut changed = false;
+    for child in &mut span.children {
+        if span.direction == direction && child.size != 1.0 {
+            child.size = 1.0;
+            changed = true;
+        }
+        changed |= equalize_spans(&mut child.node, direction);
+    }
+
+    changed
 }
 
 pub async fn get_root_dimensions(state_container: StateContainer) -> Rect {
-    let state = state_container.state();
-    let size = state.size.read().await;
+    let size = state_container.state().layout_size().await;
 
-    Rect::new(Vector2::new(0, 0), size.clone())
+    Rect::new(Vector2::new(0, 0), size)
 }
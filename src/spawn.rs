@@ -1,29 +1,85 @@
 use renterm::vector::Vector2;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicBool, Arc},
+};
 use tokio::sync::{Mutex, RwLock};
 use which::which;
 
 use crate::{
-    draw::trigger_draw,
+    config::{Config, Launcher},
+    control::control_socket_path,
+    draw::{find_process_by_id, trigger_draw},
     exit::exit,
-    layout::get_span_dimensions,
-    process::handle_process,
-    span::{get_root_dimensions, Node, NodeData, Span, SpanChild, SpanDirection},
-    state::{Process, StateContainer},
+    hooks::{hook_title, run_hook, HookEvent},
+    layout::{get_pane_dimensions, get_span_dimensions},
+    main_pane::{current_main_pane, push_to_stack},
+    process::{handle_process, ProcessData, Utf8Carryover},
+    span::{get_root_dimensions, take_node, Node, NodeData, Span, SpanChild, SpanDirection},
+    state::{ClosedPaneInfo, PaneCommand, Process, StateContainer},
     term::TerminalInfo,
     tty::spawn_interactive_process,
+    window::{remove_from_background_windows, replace_empty_window},
+    zoom::{remove_from_zoomed_layout, unzoom},
 };
 
 pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usize> {
-    let active_id = state_container
-        .state()
-        .active_id
-        .load(std::sync::atomic::Ordering::Relaxed);
+    let before = { state_container.config.read().await.split_before };
+
+    create_span_beside(state_container, before).await
+}
+
+/// Like `create_span`, with the new pane before the active one when `before`
+/// is set, whatever `Config::split_before` says.
+pub async fn create_span_beside(
+    state_container: StateContainer,
+    before: bool,
+) -> anyhow::Result<usize> {
     let new_id = state_container
         .state()
         .span_id_counter
         .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
         + 1;
+
+    insert_node_beside(state_container, Node::new(new_id, NodeData::Void), before).await
+}
+
+/// Splits `node` into the layout next to the active pane and makes it active,
+/// returning its id. The node can be a fresh pane or one taken from another
+/// window. With a main pane set, the node is stacked next to it instead.
+pub async fn insert_node(state_container: StateContainer, node: Node) -> anyhow::Result<usize> {
+    let before = { state_container.config.read().await.split_before };
+
+    insert_node_beside(state_container, node, before).await
+}
+
+/// The two children of a span split from the active pane, the new pane first
+/// when it goes `before` the active one.
+fn split_children(active: SpanChild, new: SpanChild, before: bool) -> Vec<SpanChild> {
+    if before {
+        vec![new, active]
+    } else {
+        vec![active, new]
+    }
+}
+
+/// Like `insert_node`, with the node placed before the active pane, left of
+/// or above it, when `before` is set and after it otherwise.
+async fn insert_node_beside(
+    state_container: StateContainer,
+    node: Node,
+    before: bool,
+) -> anyhow::Result<usize> {
+    unzoom(&state_container).await;
+    if let Some(main_id) = current_main_pane(&state_container).await {
+        return push_to_stack(&state_container, main_id, node).await;
+    }
+    let active_id = state_container
+        .state()
+        .active_id
+        .load(std::sync::atomic::Ordering::Relaxed);
+    let new_id = node.id;
+    let split_ratio = { state_container.config.read().await.default_split_ratio };
     state_container
         .state()
         .active_id
@@ -35,8 +91,7 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
         let root = root_guard.as_mut();
         match root {
             None => {
-                let root_node = Node::new(new_id, NodeData::Void);
-                *root_guard = Some(root_node);
+                *root_guard = Some(node);
                 tracing::debug!("Created root node: {:?}", root_guard);
                 Ok(new_id)
             }
@@ -48,9 +103,6 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
                         .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
                         + 1;
                     let mut new_root = Node::new(container_id, NodeData::Void);
-                    let old_root_as_child = SpanChild::new(root.clone()).with_size(1.0);
-                    let new_child =
-                        SpanChild::new(Node::new(new_id, NodeData::Void)).with_size(1.0);
 
                     let is_horizonal_axis_larger = root_rect.size().x > root_rect.size().y;
                     let direction = if is_horizonal_axis_larger {
@@ -58,9 +110,11 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
                     } else {
                         SpanDirection::Vertical
                     };
+                    let ratio = split_ratio.for_direction(direction);
+                    let old_root_as_child = SpanChild::new(root.clone()).with_size(1.0 - ratio);
+                    let new_child = SpanChild::new(node).with_size(ratio);
                     let mut span = Span::new(direction);
-                    span.children.push(old_root_as_child);
-                    span.children.push(new_child);
+                    span.children = split_children(old_root_as_child, new_child, before);
                     let span = NodeData::Span(span);
                     new_root.data = span;
                     *root = new_root;
@@ -106,17 +160,21 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
                         NodeData::Span(span) => {
                             match span.direction {
                                 SpanDirection::Horizontal => {
+                                    let ratio =
+                                        split_ratio.for_direction(SpanDirection::Horizontal);
                                     let total = span
                                         .children
                                         .iter()
                                         .fold(0.0, |acc, child| acc + child.size);
                                     let avg = total / span.children.len() as f64;
-                                    let size_of_new_child = avg;
+                                    let size_of_new_child = avg * ratio / (1.0 - ratio);
                                     let new_total = total + size_of_new_child;
                                     let new_ratio = size_of_new_child / new_total;
                                     let new_width = parent_sizes.size().x as f64 * new_ratio;
                                     if active_sizes.size().y as f64 > new_width {
                                         let mut new_span = Span::new(SpanDirection::Vertical);
+                                        let ratio =
+                                            split_ratio.for_direction(SpanDirection::Vertical);
                                         let container_id = state_container
                                             .state()
                                             .span_id_counter
@@ -129,12 +187,11 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
                                             ));
                                         };
                                         let active_node = active_node.0;
-                                        new_span.children.push(
-                                            SpanChild::new(active_node.clone()).with_size(1.0),
-                                        );
-                                        new_span.children.push(
-                                            SpanChild::new(Node::new(new_id, NodeData::Void))
-                                                .with_size(1.0),
+                                        new_span.children = split_children(
+                                            SpanChild::new(active_node.clone())
+                                                .with_size(1.0 - ratio),
+                                            SpanChild::new(node).with_size(ratio),
+                                            before,
                                         );
                                         *active_node =
                                             Node::new(container_id, NodeData::Span(new_span));
@@ -142,17 +199,20 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
                                     }
                                 }
                                 SpanDirection::Vertical => {
+                                    let ratio = split_ratio.for_direction(SpanDirection::Vertical);
                                     let total = span
                                         .children
                                         .iter()
                                         .fold(0.0, |acc, child| acc + child.size);
                                     let avg = total / span.children.len() as f64;
-                                    let size_of_new_child = avg;
+                                    let size_of_new_child = avg * ratio / (1.0 - ratio);
                                     let new_total = total + size_of_new_child;
                                     let new_ratio = size_of_new_child / new_total;
                                     let new_height = parent_sizes.size().y as f64 * new_ratio;
                                     if active_sizes.size().x as f64 > new_height {
                                         let mut new_span = Span::new(SpanDirection::Horizontal);
+                                        let ratio =
+                                            split_ratio.for_direction(SpanDirection::Horizontal);
                                         let container_id = state_container
                                             .state()
                                             .span_id_counter
@@ -165,12 +225,11 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
                                             ));
                                         };
                                         let active_node = active_node.0;
-                                        new_span.children.push(
-                                            SpanChild::new(active_node.clone()).with_size(1.0),
-                                        );
-                                        new_span.children.push(
-                                            SpanChild::new(Node::new(new_id, NodeData::Void))
-                                                .with_size(1.0),
+                                        new_span.children = split_children(
+                                            SpanChild::new(active_node.clone())
+                                                .with_size(1.0 - ratio),
+                                            SpanChild::new(node).with_size(ratio),
+                                            before,
                                         );
                                         *active_node =
                                             Node::new(container_id, NodeData::Span(new_span));
@@ -198,9 +257,19 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
                                 .iter()
                                 .fold(0.0, |acc, child| acc + child.size);
                             let avg = total / span.children.len() as f64;
-                            span.children.push(
-                                SpanChild::new(Node::new(new_id, NodeData::Void)).with_size(avg),
-                            );
+                            // Relative to an average sibling, the new pane gets `ratio` to its
+                            // `1 - ratio`, which keeps the panes equal at the default of 0.5.
+                            let ratio = split_ratio.for_direction(span.direction);
+                            let new_child =
+                                SpanChild::new(node).with_size(avg * ratio / (1.0 - ratio));
+                            let active_index = span
+                                .children
+                                .iter()
+                                .position(|child| child.node.id == active_id);
+                            match active_index {
+                                Some(index) if before => span.children.insert(index, new_child),
+                                _ => span.children.push(new_child),
+                            }
                             tracing::debug!("Added new node to parent: {:?}", &parent);
                             Ok(new_id)
                         }
@@ -211,34 +280,161 @@ pub async fn create_span(state_container: StateContainer) -> anyhow::Result<usiz
     }
 }
 
+fn shell_command(config: &Config) -> PaneCommand {
+    PaneCommand {
+        program: config.default_shell.clone(),
+        args: config.default_shell_args.clone(),
+        title: None,
+        tint: None,
+    }
+}
+
 pub async fn create_process(
     state_container: StateContainer,
 ) -> anyhow::Result<Arc<RwLock<Process>>> {
-    let new_id = create_span(state_container.clone()).await?;
-    let size = Vector2 { x: 1, y: 1 };
-    let program = {
-        state_container
-            .state()
-            .config
-            .read()
-            .await
-            .default_shell
-            .clone()
+    let (command, before) = {
+        let config = state_container.config.read().await;
+        (shell_command(&config), config.split_before)
     };
-    let program = which(program)?.to_string_lossy().to_string();
-    let mut env: HashMap<String, String> = HashMap::new();
-    env.insert("TERM".to_string(), "xterm-citymux".to_string());
+
+    spawn_in_new_pane(state_container, command, before).await
+}
+
+/// Like `create_process`, but always opens the shell before the active pane.
+pub async fn create_process_before(
+    state_container: StateContainer,
+) -> anyhow::Result<Arc<RwLock<Process>>> {
+    let command = { shell_command(&*state_container.config.read().await) };
+
+    spawn_in_new_pane(state_container, command, true).await
+}
+
+/// Opens a launcher's command in a new pane, titled after the launcher.
+pub async fn launch(
+    state_container: StateContainer,
+    launcher: &Launcher,
+) -> anyhow::Result<Arc<RwLock<Process>>> {
+    let Some((program, args)) = launcher.command_line() else {
+        return Err(anyhow::format_err!(
+            "Launcher {} has no command",
+            launcher.name
+        ));
+    };
+
+    let command = PaneCommand {
+        program,
+        args,
+        title: Some(launcher.name.clone()),
+        tint: launcher.tint.clone(),
+    };
+    let before = { state_container.config.read().await.split_before };
+
+    spawn_in_new_pane(state_container, command, before).await
+}
+
+/// Size of the area inside a pane's border, so that programs start out at the
+/// size they will be drawn at instead of being resized on the first frame.
+pub async fn pane_content_size(state_container: &StateContainer, span_id: usize) -> Vector2 {
+    let root_rect = get_root_dimensions(state_container.clone()).await;
+    let gap = { state_container.config.read().await.pane_gap };
+    let root = state_container.root_node.read().await;
+    let pane = root
+        .as_ref()
+        .and_then(|root| get_pane_dimensions(root, span_id, root_rect.size(), gap));
+    let Some(pane) = pane else {
+        return Vector2::new(1, 1);
+    };
+
+    (pane.size() - Vector2::new(2, 2)).max(Vector2::new(1, 1))
+}
+
+/// Environment variables set for programs started in panes.
+pub fn pane_env(config: &Config) -> HashMap<String, String> {
+    let mut env = config.env.clone();
+    env.insert("TERM".to_string(), config.term.clone());
+    if cfg!(unix) {
+        env.insert(
+            "CITYMUX_SOCKET".to_string(),
+            control_socket_path().to_string_lossy().into_owned(),
+        );
+    }
+
+    env
+}
+
+async fn spawn_in_new_pane(
+    state_container: StateContainer,
+    command: PaneCommand,
+    before: bool,
+) -> anyhow::Result<Arc<RwLock<Process>>> {
+    let new_id = create_span_beside(state_container.clone(), before).await?;
+
+    spawn_in_pane(state_container, new_id, command).await
+}
+
+/// Starts `command` in the existing, empty pane `new_id`.
+pub async fn spawn_in_pane(
+    state_container: StateContainer,
+    new_id: usize,
+    command: PaneCommand,
+) -> anyhow::Result<Arc<RwLock<Process>>> {
+    let mut terminal_info = TerminalInfo::new(pane_content_size(&state_container, new_id).await);
+    terminal_info.set_fallback_title(command.title.clone());
+    let size = terminal_info.size();
+    let program = which(&command.program)?.to_string_lossy().to_string();
+    let env = { pane_env(&*state_container.config.read().await) };
 
     tracing::debug!("Spawning program: {}", program);
-    let args = vec![];
-    let result = spawn_interactive_process(&program, &env, &args, size.clone()).await?;
-    tracing::debug!("Program spawned: {}", program);
+    let result = spawn_interactive_process(&program, &env, &command.args, size.clone()).await?;
+    tracing::debug!("Program spawned: {} (pid {})", program, result.pid);
+
+    add_process(state_container, new_id, result, terminal_info, command).await
+}
+
+/// Shows `data` in the existing, empty pane `new_id`, and draws its output
+/// until it ends.
+pub async fn add_process(
+    state_container: StateContainer,
+    new_id: usize,
+    data: ProcessData,
+    terminal_info: TerminalInfo,
+    command: PaneCommand,
+) -> anyhow::Result<Arc<RwLock<Process>>> {
+    insert_process(state_container, new_id, data, terminal_info, command, false).await
+}
+
+/// Like `add_process`, for a pane without a program, see `Process::is_virtual`.
+pub async fn add_virtual_process(
+    state_container: StateContainer,
+    new_id: usize,
+    data: ProcessData,
+    terminal_info: TerminalInfo,
+    command: PaneCommand,
+) -> anyhow::Result<Arc<RwLock<Process>>> {
+    insert_process(state_container, new_id, data, terminal_info, command, true).await
+}
+
+async fn insert_process(
+    state_container: StateContainer,
+    new_id: usize,
+    data: ProcessData,
+    terminal_info: TerminalInfo,
+    command: PaneCommand,
+    is_virtual: bool,
+) -> anyhow::Result<Arc<RwLock<Process>>> {
     let process = Process {
-        stdin: Arc::new(Mutex::new(result.stdin)),
-        stdout: Arc::new(Mutex::new(result.stdout)),
-        terminal_info: Arc::new(Mutex::new(TerminalInfo::new(size))),
-        terminal: Arc::new(Mutex::new(result.terminal)),
+        stdin: Arc::new(Mutex::new(data.stdin)),
+        stdout: Arc::new(Mutex::new(data.stdout)),
+        terminal_info: Arc::new(Mutex::new(terminal_info)),
+        terminal: Arc::new(Mutex::new(data.terminal)),
         span_id: new_id,
+        read_only: false,
+        pid: data.pid,
+        output_carryover: Arc::new(Mutex::new(Utf8Carryover::default())),
+        has_output: Arc::new(AtomicBool::new(false)),
+        tint: command.tint.clone(),
+        command,
+        is_virtual,
     };
 
     let process = Arc::new(RwLock::new(process));
@@ -276,6 +472,8 @@ pub async fn create_process(
         }
     }
 
+    let title = hook_title(&state_container, new_id).await;
+    run_hook(&state_container, HookEvent::Created, new_id, title).await;
     trigger_draw(&state_container).await;
 
     Ok(process)
@@ -354,64 +552,215 @@ pub async fn kill_span(
     span_id: usize,
 ) -> Result<(), anyhow::Error> {
     tracing::debug!("Killing span: {}", span_id);
-    remove_node_from_state(state_container.clone(), span_id).await?;
-    kill_process(state_container.clone(), span_id).await?;
+    let title = hook_title(&state_container, span_id).await;
+    remember_closed_pane(&state_container, span_id).await;
+    let process = {
+        // Frames are drawn either before or after the pane is gone from both
+        // the layout and the processes, never with one but not the other.
+        // Its terminal is released after, so draws don't wait on the release.
+        let draw_lock = state_container.draw_lock.clone();
+        let _draw_lock = draw_lock.lock().await;
+        remove_node_from_state(state_container.clone(), span_id).await?;
+        take_process(&state_container, span_id).await
+    };
+    if let Some(process) = process {
+        release_process(&process).await?;
+    }
+    run_hook(&state_container, HookEvent::Closed, span_id, title).await;
     trigger_draw(&state_container).await;
 
     Ok(())
 }
 
-pub async fn kill_process(state_container: StateContainer, span_id: usize) -> anyhow::Result<()> {
-    let processes = state_container.state().processes.clone();
-    {
-        let mut processes = processes.write().await;
-        let mut delete_index = None;
-        let mut index: usize = 0;
-        for process in &*processes {
+/// How many closed panes `reopen_closed_pane` can bring back.
+const CLOSED_PANE_HISTORY: usize = 10;
+
+/// Records where a pane of the current layout sits before it is closed.
+async fn remember_closed_pane(state_container: &StateContainer, span_id: usize) {
+    let command = match find_process_by_id(state_container.clone(), span_id).await {
+        Some(process) => {
             let process = process.read().await;
-            if process.span_id == span_id {
-                delete_index = Some(index);
-                let mut terminal = process.terminal.lock().await;
-                terminal.release().await?;
-                break;
+            if process.is_virtual {
+                return;
             }
-            index += 1;
+            process.command.clone()
         }
-        if let Some(index) = delete_index {
-            processes.remove(index);
+        None => return,
+    };
+    let info = {
+        let root = state_container.root_node.read().await;
+        let Some(root) = root.as_ref() else {
+            return;
+        };
+        let Some((_, path)) = root.find(span_id) else {
+            return;
+        };
+        match path.last() {
+            None => ClosedPaneInfo {
+                parent_id: None,
+                index: 0,
+                size: 1.0,
+                command,
+            },
+            Some(&parent_id) => {
+                let Some((parent, _)) = root.find(parent_id) else {
+                    return;
+                };
+                let NodeData::Span(span) = &parent.data else {
+                    return;
+                };
+                let Some(index) = span
+                    .children
+                    .iter()
+                    .position(|child| child.node.id == span_id)
+                else {
+                    return;
+                };
+                ClosedPaneInfo {
+                    parent_id: Some(parent_id),
+                    index,
+                    size: span.children[index].size,
+                    command,
+                }
+            }
         }
+    };
+
+    let mut closed_panes = state_container.closed_panes.lock().await;
+    closed_panes.push(info);
+    if closed_panes.len() > CLOSED_PANE_HISTORY {
+        closed_panes.remove(0);
+    }
+}
+
+/// Adds an empty pane where a closed one used to be, returning its id. When
+/// its span is gone the pane is split next to the active one instead.
+pub async fn place_closed_pane(
+    state_container: &StateContainer,
+    info: &ClosedPaneInfo,
+) -> anyhow::Result<usize> {
+    unzoom(state_container).await;
+    let new_id = state_container
+        .span_id_counter
+        .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        + 1;
+    let node = Node::new(new_id, NodeData::Void);
+    {
+        let mut root = state_container.root_node.write().await;
+        let parent = root
+            .as_mut()
+            .zip(info.parent_id)
+            .and_then(|(root, parent_id)| root.find_by_id(parent_id));
+        if let Some((parent, _)) = parent {
+            if let NodeData::Span(span) = &mut parent.data {
+                let index = info.index.min(span.children.len());
+                span.children
+                    .insert(index, SpanChild::new(node).with_size(info.size));
+                state_container.set_active_span(new_id);
+                return Ok(new_id);
+            }
+        }
+    }
+
+    insert_node(state_container.clone(), node).await
+}
+
+/// Starts the most recently closed pane's command again in its old spot.
+pub async fn reopen_closed_pane(state_container: &StateContainer) -> anyhow::Result<()> {
+    let info = { state_container.closed_panes.lock().await.pop() };
+    let Some(info) = info else {
+        state_container.flash("No closed pane to reopen").await;
+        return Ok(());
+    };
+    let new_id = place_closed_pane(state_container, &info).await?;
+    let result = spawn_in_pane(state_container.clone(), new_id, info.command).await;
+    if let Err(err) = result {
+        take_node(&mut *state_container.root_node.write().await, new_id);
+        trigger_draw(state_container).await;
+        return Err(err);
     }
 
     Ok(())
 }
 
+pub async fn kill_process(state_container: StateContainer, span_id: usize) -> anyhow::Result<()> {
+    if let Some(process) = take_process(&state_container, span_id).await {
+        release_process(&process).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes the process of `span_id` from the processes without ending it.
+async fn take_process(
+    state_container: &StateContainer,
+    span_id: usize,
+) -> Option<Arc<RwLock<Process>>> {
+    let mut processes = state_container.processes.write().await;
+    let mut delete_index = None;
+    for (index, process) in processes.iter().enumerate() {
+        if process.read().await.span_id == span_id {
+            delete_index = Some(index);
+            break;
+        }
+    }
+
+    delete_index.map(|index| processes.remove(index))
+}
+
+/// Hangs up on a process taken out with `take_process`.
+async fn release_process(process: &Arc<RwLock<Process>>) -> anyhow::Result<()> {
+    let terminal = process.read().await.terminal.clone();
+    let mut terminal = terminal.lock().await;
+    terminal.release().await?;
+
+    Ok(())
+}
+
 pub async fn remove_node_from_state(
     state_container: StateContainer,
     span_id: usize,
 ) -> anyhow::Result<()> {
+    remove_from_zoomed_layout(&state_container, span_id).await;
     {
         let state = state_container.state();
         let mut root_guard = state.root_node.write().await;
         let root = root_guard.as_mut();
         match root {
-            None => {
-                return Ok(());
-            }
+            None => {}
             Some(root) => {
-                let new_active = remove_node(root, span_id)?;
-                match new_active {
-                    Some(new_active) => {
-                        state
-                            .active_id
-                            .store(new_active, std::sync::atomic::Ordering::Relaxed);
-                    }
-                    None => {
-                        exit(0);
+                if root.find_by_id(span_id).is_none() {
+                    drop(root_guard);
+                    remove_from_background_windows(&state_container, span_id).await;
+                } else {
+                    match remove_node(root, span_id)? {
+                        Some(new_active) => {
+                            state_container.set_active_span(new_active);
+                        }
+                        None => {
+                            *root_guard = None;
+                            drop(root_guard);
+                            let exit_on_last_pane = { state.config.read().await.exit_on_last_pane };
+                            if !replace_empty_window(&state_container).await && exit_on_last_pane {
+                                exit(0);
+                            }
+                        }
                     }
                 }
             }
         };
     }
+    // Only after focusing another pane, which records when this one was left.
+    match state_container.last_active.lock() {
+        Ok(mut last_active) => {
+            last_active.remove(&span_id);
+        }
+        Err(err) => tracing::error!(
+            "Could not forget when pane {} was focused: {}",
+            span_id,
+            err
+        ),
+    }
 
     Ok(())
 }
@@ -1,19 +1,26 @@
 use std::{future::Future, pin::Pin, sync::Arc};
 
-use crate::draw::draw_loop;
+use crate::control::control_loop;
+use crate::draw::{draw_loop, trigger_draw};
 use crate::escape_codes::{
-    AllMotionTracking, ClearScreen, SetAlternateScreenBuffer, SetWin32InputMode, SgrMouseHandling,
+    ClearScreen, SetAlternateScreenBuffer, SetScrollRegion, SetWin32InputMode,
 };
-use crate::input::handle_stdin;
+use crate::exit::{
+    exit, set_alternate_screen_in_use, set_input_modes_in_use, write_restore_sequence, ScreenModes,
+};
+use crate::input::{handle_stdin, mouse_capture_sequence};
+use crate::project::open_initial_panes;
 use crate::size::update_size;
-use crate::spawn::create_process;
+use crate::spawn::kill_process;
 use crate::state::StateContainer;
+use crate::status::status_loop;
 use crate::terminal::enable_raw_mode;
+use crate::zoom::zoom_on_startup;
 use crossterm::event::{
-    EnableBracketedPaste, EnableFocusChange, EnableMouseCapture, KeyboardEnhancementFlags,
+    EnableBracketedPaste, EnableFocusChange, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
     PushKeyboardEnhancementFlags,
 };
-use crossterm::execute;
+use crossterm::{execute, Command};
 use tokio::{io::AsyncWriteExt, sync::Mutex, task::JoinSet};
 
 async fn handle_loop<F, R>(func: F) -> anyhow::Result<()>
@@ -70,54 +77,236 @@ async fn handle_child_processes(
 async fn init_screen(state_container: StateContainer) -> anyhow::Result<()> {
     enable_raw_mode().map_err(|err| anyhow::Error::from_boxed(err))?;
     update_size(state_container.clone()).await?;
+    enable_terminal_modes()?;
+    {
+        let config = state_container.config.read().await;
+        set_alternate_screen_in_use(config.use_alternate_screen);
+        set_input_modes_in_use(
+            config.win32_input && cfg!(target_os = "windows"),
+            config.enhanced_keyboard,
+        );
+    }
+
+    write_startup_sequence(state_container).await
+}
 
+/// Puts the host terminal into raw mode and turns on the input reporting
+/// citymux relies on. Mouse reporting is part of `write_mode_sequence`.
+fn enable_terminal_modes() -> anyhow::Result<()> {
     crossterm::terminal::enable_raw_mode()?;
-    execute!(
-        std::io::stdout(),
-        EnableBracketedPaste,
-        EnableFocusChange,
-        EnableMouseCapture,
-    )?;
-
-    let _ignored = execute!(
-        std::io::stdout(),
-        PushKeyboardEnhancementFlags(
-            KeyboardEnhancementFlags::empty()
-                .union(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
-                .union(KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES)
-                .union(KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS)
-                .union(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
-        )
-    );
+    execute!(std::io::stdout(), EnableBracketedPaste, EnableFocusChange)?;
+
+    Ok(())
+}
+
+/// Turns on the kitty keyboard protocol, with every key reported as an escape code.
+fn enhanced_keyboard_sequence() -> String {
+    let mut sequence = String::new();
+    let _ignored = PushKeyboardEnhancementFlags(
+        KeyboardEnhancementFlags::empty()
+            .union(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+            .union(KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES)
+            .union(KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS)
+            .union(KeyboardEnhancementFlags::REPORT_EVENT_TYPES),
+    )
+    .write_ansi(&mut sequence);
+
+    sequence
+}
+
+/// Prepares the screen through the state's output: switches to the alternate
+/// screen or makes room for inline drawing, and turns on mouse reporting when
+/// it is enabled.
+pub async fn write_startup_sequence(state_container: StateContainer) -> anyhow::Result<()> {
+    let (use_alternate_screen, enable_mouse) = {
+        let config = state_container.config.read().await;
+        (config.use_alternate_screen, config.enable_mouse)
+    };
+    if !use_alternate_screen {
+        // Scroll the current screen contents into the scrollback instead of clearing
+        // them, so that frames drawn from the top-left corner don't overwrite them.
+        let rows = { state_container.size.read().await.y };
+        let newlines = "\n".repeat(rows.max(0) as usize);
+        let stdout = state_container.state().stdout.clone();
+        stdout.lock().await.write_all(newlines.as_bytes()).await?;
+    }
+
+    write_mode_sequence(&state_container, use_alternate_screen, enable_mouse, false).await
+}
 
+/// Writes the screen modes citymux draws with, which the host terminal can
+/// lose while citymux is suspended.
+/// Writes the sequences that set the screen up for drawing. The kitty keyboard
+/// flags are kept on a stack by the terminal, `pop_keyboard_flags` pops the
+/// ones pushed before so that they aren't pushed twice.
+async fn write_mode_sequence(
+    state_container: &StateContainer,
+    use_alternate_screen: bool,
+    enable_mouse: bool,
+    pop_keyboard_flags: bool,
+) -> anyhow::Result<()> {
+    let (win32_input, enhanced_keyboard) = {
+        let config = state_container.config.read().await;
+        (config.win32_input, config.enhanced_keyboard)
+    };
     let stdout = state_container.state().stdout.clone();
     let mut stdout = stdout.lock().await;
+    if use_alternate_screen {
+        stdout
+            .write(SetAlternateScreenBuffer::new(true).into())
+            .await?;
+        stdout.write(ClearScreen::new().into()).await?;
+    }
     stdout
-        .write(SetAlternateScreenBuffer::new(true).into())
+        .write_all(&Into::<Vec<u8>>::into(SetScrollRegion::reset()))
         .await?;
-    stdout.write(ClearScreen::new().into()).await?;
-    stdout.write(AllMotionTracking::new(true).into()).await?;
-    stdout.write(SgrMouseHandling::new(true).into()).await?;
+    if win32_input && cfg!(target_os = "windows") {
+        stdout
+            .write_all(SetWin32InputMode::new(true).into())
+            .await?;
+    }
+    if enhanced_keyboard {
+        if pop_keyboard_flags {
+            let mut sequence = String::new();
+            let _ignored = PopKeyboardEnhancementFlags.write_ansi(&mut sequence);
+            stdout.write_all(sequence.as_bytes()).await?;
+        }
+        stdout
+            .write_all(enhanced_keyboard_sequence().as_bytes())
+            .await?;
+    }
+    if enable_mouse {
+        stdout.write_all(&mouse_capture_sequence(true)).await?;
+    }
     stdout.flush().await?;
 
     Ok(())
 }
 
+/// Restores the screen after citymux was resumed from a suspension: the
+/// modes are set again and the next frame is drawn in full. After `suspend`
+/// the modes were turned off, otherwise citymux was stopped from outside and
+/// the keyboard flags it pushed are still there.
+pub async fn redraw_after_resume(state_container: &StateContainer) -> anyhow::Result<()> {
+    let suspended = state_container
+        .suspended
+        .swap(false, std::sync::atomic::Ordering::Relaxed);
+    let (use_alternate_screen, enable_mouse) = {
+        let config = state_container.config.read().await;
+        (
+            config.use_alternate_screen,
+            state_container
+                .mouse_enabled
+                .load(std::sync::atomic::Ordering::Relaxed),
+        )
+    };
+    write_mode_sequence(
+        state_container,
+        use_alternate_screen,
+        enable_mouse,
+        !suspended,
+    )
+    .await?;
+    state_container.invalidate().await;
+    trigger_draw(state_container).await;
+
+    Ok(())
+}
+
+/// Waits for `State::shutdown`, then closes every pane and exits.
+async fn shutdown_loop(state_container: StateContainer) -> anyhow::Result<()> {
+    state_container.shutdown.notified().await;
+    tracing::info!("Quitting");
+    let mut span_ids = Vec::new();
+    state_container
+        .for_each_process(|process| span_ids.push(process.span_id))
+        .await;
+    for span_id in span_ids {
+        if let Err(err) = kill_process(state_container.clone(), span_id).await {
+            tracing::warn!("Could not close pane {}: {:?}", span_id, err);
+        }
+    }
+    exit(0);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn resume_loop(state_container: StateContainer) -> anyhow::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut resumed = signal(SignalKind::from_raw(
+        nix::sys::signal::Signal::SIGCONT as i32,
+    ))?;
+    loop {
+        resumed.recv().await;
+        tracing::debug!("Resumed, restoring the screen");
+        enable_terminal_modes()?;
+        redraw_after_resume(&state_container).await?;
+    }
+}
+
+#[cfg(not(unix))]
+async fn resume_loop(_state_container: StateContainer) -> anyhow::Result<()> {
+    std::future::pending().await
+}
+
+/// Hands the host terminal back to the shell and stops citymux with SIGTSTP.
+/// In raw mode Ctrl-Z reaches the pane instead of raising the signal, so this
+/// is the way to suspend from the keyboard. `resume_loop` sets the screen up
+/// again on SIGCONT.
+#[cfg(unix)]
+pub async fn suspend(state_container: &StateContainer) -> anyhow::Result<()> {
+    let mut sequence = Vec::new();
+    write_restore_sequence(&mut sequence, ScreenModes::in_use())?;
+    {
+        let stdout = state_container.state().stdout.clone();
+        let mut stdout = stdout.lock().await;
+        stdout.write_all(&sequence).await?;
+        stdout.flush().await?;
+    }
+    crossterm::terminal::disable_raw_mode()?;
+    state_container
+        .suspended
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+    nix::sys::signal::raise(nix::sys::signal::Signal::SIGTSTP)?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn suspend(state_container: &StateContainer) -> anyhow::Result<()> {
+    state_container
+        .flash("Suspending is not supported here")
+        .await;
+
+    Ok(())
+}
+
 pub async fn run_application(state_container: StateContainer) -> anyhow::Result<()> {
     init_screen(state_container.clone()).await?;
     let rx = init_proc_handler(state_container.clone()).await?;
     let rx = Arc::new(Mutex::new(rx));
     let stdout_handler =
         handle_loop(|| handle_child_processes(state_container.clone(), rx.clone()));
-    create_process(state_container.clone()).await?;
+    open_initial_panes(&state_container).await?;
+    zoom_on_startup(&state_container).await?;
     let results = tokio::join!(
         handle_loop(|| handle_stdin(state_container.clone())),
         stdout_handler,
         handle_loop(|| draw_loop(state_container.clone())),
+        handle_loop(|| status_loop(state_container.clone())),
+        handle_loop(|| resume_loop(state_container.clone())),
+        handle_loop(|| control_loop(state_container.clone())),
+        handle_loop(|| shutdown_loop(state_container.clone())),
     );
     results.0?;
     results.1?;
     results.2?;
+    results.3?;
+    results.4?;
+    results.5?;
+    results.6?;
 
     Ok(())
 }
@@ -1,22 +1,34 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     ops::Deref,
     pin::Pin,
     sync::{
         atomic::{AtomicBool, AtomicUsize},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
-use renterm::{canvas::Canvas, rect::Rect, vector::Vector2};
+use crossterm::event::MouseButton;
+use renterm::{canvas::Canvas, color::Color, rect::Rect, surface::Surface, vector::Vector2};
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::{Mutex, RwLock},
+    sync::{Mutex, Notify, RwLock},
 };
 
 use crate::{
-    args::CliArgs, config::Config, draw::DrawMessage, layout::get_span_dimensions,
-    process::TerminalLike, span::Node, term::TerminalInfo,
+    args::CliArgs,
+    config::Config,
+    draw::{active_cursor_position, active_cursor_state, compose, DrawSignal, Frame, FrameLimiter},
+    hooks::{hook_title, run_hook, HookEvent},
+    layout::{get_pane_dimensions, pane_order},
+    metrics::RenderMetrics,
+    mode::InputMode,
+    picker::PanePicker,
+    process::{TerminalLike, Utf8Carryover},
+    span::Node,
+    term::{CursorState, TerminalInfo},
+    window::Window,
 };
 
 pub struct Process {
@@ -25,6 +37,51 @@ pub struct Process {
     pub terminal_info: Arc<Mutex<TerminalInfo>>,
     pub terminal: Arc<Mutex<Box<dyn TerminalLike>>>,
     pub span_id: usize,
+    /// Keys, pastes and mouse events are not forwarded to read-only panes.
+    pub read_only: bool,
+    pub pid: u32,
+    pub output_carryover: Arc<Mutex<Utf8Carryover>>,
+    /// Set once the program has written anything, until then the pane
+    /// shows a placeholder.
+    pub has_output: Arc<AtomicBool>,
+    /// What was started in the pane, so that it can be started again.
+    pub command: PaneCommand,
+    /// Set for panes without a program, like the help pane, which have
+    /// nothing to start again.
+    pub is_virtual: bool,
+    /// Color the pane's background is tinted with, see `draw::apply_tint`.
+    pub tint: Option<Color>,
+}
+
+/// A program started in a pane, with the title shown until it sets its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaneCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub title: Option<String>,
+    /// Initial `Process::tint` of the pane.
+    pub tint: Option<Color>,
+}
+
+impl PaneCommand {
+    /// The program and its arguments, separated by spaces.
+    pub fn command_line(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Where a closed pane was in the layout and what ran in it.
+#[derive(Debug, Clone)]
+pub struct ClosedPaneInfo {
+    /// The span the pane was a child of, `None` if it was the root.
+    pub parent_id: Option<usize>,
+    pub index: usize,
+    pub size: f64,
+    pub command: PaneCommand,
 }
 
 pub struct State {
@@ -41,7 +98,7 @@ pub struct State {
             >,
         >,
     >,
-    pub draw_channel: Arc<Mutex<Option<tokio::sync::mpsc::Sender<DrawMessage>>>>,
+    pub draw_signal: Arc<DrawSignal>,
     canvas_1: Arc<Mutex<Canvas>>,
     canvas_2: Arc<Mutex<Canvas>>,
     canvas_toggle: AtomicBool,
@@ -51,6 +108,52 @@ pub struct State {
     pub current_mouse_buttons: Arc<RwLock<HashMap<u8, bool>>>,
     pub active_id: AtomicUsize,
     pub draw_lock: Arc<Mutex<()>>,
+    pub pending_message: Arc<RwLock<Option<(String, tokio::time::Instant)>>>,
+    /// A multiline paste waiting for the user to confirm it.
+    pub pending_paste: Arc<Mutex<Option<String>>>,
+    /// Set while the pane picker is open, see `picker::handle_picker_key`.
+    pub pane_picker: Arc<Mutex<Option<PanePicker>>>,
+    input_mode: Arc<Mutex<InputMode>>,
+    /// Notified to close every pane and exit, see `startup::shutdown_loop`.
+    pub shutdown: Arc<Notify>,
+    /// Whether mouse events are captured, rather than left to the host terminal.
+    pub mouse_enabled: AtomicBool,
+    /// Set by `startup::suspend` once the host terminal was handed back, so
+    /// that resuming doesn't undo more than the suspension did.
+    pub suspended: AtomicBool,
+    /// When a bell was last forwarded to the host, see `process::forward_bell`.
+    pub last_forwarded_bell: Arc<Mutex<Option<Instant>>>,
+    /// Position and time of the last left click, to tell double clicks apart.
+    pub last_click: Arc<Mutex<Option<(Vector2, Instant)>>>,
+    /// Button whose press focused a pane with `Config::focus_click_swallow`
+    /// set. The drags and the release that follow it aren't forwarded either.
+    pub swallowed_button: Arc<Mutex<Option<MouseButton>>>,
+    /// Set after the prefix key, until the key that follows it.
+    pub pending_prefix: AtomicBool,
+    /// Counts prefix presses, so a timeout only clears the press it started for.
+    pub prefix_presses: AtomicUsize,
+    /// Draws pane ids and dimensions over the layout, see `--debug-layout`.
+    pub debug_layout: AtomicBool,
+    pub frame_limiter: Arc<Mutex<FrameLimiter>>,
+    /// Layouts that aren't currently shown, in the order they are cycled through.
+    pub windows: Arc<RwLock<VecDeque<Window>>>,
+    /// Whether the status bar is shown, starts as `Config::status_bar`.
+    pub status_bar_visible: AtomicBool,
+    /// Last output of `Config::status_right_command`.
+    pub status_right: Arc<RwLock<Option<String>>>,
+    /// Recently closed panes, most recent last, see `spawn::reopen_closed_pane`.
+    pub closed_panes: Arc<Mutex<Vec<ClosedPaneInfo>>>,
+    /// Pane that keeps its area while new panes are stacked next to it.
+    pub main_pane: Arc<RwLock<Option<usize>>>,
+    /// The layout from before the main pane was set, brought back when it is
+    /// cleared, see `main_pane::toggle_main_pane`.
+    pub layout_before_main_pane: Arc<Mutex<Option<Node>>>,
+    /// Only updated with `--debug-metrics`.
+    pub render_metrics: Arc<Mutex<RenderMetrics>>,
+    /// The layout hidden while a pane is zoomed, see `zoom::zoom`.
+    pub zoomed_layout: Arc<Mutex<Option<Node>>>,
+    /// When each pane was last focused or left, see `input::handle_navigation`.
+    pub last_active: Arc<std::sync::Mutex<HashMap<usize, Instant>>>,
 }
 
 impl State {
@@ -76,6 +179,11 @@ impl State {
             self.canvas_2.clone()
         }
     }
+    /// Makes the next frame emit every cell, for when the screen contents
+    /// can no longer be trusted.
+    pub async fn invalidate(&self) {
+        *self.get_last_canvas().lock().await = Canvas::default();
+    }
     pub fn swap_canvas(&self) {
         self.canvas_toggle.store(
             !self
@@ -86,16 +194,68 @@ impl State {
     }
     pub async fn active_process(&self) -> Option<Arc<RwLock<Process>>> {
         let active_process_id = self.active_id.load(std::sync::atomic::Ordering::Relaxed);
-        let lock = self.processes.read().await;
-        for process in lock.iter() {
-            let lock = process.read().await;
-            if lock.span_id == active_process_id {
-                return Some(process.clone());
+        self.find_process(|process| process.span_id == active_process_id)
+            .await
+    }
+    /// Returns the first process matching `predicate`.
+    pub async fn find_process(
+        &self,
+        mut predicate: impl FnMut(&Process) -> bool,
+    ) -> Option<Arc<RwLock<Process>>> {
+        let processes = self.processes.read().await.clone();
+        for process in processes {
+            if predicate(&*process.read().await) {
+                return Some(process);
             }
         }
 
         None
     }
+    /// Calls `f` with each process in turn. The process list is copied first,
+    /// so `f` may start or close panes without deadlocking.
+    pub async fn for_each_process(&self, mut f: impl FnMut(&Process)) {
+        let processes = self.processes.read().await.clone();
+        for process in processes {
+            f(&*process.read().await);
+        }
+    }
+    /// Panes of the layout on screen, in the order of `layout::pane_order`.
+    pub async fn ordered_pane_ids(&self) -> Vec<usize> {
+        let size = self.layout_size().await;
+        let gap = self.config.read().await.pane_gap;
+        let root = self.root_node.read().await;
+        match root.as_ref() {
+            Some(root) => pane_order(root, size, gap),
+            None => Vec::new(),
+        }
+    }
+    /// Processes of the panes on screen in pane order, followed by those in
+    /// background windows.
+    pub async fn ordered_processes(&self) -> Vec<Arc<RwLock<Process>>> {
+        let order = self.ordered_pane_ids().await;
+        let processes = self.processes.read().await.clone();
+        let mut keyed = Vec::with_capacity(processes.len());
+        for process in processes {
+            let span_id = process.read().await.span_id;
+            let index = order
+                .iter()
+                .position(|id| *id == span_id)
+                .unwrap_or(order.len());
+            keyed.push((index, process));
+        }
+        keyed.sort_by_key(|(index, _)| *index);
+
+        keyed.into_iter().map(|(_, process)| process).collect()
+    }
+    /// Returns the PID of the process running in a pane.
+    pub async fn pid_of(&self, span_id: usize) -> Option<u32> {
+        let process = self
+            .find_process(|process| process.span_id == span_id)
+            .await?;
+        let pid = process.read().await.pid;
+
+        Some(pid)
+    }
     pub async fn active_terminal_info(&self) -> Option<Arc<Mutex<TerminalInfo>>> {
         let active_process = self.active_process().await?;
         let terminal_info = { active_process.read().await.terminal_info.clone() };
@@ -107,11 +267,24 @@ impl State {
         let terminal_info = terminal_info.lock().await;
         Some(terminal_info.application_keypad_mode())
     }
+    /// Number of rows taken by the status bar.
+    pub async fn status_bar_height(&self) -> i32 {
+        i32::from(
+            self.status_bar_visible
+                .load(std::sync::atomic::Ordering::Relaxed),
+        )
+    }
+    /// Size of the area the panes are laid out in, the screen minus the status bar.
+    pub async fn layout_size(&self) -> Vector2 {
+        let size = self.size.read().await.to_owned();
+        layout_size(size, self.status_bar_height().await)
+    }
     pub async fn get_span_dimensions(&self, span_id: usize) -> Option<Rect> {
+        let size = self.layout_size().await;
         let root_node = self.root_node.read().await;
         let root_node = root_node.as_ref()?;
-        let size = self.size.read().await.to_owned();
-        get_span_dimensions(root_node, span_id, size)
+        let gap = self.config.read().await.pane_gap;
+        get_pane_dimensions(root_node, span_id, size, gap)
     }
     pub fn new(
         args: CliArgs,
@@ -119,6 +292,10 @@ impl State {
         input: impl AsyncRead + Unpin + Send + Sync + 'static,
         output: impl AsyncWrite + Unpin + Send + Sync + 'static,
     ) -> Self {
+        let mouse_enabled = AtomicBool::new(config.enable_mouse);
+        let debug_layout = AtomicBool::new(args.debug_layout);
+        let frame_limiter = FrameLimiter::new(config.max_fps);
+        let status_bar_visible = AtomicBool::new(config.status_bar);
         State {
             args,
             config: Arc::new(RwLock::new(config)),
@@ -127,7 +304,7 @@ impl State {
             size: Arc::new(RwLock::new(Vector2::null())),
             processes: Arc::new(RwLock::new(Vec::new())),
             process_channel: Arc::new(Mutex::new(None)),
-            draw_channel: Arc::new(Mutex::new(None)),
+            draw_signal: Arc::new(DrawSignal::default()),
             canvas_1: Arc::new(Mutex::new(Canvas::new(Vector2::new(0, 0)))),
             canvas_2: Arc::new(Mutex::new(Canvas::new(Vector2::new(0, 0)))),
             canvas_toggle: AtomicBool::new(false),
@@ -137,22 +314,122 @@ impl State {
             current_mouse_position: Arc::new(RwLock::new(Vector2::null())),
             current_mouse_buttons: Arc::new(RwLock::new(HashMap::new())),
             draw_lock: Arc::new(Mutex::new(())),
+            pending_message: Arc::new(RwLock::new(None)),
+            pending_paste: Arc::new(Mutex::new(None)),
+            pane_picker: Arc::new(Mutex::new(None)),
+            input_mode: Arc::new(Mutex::new(InputMode::Normal)),
+            shutdown: Arc::new(Notify::new()),
+            mouse_enabled,
+            suspended: AtomicBool::new(false),
+            last_forwarded_bell: Arc::new(Mutex::new(None)),
+            last_click: Arc::new(Mutex::new(None)),
+            swallowed_button: Arc::new(Mutex::new(None)),
+            pending_prefix: AtomicBool::new(false),
+            prefix_presses: AtomicUsize::new(0),
+            debug_layout,
+            frame_limiter: Arc::new(Mutex::new(frame_limiter)),
+            windows: Arc::new(RwLock::new(VecDeque::new())),
+            status_bar_visible,
+            status_right: Arc::new(RwLock::new(None)),
+            closed_panes: Arc::new(Mutex::new(Vec::new())),
+            main_pane: Arc::new(RwLock::new(None)),
+            layout_before_main_pane: Arc::new(Mutex::new(None)),
+            render_metrics: Arc::new(Mutex::new(RenderMetrics::default())),
+            zoomed_layout: Arc::new(Mutex::new(None)),
+            last_active: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
-    pub fn set_active_span(&self, span_id: usize) {
-        self.active_id
-            .store(span_id, std::sync::atomic::Ordering::Relaxed)
-    }
     pub async fn set_mouse_position(&self, position: impl Into<Vector2>) {
         let mut lock = self.current_mouse_position.write().await;
         *lock = position.into();
     }
+    /// Shows a transient message on the bottom row for `Config::message_duration_ms`.
+    pub async fn flash(&self, message: impl Into<String>) {
+        let message = message.into();
+        tracing::debug!("Flashing message: {}", message);
+        {
+            let mut pending_message = self.pending_message.write().await;
+            *pending_message = Some((message, tokio::time::Instant::now()));
+        }
+        let duration = Duration::from_millis(self.config.read().await.message_duration_ms);
+        let draw_signal = self.draw_signal.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(duration).await;
+            draw_signal.request();
+        });
+    }
+    /// Returns the flashed message, clearing it once its duration has elapsed.
+    pub async fn current_message(&self) -> Option<String> {
+        let duration = Duration::from_millis(self.config.read().await.message_duration_ms);
+        let mut pending_message = self.pending_message.write().await;
+        let (message, created_at) = pending_message.as_ref()?;
+        if created_at.elapsed() >= duration {
+            *pending_message = None;
+            return None;
+        }
+
+        Some(message.clone())
+    }
+    /// Returns the confirmation prompt for a pending paste, if there is one.
+    pub async fn paste_prompt(&self) -> Option<String> {
+        let pending_paste = self.pending_paste.lock().await;
+        let lines = pending_paste.as_ref()?.lines().count();
+
+        Some(format!("Paste {} lines? [y/n]", lines))
+    }
+    pub async fn input_mode(&self) -> InputMode {
+        *self.input_mode.lock().await
+    }
+    pub async fn set_input_mode(&self, mode: InputMode) {
+        *self.input_mode.lock().await = mode;
+    }
+    /// Returns the quit confirmation prompt, if it is shown.
+    pub async fn quit_prompt(&self) -> Option<String> {
+        if self.input_mode().await != InputMode::ConfirmQuit {
+            return None;
+        }
+        let panes = self.processes.read().await.len();
+
+        Some(format!("Quit and close {} panes? [y/n]", panes))
+    }
+    /// Returns the hint shown while a mode other than `InputMode::Normal` is on.
+    pub async fn mode_prompt(&self) -> Option<String> {
+        match self.input_mode().await {
+            InputMode::Normal => None,
+            InputMode::Resize => Some("Resize: arrows resize the pane, Esc exits".to_string()),
+            InputMode::ConfirmQuit => self.quit_prompt().await,
+        }
+    }
     pub async fn set_size(&self, size: impl Into<Vector2>) {
         let mut lock = self.size.write().await;
         *lock = size.into();
     }
 }
 
+#[cfg(test)]
+impl State {
+    /// Creates a state with the test args and config whose input and output
+    /// are in-memory pipes. Returns the ends to write input into and to read
+    /// the output from.
+    pub fn new_for_test() -> (State, tokio::io::DuplexStream, tokio::io::DuplexStream) {
+        let (input_writer, input) = tokio::io::duplex(64 * 1024);
+        let (output, output_reader) = tokio::io::duplex(64 * 1024);
+        let state = State::new(
+            crate::test::test_args(),
+            crate::test::test_config(),
+            input,
+            output,
+        );
+
+        (state, input_writer, output_reader)
+    }
+}
+
+/// Shrinks a screen size by the rows reserved for the status bar.
+pub fn layout_size(size: Vector2, status_bar_height: i32) -> Vector2 {
+    Vector2::new(size.x, (size.y - status_bar_height).max(0))
+}
+
 #[derive(Clone)]
 pub struct StateContainer {
     state: Arc<State>,
@@ -166,6 +443,77 @@ impl StateContainer {
     pub fn state(&self) -> Arc<State> {
         self.state.clone()
     }
+    /// Focuses a pane, running the `pane_focused` hook when it wasn't focused.
+    /// No task is spawned without a hook, unless the config is being written
+    /// to right now, in which case `run_hook` looks again.
+    pub fn set_active_span(&self, span_id: usize) {
+        let previous = self
+            .active_id
+            .swap(span_id, std::sync::atomic::Ordering::Relaxed);
+        if previous == span_id {
+            return;
+        }
+        match self.last_active.lock() {
+            Ok(mut last_active) => {
+                let now = Instant::now();
+                last_active.insert(previous, now);
+                last_active.insert(span_id, now);
+            }
+            Err(err) => tracing::error!(
+                "Could not record when pane {} was focused: {}",
+                span_id,
+                err
+            ),
+        }
+        let has_hook = match self.config.try_read() {
+            Ok(config) => config.hooks.pane_focused.is_some(),
+            Err(_) => true,
+        };
+        if !has_hook {
+            return;
+        }
+        let state_container = self.clone();
+        tokio::spawn(async move {
+            let title = hook_title(&state_container, span_id).await;
+            run_hook(&state_container, HookEvent::Focused, span_id, title).await;
+        });
+    }
+    /// Composes the screen at its current size without writing it anywhere,
+    /// for frontends that draw citymux with their own backend. Can be
+    /// called whenever `State::draw_signal` asks for a draw.
+    pub async fn compose_frame(&self) -> anyhow::Result<Frame> {
+        let size = self.size.read().await.to_owned();
+        self.compose_frame_at(size).await
+    }
+    /// Composes the screen at `size`, a snapshot of `State::size` that the
+    /// canvas, the pane PTYs and the cursor are all laid out against. A
+    /// resize that arrives while composing is left for the next draw.
+    pub async fn compose_frame_at(&self, size: Vector2) -> anyhow::Result<Frame> {
+        let mut canvas = Canvas::default();
+        let cursor = self.compose_into(&mut canvas, size).await?;
+
+        Ok(Frame { canvas, cursor })
+    }
+    /// Like `compose_frame_at`, but into a canvas that is kept between
+    /// frames, returning only the cursor. `draw` composes into the current
+    /// canvas this way instead of allocating one for every frame.
+    pub async fn compose_into(
+        &self,
+        canvas: &mut Canvas,
+        size: Vector2,
+    ) -> anyhow::Result<Option<(Vector2, CursorState)>> {
+        compose(self.clone(), canvas, size.clone()).await?;
+        let layout_size = layout_size(canvas.size(), self.status_bar_height().await);
+        let cursor = match active_cursor_position(self.clone(), layout_size).await {
+            Some(position) => Some((position, active_cursor_state(self.clone()).await)),
+            None => None,
+        };
+        if *self.size.read().await != size {
+            self.draw_signal.request();
+        }
+
+        Ok(cursor)
+    }
 }
 
 impl Deref for StateContainer {
@@ -0,0 +1,64 @@
+use std::process::Stdio;
+
+use crate::{draw::trigger_draw, process::shell_command, state::StateContainer};
+
+/// Runs a status command through the shell, returning the first line of its
+/// output. Failing commands and empty output give `None`.
+pub async fn run_status_command(command: &str) -> Option<String> {
+    let output = shell_command(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await;
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            tracing::debug!("Status command failed: {:?}", output.status);
+            return None;
+        }
+        Err(err) => {
+            tracing::debug!("Could not run status command: {:?}", err);
+            return None;
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().next()?.trim();
+
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+/// Runs `Config::status_right_command` once and stores its output.
+pub async fn refresh_status_right(state_container: &StateContainer) {
+    let command = {
+        state_container
+            .config
+            .read()
+            .await
+            .status_right_command
+            .clone()
+    };
+    let Some(command) = command else {
+        return;
+    };
+    let value = run_status_command(&command).await;
+    let changed = {
+        let mut status_right = state_container.status_right.write().await;
+        let changed = *status_right != value;
+        *status_right = value;
+        changed
+    };
+    if changed {
+        trigger_draw(state_container).await;
+    }
+}
+
+pub async fn status_loop(state_container: StateContainer) -> anyhow::Result<()> {
+    let interval_ms = { state_container.config.read().await.status_interval_ms };
+    let mut interval =
+        tokio::time::interval(std::time::Duration::from_millis(interval_ms.max(100)));
+    loop {
+        interval.tick().await;
+        refresh_status_right(&state_container).await;
+    }
+}
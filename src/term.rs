@@ -1,16 +1,39 @@
 use renterm::{
     canvas::Canvas,
     cell::{Cell, CellValue},
+    color::Color,
     style::Style,
     surface::Surface,
     vector::Vector2,
 };
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug, ops::Range};
 use vt100::Parser;
 
+use crate::scan::{GroundAction, OutputScanner, ScanEvent};
+
+/// A cell printed while an OSC 8 hyperlink was open.
+struct LinkedCell {
+    uri: String,
+    contents: String,
+}
+
 pub struct TerminalInfo {
     size: Vector2,
     parser: Parser,
+    scanner: vte::Parser,
+    hyperlink: Option<String>,
+    /// Linked cells keyed by (row, column). Entries whose contents no longer
+    /// match the screen have been overwritten or scrolled and are ignored.
+    linked_cells: HashMap<(u16, u16), LinkedCell>,
+    default_background: Color,
+    /// Shown as the title until the program sets its own.
+    fallback_title: Option<String>,
+    cursor_state: CursorState,
+    /// Answers to queries in the output, waiting to be written to the program.
+    replies: Vec<u8>,
+    /// Set by a bell in the output, until `take_bell`.
+    bell: bool,
+    selection: Option<Selection>,
 }
 
 impl Debug for TerminalInfo {
@@ -19,7 +42,63 @@ impl Debug for TerminalInfo {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum CursorShape {
+    /// Whatever the host terminal is configured to use.
+    #[default]
+    Default,
+    Block,
+    Underline,
+    Bar,
+}
+
+/// Cursor style requested by a pane through DECSCUSR and mode 12.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CursorState {
+    pub shape: CursorShape,
+    /// Whether the cursor blinks, `None` leaves it to the host terminal.
+    pub blink: Option<bool>,
+}
+
+impl CursorState {
+    /// Interprets the parameter of `CSI n SP q`.
+    pub fn from_decscusr(value: u16) -> Self {
+        let shape = match value {
+            1 | 2 => CursorShape::Block,
+            3 | 4 => CursorShape::Underline,
+            5 | 6 => CursorShape::Bar,
+            _ => return CursorState::default(),
+        };
+
+        CursorState {
+            shape,
+            blink: Some(value % 2 == 1),
+        }
+    }
+    /// The DECSCUSR parameter that selects this style on the host terminal.
+    pub fn to_decscusr(self) -> u16 {
+        let blink = self.blink.unwrap_or(true);
+        let steady = u16::from(!blink);
+        match (self.shape, self.blink) {
+            (CursorShape::Default, None) => 0,
+            (CursorShape::Default | CursorShape::Block, _) => 1 + steady,
+            (CursorShape::Underline, _) => 3 + steady,
+            (CursorShape::Bar, _) => 5 + steady,
+        }
+    }
+}
+
+/// Cells of a screen row picked out with the mouse, drawn inverted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Selection {
+    pub row: u16,
+    pub columns: Range<u16>,
+}
+
 const MIN_TERMINAL_SIZE: Vector2 = Vector2 { x: 5, y: 5 };
+const SCROLLBACK_LEN: usize = 1000;
+/// Primary DA reply: a VT100 with advanced video, as tmux and screen report.
+const DEVICE_ATTRIBUTES: &[u8] = b"\x1b[?1;2c";
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum MouseProtocolMode {
@@ -61,11 +140,97 @@ impl From<vt100::MouseProtocolEncoding> for MouseProtocolEncoding {
 
 impl TerminalInfo {
     pub fn process(&mut self, bytes: &[u8]) {
-        self.parser.process(bytes);
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let mut scanner = OutputScanner::new(self.hyperlink.is_some());
+            let consumed = self.scanner.advance_until_terminated(&mut scanner, bytes);
+            let (segment, rest) = bytes.split_at(consumed);
+            let has_text = scanner
+                .actions
+                .iter()
+                .any(|action| matches!(action, GroundAction::Print(_)));
+            if has_text {
+                self.process_linked_text(&scanner.actions);
+                if segment.last() == Some(&0x1b) {
+                    self.parser.process(b"\x1b");
+                }
+            } else {
+                self.parser.process(segment);
+            }
+            match scanner.event {
+                Some(ScanEvent::Hyperlink(uri)) => self.hyperlink = uri,
+                Some(ScanEvent::DefaultBackground(color)) => {
+                    self.default_background = color.unwrap_or_default();
+                }
+                Some(ScanEvent::CursorStyle(value)) => {
+                    self.cursor_state = CursorState::from_decscusr(value);
+                }
+                Some(ScanEvent::CursorBlink(blink)) => self.cursor_state.blink = Some(blink),
+                Some(ScanEvent::DeviceAttributes) => {
+                    self.replies.extend_from_slice(DEVICE_ATTRIBUTES);
+                }
+                Some(ScanEvent::StatusReport) => self.replies.extend_from_slice(b"\x1b[0n"),
+                Some(ScanEvent::CursorReport) => {
+                    let (row, column) = self.parser.screen().cursor_position();
+                    let report = format!("\x1b[{};{}R", row + 1, column + 1);
+                    self.replies.extend_from_slice(report.as_bytes());
+                }
+                Some(ScanEvent::Bell) => self.bell = true,
+                None => {}
+            }
+            bytes = rest;
+        }
+        self.prune_linked_cells();
+    }
+    /// Feeds text printed under an open hyperlink one character at a time,
+    /// recording where each character lands.
+    fn process_linked_text(&mut self, actions: &[GroundAction]) {
+        let Some(uri) = self.hyperlink.clone() else {
+            return;
+        };
+        for action in actions {
+            match action {
+                GroundAction::Print(c) => {
+                    let before = self.parser.screen().cursor_position();
+                    self.parser.process(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    let after = self.parser.screen().cursor_position();
+                    let position = if after.0 == before.0 {
+                        before
+                    } else {
+                        (after.0, 0)
+                    };
+                    let cell = LinkedCell {
+                        uri: uri.clone(),
+                        contents: c.to_string(),
+                    };
+                    self.linked_cells.insert(position, cell);
+                }
+                GroundAction::Execute(byte) => self.parser.process(&[*byte]),
+            }
+        }
+    }
+    fn linked_uri(&self, row: u16, column: u16, contents: &str) -> Option<String> {
+        let cell = self.linked_cells.get(&(row, column))?;
+        (cell.contents == contents).then(|| cell.uri.clone())
+    }
+    fn prune_linked_cells(&mut self) {
+        let (height, width) = self.parser.screen().size();
+        if self.linked_cells.len() <= height as usize * width as usize {
+            return;
+        }
+        let screen = self.parser.screen();
+        self.linked_cells.retain(|(row, column), linked| {
+            screen
+                .cell(*row, *column)
+                .is_some_and(|cell| cell.contents() == linked.contents)
+        });
     }
     pub fn application_keypad_mode(&self) -> bool {
         self.parser.screen().application_keypad()
     }
+    pub fn bracketed_paste_mode(&self) -> bool {
+        self.parser.screen().bracketed_paste()
+    }
     pub fn mouse_protocol_mode(&self) -> MouseProtocolMode {
         self.parser.screen().mouse_protocol_mode().into()
     }
@@ -74,60 +239,208 @@ impl TerminalInfo {
     }
     pub fn new(size: Vector2) -> Self {
         let size = size.max(MIN_TERMINAL_SIZE);
+        let parser_size = size.clone().saturating_cast::<u16>();
         TerminalInfo {
-            parser: vt100::Parser::new(size.y as u16, size.x as u16, 0),
+            parser: vt100::Parser::new(parser_size.y, parser_size.x, SCROLLBACK_LEN),
+            scanner: vte::Parser::new(),
+            hyperlink: None,
+            linked_cells: HashMap::new(),
+            default_background: Color::default(),
+            fallback_title: None,
+            cursor_state: CursorState::default(),
+            replies: Vec::new(),
+            bell: false,
+            selection: None,
             size,
         }
     }
-    pub fn set_size(&mut self, size: Vector2) {
+    /// Resizes the screen, keeping its contents. Returns whether the size changed.
+    pub fn set_size(&mut self, size: Vector2) -> bool {
         let size = size.max(MIN_TERMINAL_SIZE);
         if self.size == size {
-            return;
+            return false;
         }
-        self.parser.set_size(size.y as u16, size.x as u16);
+        let parser_size = size.clone().saturating_cast::<u16>();
+        self.parser.set_size(parser_size.y, parser_size.x);
         self.size = size;
+        self.selection = None;
+
+        true
+    }
+    pub fn size(&self) -> Vector2 {
+        self.size.clone()
+    }
+    /// Number of rows the view is scrolled back from the live screen.
+    pub fn scroll_offset(&self) -> usize {
+        self.parser.screen().scrollback()
+    }
+    /// Scrolls the view into the scrollback, positive values move towards older output.
+    pub fn scroll_by(&mut self, rows: isize) {
+        let offset = self.scroll_offset().saturating_add_signed(rows);
+        self.parser.set_scrollback(offset);
+    }
+    /// Takes the replies to DA and DSR queries found in the output, which
+    /// are to be written to the program's input.
+    pub fn take_replies(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.replies)
+    }
+    /// Returns whether the output rang the bell since the last call.
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell)
+    }
+    /// Number of rows of output kept above the screen.
+    pub fn scrollback_len(&mut self) -> usize {
+        let offset = self.scroll_offset();
+        self.parser.set_scrollback(usize::MAX);
+        let len = self.scroll_offset();
+        self.parser.set_scrollback(offset);
+
+        len
+    }
+    /// Forgets the output above the screen, keeping the screen, the cursor
+    /// and the input modes. vt100 can't drop its scrollback, so the parser
+    /// is replaced by one that is given the screen's state. Nothing happens
+    /// while the alternate screen is in use, as the main screen can't be read.
+    pub fn clear_scrollback(&mut self) {
+        if self.parser.screen().alternate_screen() {
+            return;
+        }
+        self.parser.set_scrollback(0);
+        let screen = self.parser.screen();
+        let state = screen.state_formatted();
+        let (rows, columns) = screen.size();
+        self.parser = vt100::Parser::new(rows, columns, SCROLLBACK_LEN);
+        self.parser.process(&state);
+    }
+    /// Columns of the word at a screen position, which runs until the
+    /// nearest blank cells or cells holding one of `separators` either side.
+    pub fn word_range(&self, row: u16, column: u16, separators: &str) -> Option<Range<u16>> {
+        let screen = self.parser.screen();
+        let (_, width) = screen.size();
+        let is_word = |column: u16| {
+            screen.cell(row, column).is_some_and(|cell| {
+                let contents = cell.contents();
+                cell.is_wide_continuation()
+                    || !contents.is_empty() && !contents.chars().all(|c| separators.contains(c))
+            })
+        };
+        if column >= width || !is_word(column) {
+            return None;
+        }
+        let mut start = column;
+        while start > 0 && is_word(start - 1) {
+            start -= 1;
+        }
+        let mut end = column + 1;
+        while end < width && is_word(end) {
+            end += 1;
+        }
+
+        Some(start..end)
+    }
+    /// Selects the word at a screen position, see `word_range`. Returns
+    /// whether there was a word there.
+    pub fn select_word(&mut self, row: u16, column: u16, separators: &str) -> bool {
+        self.selection = self
+            .word_range(row, column, separators)
+            .map(|columns| Selection { row, columns });
+
+        self.selection.is_some()
+    }
+    pub fn selection(&self) -> Option<&Selection> {
+        self.selection.as_ref()
+    }
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+    pub fn selected_text(&self) -> Option<String> {
+        let selection = self.selection.as_ref()?;
+        let text = self.parser.screen().contents_between(
+            selection.row,
+            selection.columns.start,
+            selection.row,
+            selection.columns.end,
+        );
+
+        Some(text)
     }
     pub fn title(&self) -> String {
-        self.parser.screen().title().to_string()
+        let title = self.parser.screen().title();
+        match &self.fallback_title {
+            Some(fallback_title) if title.is_empty() => fallback_title.clone(),
+            _ => title.to_string(),
+        }
+    }
+    pub fn set_fallback_title(&mut self, title: Option<String>) {
+        self.fallback_title = title;
     }
     pub fn cursor_position(&self) -> Vector2 {
         let (y, x) = self.parser.screen().cursor_position();
         Vector2::new(x, y)
     }
+    pub fn cursor_state(&self) -> CursorState {
+        self.cursor_state
+    }
     pub fn is_cursor_visible(&self) -> bool {
         !self.parser.screen().hide_cursor()
     }
+    /// Background the pane uses for cells without a color of their own, as
+    /// set through OSC 11.
+    pub fn default_background(&self) -> Color {
+        self.default_background.clone()
+    }
     pub fn draw(&self, canvas: &mut impl Surface) {
         let screen = self.parser.screen();
         let (height, width) = screen.size();
         let size = Vector2::new(width, height);
         canvas.set_size(size);
+        let is_live = screen.scrollback() == 0;
         for y in 0..height {
             for x in 0..width {
                 let position = (x, y).into();
                 let cell = screen.cell(y, x);
                 let Some(cell) = cell else {
-                    let style = Style::default();
+                    let style = Style::default().with_background_color(self.default_background());
                     let value = CellValue::from(" ");
                     let cell = Cell::new_styled(value, style);
                     canvas.set_cell(position, cell);
                     continue;
                 };
+                let background = match cell.bgcolor() {
+                    vt100::Color::Default => self.default_background(),
+                    color => color.into(),
+                };
                 let style = Style::default()
-                    .with_background_color(cell.bgcolor())
-                    .with_foreground_color(cell.fgcolor());
+                    .with_background_color(background)
+                    .with_foreground_color(cell.fgcolor())
+                    .with_bold(cell.bold())
+                    .with_italic(cell.italic())
+                    .with_underline(cell.underline())
+                    .with_inverse(cell.inverse() != self.is_selected(y, x));
                 let string_value = cell.contents();
-                let string_value = if string_value.is_empty() {
+                // A wide character left in the last column by a resize would
+                // spill over into the pane border, so it is drawn as a space,
+                // as vt100 does when writing one that doesn't fit.
+                let is_cut_off = cell.is_wide() && x + 1 >= width;
+                let string_value = if string_value.is_empty() || is_cut_off {
                     " ".to_string()
                 } else {
                     string_value
                 };
+                let hyperlink = is_live
+                    .then(|| self.linked_uri(y, x, &string_value))
+                    .flatten();
                 let value = CellValue::from(string_value);
-                let cell = Cell::new_styled(value, style);
+                let cell = Cell::new_styled(value, style).with_hyperlink(hyperlink);
                 canvas.set_cell(position, cell);
             }
         }
     }
+    fn is_selected(&self, row: u16, column: u16) -> bool {
+        self.selection
+            .as_ref()
+            .is_some_and(|selection| selection.row == row && selection.columns.contains(&column))
+    }
     pub fn canvas(&self) -> Canvas {
         let mut canvas = Canvas::default();
         self.draw(&mut canvas);
@@ -0,0 +1,354 @@
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton};
+use renterm::color::{Color, ColorDepth};
+
+use crate::{
+    config::{
+        detect_border_style, detect_color_depth, error_line, get_config_dirs, get_config_file_with,
+        parse_config, parse_launchers, parse_split_ratio, read_key, read_list, read_map,
+        split_command_line, BorderStyle, RenderStrategy, SplitRatio,
+    },
+    hooks::HookEvent,
+    keybind::{prefix_action, Action, MouseArea},
+    spawn::pane_env,
+};
+
+#[test]
+fn launchers_block_is_parsed() {
+    let document = kdl::KdlDocument::parse_v2(
+        "launchers {\n    gitui \"gitui\" key=\"g\"\n    top \"htop -d 10\" tint=\"#200000\"\n}\n",
+    )
+    .unwrap();
+    let launchers = parse_launchers(&document);
+
+    assert_eq!(launchers.len(), 2);
+    assert_eq!(launchers[0].name, "gitui");
+    assert_eq!(launchers[0].key, Some(KeyCode::Char('g')));
+    assert_eq!(launchers[1].key, None);
+    assert_eq!(launchers[0].tint, None);
+    assert_eq!(launchers[1].tint, Some(Color::new_rgb(32, 0, 0)));
+    assert_eq!(
+        launchers[1].command_line(),
+        Some(("htop".to_string(), vec!["-d".to_string(), "10".to_string()]))
+    );
+}
+
+#[test]
+fn command_lines_keep_quoted_arguments_together() {
+    assert_eq!(
+        split_command_line(r#"sh -c 'echo "a  b"' "it's" a\ b "q\"d" ''"#),
+        vec!["sh", "-c", r#"echo "a  b""#, "it's", "a b", "q\"d", ""]
+    );
+    assert_eq!(split_command_line("  "), Vec::<String>::new());
+    assert_eq!(
+        split_command_line("less 'open ended"),
+        vec!["less", "open ended"]
+    );
+}
+
+#[test]
+fn launcher_key_selects_launcher() {
+    let document = kdl::KdlDocument::parse_v2("launchers { top \"htop\" key=\"m\"; }").unwrap();
+    let launchers = parse_launchers(&document);
+    let event = KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE);
+
+    assert_eq!(
+        prefix_action(&event, &launchers, &[]),
+        Some(Action::Launch(0))
+    );
+    assert_eq!(prefix_action(&event, &[], &[]), Some(Action::ToggleMouse));
+}
+
+#[test]
+fn split_ratio_can_be_set_per_direction() {
+    let default = SplitRatio::new(0.5);
+    let document = kdl::KdlDocument::parse_v2("default_split_ratio 0.3 vertical=0.6").unwrap();
+    assert_eq!(
+        parse_split_ratio(&document, default),
+        Some(SplitRatio {
+            horizontal: 0.3,
+            vertical: 0.6
+        })
+    );
+
+    let document = kdl::KdlDocument::parse_v2("default_split_ratio 1.5").unwrap();
+    assert_eq!(parse_split_ratio(&document, default), Some(default));
+}
+
+fn test_config_home() -> PathBuf {
+    std::env::temp_dir().join(format!("citymux-config-test-{}", std::process::id()))
+}
+
+#[test]
+fn xdg_config_home_is_used_even_when_missing() {
+    let env =
+        |name: &str| (name == "XDG_CONFIG_HOME").then(|| test_config_home().display().to_string());
+    let dir = test_config_home();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(get_config_dirs(env), vec![dir.clone()]);
+    assert_eq!(get_config_file_with(env), None);
+
+    let config_file = dir.join("citymux").join("config.kdl");
+    std::fs::create_dir_all(config_file.parent().unwrap()).unwrap();
+    std::fs::write(&config_file, "pane_gap 1\n").unwrap();
+    assert_eq!(get_config_file_with(env), Some(config_file));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn empty_xdg_config_home_is_ignored() {
+    let env = |name: &str| (name == "XDG_CONFIG_HOME").then(String::new);
+
+    assert!(!get_config_dirs(env).contains(&PathBuf::new()));
+}
+
+#[test]
+fn malformed_config_is_an_error() {
+    assert!(parse_config("pane_gap {").is_err());
+
+    let config = parse_config("pane_gap 2\n").unwrap();
+    assert_eq!(config.pane_gap, 2);
+}
+
+#[test]
+fn canvas_cell_limit_is_read() {
+    let config = parse_config("max_canvas_cells 100000\n").unwrap();
+    assert_eq!(config.max_canvas_cells, 100_000);
+    let error = parse_config("max_canvas_cells 0\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("must be at least 1"));
+}
+
+fn document(contents: &str) -> kdl::KdlDocument {
+    kdl::KdlDocument::parse_v2(contents).unwrap()
+}
+
+#[test]
+fn string_and_bool_keys_are_read() {
+    let document = document("term \"xterm-256color\"\nenable_mouse #false\n");
+    assert_eq!(
+        read_key::<String>(&document, "term").unwrap(),
+        Some("xterm-256color".to_string())
+    );
+    assert_eq!(
+        read_key::<bool>(&document, "enable_mouse").unwrap(),
+        Some(false)
+    );
+    assert_eq!(read_key::<bool>(&document, "missing").unwrap(), None);
+}
+
+#[test]
+fn integer_keys_are_range_checked() {
+    let document = document("pane_gap 70000\nscroll_lines 5\n");
+    assert_eq!(
+        read_key::<usize>(&document, "scroll_lines").unwrap(),
+        Some(5)
+    );
+    let error = read_key::<u16>(&document, "pane_gap").unwrap_err();
+    assert!(error.to_string().contains("u16"));
+}
+
+#[test]
+fn wrong_type_is_described() {
+    let error = parse_config("default_shell 5\n").unwrap_err();
+    assert_eq!(error.to_string(), "`default_shell` expects a string, got 5");
+    assert!(parse_config("render_strategy \"fast\"\n").is_err());
+}
+
+#[test]
+fn list_and_map_keys_are_read() {
+    let document = document("default_shell_args \"-l\" \"-i\"\nenv {\n    EDITOR \"vim\"\n}\n");
+    assert_eq!(
+        read_list::<String>(&document, "default_shell_args").unwrap(),
+        Some(vec!["-l".to_string(), "-i".to_string()])
+    );
+    let env = read_map::<String>(&document, "env").unwrap().unwrap();
+    assert_eq!(env.get("EDITOR").map(String::as_str), Some("vim"));
+}
+
+#[test]
+fn config_keys_reach_the_config() {
+    let config = parse_config(
+        "term \"screen\"\nrender_strategy \"direct\"\nenv {\n    TERM \"ignored\"\n    LANG \"C\"\n}\n",
+    )
+    .unwrap();
+    assert_eq!(config.render_strategy, RenderStrategy::Direct);
+    let env = pane_env(&config);
+    assert_eq!(env.get("TERM").map(String::as_str), Some("screen"));
+    assert_eq!(env.get("LANG").map(String::as_str), Some("C"));
+}
+
+#[test]
+fn color_depth_is_detected_from_env() {
+    assert_eq!(
+        detect_color_depth(Some("truecolor"), Some("xterm-256color"), None),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        detect_color_depth(Some("24bit"), Some("xterm"), None),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        detect_color_depth(None, Some("screen-256color"), None),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(
+        detect_color_depth(Some("yes"), Some("xterm"), None),
+        ColorDepth::Ansi16
+    );
+    assert_eq!(
+        detect_color_depth(None, Some("xterm-256color"), Some("8f2b")),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        detect_color_depth(None, None, Some("8f2b")),
+        ColorDepth::TrueColor
+    );
+    let unset = if cfg!(target_os = "windows") {
+        ColorDepth::TrueColor
+    } else {
+        ColorDepth::Ansi16
+    };
+    assert_eq!(detect_color_depth(None, None, None), unset);
+}
+
+#[test]
+fn border_style_is_detected_from_env() {
+    assert_eq!(
+        detect_border_style(Some("dumb"), Some("en_US.UTF-8")),
+        BorderStyle::Ascii
+    );
+    assert_eq!(detect_border_style(Some("vt52"), None), BorderStyle::Ascii);
+    assert_eq!(
+        detect_border_style(Some("xterm-256color"), Some("en_US.ISO-8859-1")),
+        BorderStyle::Ascii
+    );
+    assert_eq!(
+        detect_border_style(Some("xterm"), Some("de_DE.ISO-8859-15@euro")),
+        BorderStyle::Ascii
+    );
+    assert_eq!(
+        detect_border_style(Some("xterm-256color"), Some("en_US.utf8")),
+        BorderStyle::Single
+    );
+    assert_eq!(
+        detect_border_style(Some("linux"), None),
+        BorderStyle::Single
+    );
+    assert_eq!(
+        detect_border_style(Some("xterm"), Some("C")),
+        BorderStyle::Single
+    );
+    assert_eq!(
+        detect_border_style(Some("xterm"), Some("POSIX")),
+        BorderStyle::Single
+    );
+    assert_eq!(detect_border_style(None, None), BorderStyle::Single);
+}
+
+#[test]
+fn border_style_can_be_forced() {
+    let config = parse_config("border_style \"rounded\"\n").unwrap();
+    assert_eq!(config.border_style, BorderStyle::Rounded);
+    assert_eq!(config.border_style.glyphs().top_left, "╭");
+
+    assert!(parse_config("border_style \"auto\"\n").is_ok());
+    assert!(parse_config("border_style \"double\"\n").is_err());
+}
+
+#[test]
+fn color_depth_can_be_overridden() {
+    let config = parse_config("color_depth \"256\"\n").unwrap();
+    assert_eq!(config.color_depth, ColorDepth::Ansi256);
+
+    let error = parse_config("color_depth \"lots\"\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("`color_depth` expects"));
+}
+
+#[test]
+fn key_bindings_need_a_known_action_and_a_string() {
+    let error = parse_config("keybindings {\n    t \"fly\"\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("expects \"send-string\""));
+    let error = parse_config("keybindings {\n    t \"send-string\"\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("expects the string to send"));
+    let error = parse_config("keybindings {\n    tt \"send-string\" \"x\"\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("single character key"));
+}
+
+#[test]
+fn prefix_key_conflicts_are_rejected() {
+    let error = parse_config("launchers { top \"htop\" key=\"m\"; }").unwrap_err();
+    assert!(format!("{:#}", error).contains("`launchers.top` uses the key m"));
+    let error = parse_config("keybindings {\n    n \"send-string\" \"x\"\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("already bound to NewPane"));
+    let error = parse_config(
+        "launchers { top \"htop\" key=\"t\"; }\nkeybindings {\n    t \"send-string\" \"x\"\n}\n",
+    )
+    .unwrap_err();
+    assert!(format!("{:#}", error).contains("already bound by `launchers.top`"));
+
+    assert!(parse_config("launchers { top \"htop\" key=\"t\"; }").is_ok());
+}
+
+#[test]
+fn hooks_are_parsed() {
+    let config = parse_config(
+        "hooks {\n    pane_created \"notify-send created\"\n    pane_closed \"logger $1\"\n}\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.hooks.pane_created.as_deref(),
+        Some("notify-send created")
+    );
+    assert_eq!(config.hooks.pane_focused, None);
+    assert_eq!(config.hooks.command(HookEvent::Closed), Some("logger $1"));
+
+    let error = parse_config("hooks {\n    pane_moved \"true\"\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("`hooks` expects"));
+}
+
+#[test]
+fn mouse_bindings_are_parsed() {
+    let config = parse_config(
+        "mousebindings {\n    middle \"new_pane\"\n    right \"break_pane\" area=\"title\"\n}\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.mouse_bindings.len(), 2);
+    assert_eq!(config.mouse_bindings[0].button, MouseButton::Middle);
+    assert_eq!(config.mouse_bindings[0].action, Action::NewPane);
+    assert_eq!(config.mouse_bindings[0].area, None);
+    assert_eq!(config.mouse_bindings[1].area, Some(MouseArea::Title));
+    assert!(config.mouse_bindings[1].matches(MouseButton::Right, MouseArea::Title));
+    assert!(!config.mouse_bindings[1].matches(MouseButton::Right, MouseArea::Content));
+
+    let error = parse_config("mousebindings {\n    middle \"fly\"\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("expects an action name"));
+}
+
+#[test]
+fn error_line_points_at_the_failing_node() {
+    let contents = "// scroll_lines is set below\nterm \"xterm\"\nscroll_lines \"many\"\n";
+    let error = parse_config(contents).unwrap_err();
+    assert_eq!(
+        error_line(contents, &error),
+        Some((3, "scroll_lines \"many\""))
+    );
+
+    let contents = "hooks {\n    pane_created \"echo\"\n    pane_moved \"echo\"\n}\n";
+    let error = parse_config(contents).unwrap_err();
+    assert_eq!(
+        error_line(contents, &error),
+        Some((3, "    pane_moved \"echo\""))
+    );
+
+    let contents = "term \"xterm\"\nscroll_lines {\n";
+    let error = parse_config(contents).unwrap_err();
+    assert_eq!(
+        error_line(contents, &error).map(|(number, _)| number),
+        Some(3)
+    );
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use renterm::{color::Color, vector::Vector2};
+
+use crate::{
+    control::{
+        control_socket_dir, create_socket_dir, handle_control_line, parse_control_command,
+        parse_escaped, run_control_command, ControlCommand,
+    },
+    span::{Node, NodeData},
+    test::{add_test_process, test_config, test_state},
+};
+
+#[test]
+fn escapes_are_decoded() {
+    assert_eq!(
+        parse_escaped(r"\e[31m\x41\\\n").unwrap(),
+        b"\x1b[31mA\\\n".to_vec()
+    );
+    assert_eq!(parse_escaped("hex:1b 5b 41").unwrap(), b"\x1b[A".to_vec());
+    assert!(parse_escaped(r"\x4").is_err());
+    assert!(parse_escaped(r"\xZZ").is_err());
+    assert!(parse_escaped(r"\q").is_err());
+    assert!(parse_escaped("abc\\").is_err());
+    assert!(parse_escaped("hex:1b5").is_err());
+}
+
+#[test]
+fn send_raw_is_parsed() {
+    assert_eq!(
+        parse_control_command(r"send-raw 2 \e[A").unwrap(),
+        ControlCommand::SendRaw {
+            span_id: 2,
+            bytes: b"\x1b[A".to_vec()
+        }
+    );
+    assert!(parse_control_command(r"send-raw two \e").is_err());
+    assert!(parse_control_command("launch-rockets").is_err());
+}
+
+#[tokio::test]
+async fn send_raw_reaches_the_pane() {
+    let state = test_state(test_config());
+    add_test_process(&state, 1, Vector2::new(10, 5)).await;
+    let (_, input) = add_test_process(&state, 2, Vector2::new(10, 5)).await;
+
+    let command = parse_control_command(r"send-raw 2 \e[31m").unwrap();
+    run_control_command(&state, command).await.unwrap();
+
+    assert_eq!(input.bytes(), vec![0x1b, 0x5b, 0x33, 0x31, 0x6d]);
+    let command = parse_control_command(r"send-raw 3 x").unwrap();
+    assert!(run_control_command(&state, command).await.is_err());
+}
+
+#[tokio::test]
+async fn send_raw_respects_read_only_panes() {
+    let state = test_state(test_config());
+    let (process, input) = add_test_process(&state, 1, Vector2::new(10, 5)).await;
+    process.write().await.read_only = true;
+
+    let reply = handle_control_line(&state, "send-raw 1 rm").await;
+
+    assert_eq!(reply, "error: Pane 1 is read-only");
+    assert!(input.bytes().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn control_socket_goes_in_a_private_directory() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let runtime_dir = |name: &str| (name == "XDG_RUNTIME_DIR").then(|| "/run/user/7".to_string());
+    assert_eq!(
+        control_socket_dir(runtime_dir),
+        PathBuf::from("/run/user/7/citymux")
+    );
+    let dir = control_socket_dir(|_| None);
+    assert!(dir.starts_with(std::env::temp_dir()));
+
+    let dir = std::env::temp_dir().join(format!("citymux-socket-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    create_socket_dir(&dir).unwrap();
+    let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+    create_socket_dir(&dir).unwrap();
+
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(create_socket_dir(&dir).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn control_lines_get_a_reply() {
+    let state = test_state(test_config());
+    let (_, input) = add_test_process(&state, 1, Vector2::new(10, 5)).await;
+
+    assert_eq!(handle_control_line(&state, r"send-raw 1 hi\r").await, "ok");
+    assert_eq!(input.bytes(), b"hi\r".to_vec());
+    assert!(handle_control_line(&state, "send-raw 1 \\")
+        .await
+        .starts_with("error: "));
+}
+
+#[tokio::test]
+async fn clear_history_defaults_to_the_active_pane() {
+    let state = test_state(test_config());
+    let (process, _) = add_test_process(&state, 1, Vector2::new(10, 5)).await;
+    state.set_active_span(1);
+    {
+        let process = process.read().await;
+        process
+            .terminal_info
+            .lock()
+            .await
+            .process(&b"x\r\n".repeat(20));
+    }
+
+    assert_eq!(
+        parse_control_command("clear-history").unwrap(),
+        ControlCommand::ClearHistory { span_id: None }
+    );
+    assert_eq!(handle_control_line(&state, "clear-history").await, "ok");
+    let process = process.read().await;
+    assert_eq!(process.terminal_info.lock().await.scrollback_len(), 0);
+}
+
+#[test]
+fn tint_is_parsed() {
+    assert_eq!(
+        parse_control_command("tint 2 #ff8000").unwrap(),
+        ControlCommand::Tint {
+            span_id: 2,
+            color: Some(Color::new_rgb(255, 128, 0))
+        }
+    );
+    assert_eq!(
+        parse_control_command("tint 2 none").unwrap(),
+        ControlCommand::Tint {
+            span_id: 2,
+            color: None
+        }
+    );
+    assert!(parse_control_command("tint 2 orange").is_err());
+}
+
+#[tokio::test]
+async fn print_layout_replies_with_the_diagram() {
+    let state = test_state(test_config());
+    assert_eq!(
+        parse_control_command("print-layout").unwrap(),
+        ControlCommand::PrintLayout
+    );
+    assert!(handle_control_line(&state, "print-layout")
+        .await
+        .starts_with("error: "));
+
+    *state.size.write().await = Vector2::new(10, 5);
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let reply = handle_control_line(&state, "print-layout").await;
+    assert!(reply.starts_with("+-"));
+    assert!(reply.contains("|1 "));
+    assert!(reply.ends_with("+\nok"));
+}
@@ -0,0 +1,867 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use renterm::{
+    canvas::Canvas, cell::Cell, color::Color, style::Style, surface::Surface, text::text_width,
+    vector::Vector2,
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    args::CliArgs,
+    config::ActiveIndicator,
+    draw::{
+        compose, draw, encode_canvas, format_title, inactive_cursor_positions, inverted_style,
+        run_draw_loop, sanitize_title, trigger_draw, DrawSignal, FrameLimiter, TitleFields,
+    },
+    input::handle_key_event,
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+    spawn::{add_process, create_span},
+    state::{PaneCommand, State, StateContainer},
+    status::refresh_status_right,
+    term::{CursorState, TerminalInfo},
+    virtual_process::virtual_process,
+};
+
+use super::{
+    add_test_process, run_process_tasks, test_args, test_config, test_state, test_state_with_output,
+};
+
+#[test]
+fn encode_unchanged_canvas() {
+    let last_canvas: Canvas = Canvas::new_filled(Vector2::new(200, 60), Cell::new('#'));
+    let new_canvas = last_canvas.clone();
+    let mut output = Vec::new();
+    let changed_cells = encode_canvas(Some(&last_canvas), &new_canvas, &mut output);
+    assert_eq!(changed_cells, 0);
+    assert!(output.is_empty());
+}
+
+#[test]
+fn encode_changed_cell() {
+    let last_canvas: Canvas = Canvas::new_filled(Vector2::new(200, 60), Cell::new('#'));
+    let mut new_canvas = last_canvas.clone();
+    new_canvas.set_cell(Vector2::new(10, 5), Cell::new('A'));
+    let mut output = Vec::new();
+    let changed_cells = encode_canvas(Some(&last_canvas), &new_canvas, &mut output);
+    assert_eq!(changed_cells, 1);
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("\x1b[6;11H"));
+    assert!(output.contains('A'));
+    assert!(!output.contains('#'));
+}
+
+#[test]
+fn encode_without_last_canvas() {
+    let new_canvas: Canvas = Canvas::new_filled(Vector2::new(20, 6), Cell::new('#'));
+    let mut output = Vec::new();
+    let changed_cells = encode_canvas(None, &new_canvas, &mut output);
+    assert_eq!(changed_cells, 20 * 6);
+}
+
+#[test]
+fn encode_counts_on_a_200_by_60_canvas() {
+    let last_canvas: Canvas = Canvas::new_filled(Vector2::new(200, 60), Cell::new('#'));
+    let mut new_canvas = last_canvas.clone();
+    new_canvas.set_cell(Vector2::new(199, 59), Cell::new('A'));
+    let mut output = Vec::new();
+
+    assert_eq!(encode_canvas(None, &new_canvas, &mut output), 200 * 60);
+    output.clear();
+    assert_eq!(
+        encode_canvas(Some(&last_canvas), &new_canvas, &mut output),
+        1
+    );
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("\x1b[60;200H"));
+}
+
+#[test]
+fn hyperlink_round_trips() {
+    let mut terminal = TerminalInfo::new(Vector2::new(20, 5));
+    terminal.process(b"\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\ text");
+    let canvas = terminal.canvas();
+    let link = Some("https://example.com".to_string());
+    assert_eq!(canvas.get_cell(Vector2::new(0, 0)).hyperlink, link);
+    assert_eq!(canvas.get_cell(Vector2::new(3, 0)).hyperlink, link);
+    assert_eq!(canvas.get_cell(Vector2::new(5, 0)).hyperlink, None);
+
+    let mut output = Vec::new();
+    encode_canvas(None, &canvas, &mut output);
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\ text"));
+}
+
+#[tokio::test]
+async fn software_cursor_inverts_cursor_cell() {
+    let mut config = test_config();
+    config.software_cursor = true;
+    let state = test_state(config);
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 8)).await;
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"ab");
+    }
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 10))
+        .await
+        .unwrap();
+
+    let cursor_cell = canvas.get_cell(Vector2::new(3, 1));
+    assert_eq!(cursor_cell.style.foreground_color(), Color::new_one_byte(0));
+    assert_eq!(cursor_cell.style.background_color(), Color::new_one_byte(7));
+    let text_cell = canvas.get_cell(Vector2::new(2, 1));
+    assert_eq!(text_cell.to_string(), "b");
+    assert_eq!(text_cell.style, Style::default());
+}
+
+#[tokio::test]
+async fn inactive_pane_cursors_are_marked_when_enabled() {
+    let mut config = test_config();
+    config.show_inactive_cursors = true;
+    let state = test_state(config);
+    let mut span = Span::new(SpanDirection::Horizontal);
+    for id in [1, 2, 3] {
+        span.children
+            .push(SpanChild::new(Node::new(id, NodeData::Void)));
+    }
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(1);
+    for (id, text) in [(1, "ab"), (2, "cd"), (3, "ef\x1b[?25l")] {
+        let (process, _) = add_test_process(&state, id, Vector2::new(8, 8)).await;
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(text.as_bytes());
+    }
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(30, 10))
+        .await
+        .unwrap();
+
+    let marked = |x: i32| {
+        let style = canvas.get_cell(Vector2::new(x, 1)).style;
+        style.is_dim() && style.background_color() == Color::new_one_byte(7)
+    };
+    assert!(
+        !marked(3),
+        "the active pane's cursor is left to the terminal"
+    );
+    assert!(marked(13));
+    assert!(!marked(12));
+    assert!(!marked(23), "hidden cursors aren't marked");
+
+    let busy = state.find_process(|process| process.span_id == 2).await;
+    let busy = busy.unwrap();
+    let busy = busy.read().await;
+    let _terminal_info = busy.terminal_info.lock().await;
+    let positions = inactive_cursor_positions(&state, Vector2::new(30, 10)).await;
+    assert!(positions.is_empty(), "busy panes are skipped");
+}
+
+#[tokio::test]
+async fn wide_char_in_the_last_column_does_not_cover_the_border() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(19, 8)).await;
+    {
+        let process = process.read().await;
+        process
+            .terminal_info
+            .lock()
+            .await
+            .process("abcdefghijklmnopq中".as_bytes());
+    }
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 10))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 1), "│abcdefghijklmnopq │");
+    let mut output = Vec::new();
+    encode_canvas(None, &canvas, &mut output);
+    assert!(!String::from_utf8(output).unwrap().contains('中'));
+}
+
+#[tokio::test]
+async fn panes_too_small_for_a_border_are_skipped() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    add_test_process(&state, 1, Vector2::new(10, 5)).await;
+
+    for size in [Vector2::new(1, 1), Vector2::new(2, 6), Vector2::new(8, 2)] {
+        let mut canvas = Canvas::default();
+        compose(state.clone(), &mut canvas, size.clone())
+            .await
+            .unwrap();
+        assert_eq!(canvas.size(), size);
+        assert_eq!(canvas.get_cell(Vector2::new(0, 0)).to_string(), " ");
+    }
+}
+
+#[test]
+fn title_format_placeholders_are_filled_in() {
+    let fields = TitleFields {
+        id: 3,
+        title: "vim".to_string(),
+        command: "bash -l".to_string(),
+        pid: 1234,
+        index: 2,
+    };
+    assert_eq!(
+        format_title("{index}: {title} (pid {pid}) #{id} {command}", &fields),
+        "2: vim (pid 1234) #3 bash -l"
+    );
+    assert_eq!(format_title("{nope} {title", &fields), "{nope} {title");
+}
+
+#[tokio::test]
+async fn custom_title_format_is_drawn() {
+    let mut config = test_config();
+    config.title_format = "{index}: {command} (pid {pid})".to_string();
+    let state = test_state(config);
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(28, 3)).await;
+    {
+        let mut process = process.write().await;
+        process.pid = 1234;
+        process.command = PaneCommand {
+            program: "bash".to_string(),
+            args: vec!["-l".to_string()],
+            title: None,
+            tint: None,
+        };
+    }
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(30, 5))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 0), "┌1: bash -l (pid 1234)───────┐");
+}
+
+#[tokio::test]
+async fn pane_numbers_follow_the_pane_order() {
+    let mut config = test_config();
+    config.title_format = "{index}".to_string();
+    let state = test_state(config);
+    let mut span = Span::new(SpanDirection::Horizontal);
+    for id in [7, 3] {
+        span.children
+            .push(SpanChild::new(Node::new(id, NodeData::Void)));
+        add_test_process(&state, id, Vector2::new(8, 3)).await;
+    }
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 0), "┌1───────┐┌2───────┐");
+}
+
+#[tokio::test]
+async fn control_characters_in_titles_are_replaced() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 3)).await;
+    {
+        let process = process.read().await;
+        let mut terminal_info = process.terminal_info.lock().await;
+        terminal_info.set_fallback_title(Some("a\x07b\x1b[2Jc".to_string()));
+    }
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 0), "┌[a?b?[2Jc]────────┐");
+    let mut output = Vec::new();
+    encode_canvas(None, &canvas, &mut output);
+    assert!(!output.contains(&0x07));
+
+    let long_title = "x".repeat(100_000);
+    assert_eq!(sanitize_title(&long_title).len(), 512);
+    let wide_title = "表".repeat(100_000);
+    assert_eq!(text_width(&sanitize_title(&wide_title)), 512);
+    let combining_title = "\u{301}".repeat(100_000);
+    assert_eq!(sanitize_title(&combining_title).chars().count(), 512);
+}
+
+#[tokio::test]
+async fn tinted_pane_backgrounds_take_the_tint() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 3)).await;
+    let tint = Color::new_rgb(40, 0, 0);
+    {
+        let mut process = process.write().await;
+        process.tint = Some(tint.clone());
+        process
+            .terminal_info
+            .lock()
+            .await
+            .process(b"\x1b[44mx\x1b[0m");
+    }
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    let empty = canvas.get_cell(Vector2::new(5, 2));
+    assert_eq!(empty.style.background_color(), tint);
+    let blue = canvas.get_cell(Vector2::new(1, 1));
+    assert_eq!(blue.style.background_color(), Color::new_rgb(8, 0, 190));
+    let border = canvas.get_cell(Vector2::new(0, 2));
+    assert_eq!(border.style.background_color(), Color::default());
+}
+
+fn row_text(canvas: &Canvas, y: i32) -> String {
+    (0..canvas.size().x)
+        .map(|x| canvas.get_cell(Vector2::new(x, y)).to_string())
+        .collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn flashed_message_expires() {
+    let mut config = test_config();
+    config.message_duration_ms = 50;
+    let state = test_state(config);
+    state.flash("hello").await;
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 4), "       hello        ");
+
+    tokio::time::advance(std::time::Duration::from_millis(49)).await;
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 4), "       hello        ");
+
+    tokio::time::advance(std::time::Duration::from_millis(1)).await;
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 4).trim(), "");
+}
+
+#[tokio::test]
+async fn debug_layout_labels_panes() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    add_test_process(&state, 1, Vector2::new(18, 3)).await;
+    state
+        .debug_layout
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    assert!(row_text(&canvas, 4).starts_with("└#1 0,0 20x5"));
+    assert_eq!(canvas.get_cell(Vector2::new(1, 1)).to_string(), "+");
+    assert_eq!(canvas.get_cell(Vector2::new(18, 3)).to_string(), "+");
+}
+
+#[tokio::test]
+async fn frame_limiter_spaces_frames() {
+    let mut limiter = FrameLimiter::new(100);
+    let start = std::time::Instant::now();
+    for _ in 0..5 {
+        limiter.wait().await;
+    }
+    assert!(start.elapsed() >= std::time::Duration::from_millis(40));
+}
+
+#[tokio::test]
+async fn rapid_triggers_coalesce_into_one_draw() {
+    let state = test_state(test_config());
+    for _ in 0..10 {
+        trigger_draw(&state).await;
+    }
+    assert!(state.draw_signal.take());
+    assert!(!state.draw_signal.take());
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_burst_of_triggers_draws_the_final_state() {
+    let signal = std::sync::Arc::new(DrawSignal::default());
+    let frame_limiter = std::sync::Arc::new(tokio::sync::Mutex::new(FrameLimiter::new(1000)));
+    let value = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let drawn = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let draw_loop = {
+        let value = value.clone();
+        let drawn = drawn.clone();
+        tokio::spawn(run_draw_loop(
+            signal.clone(),
+            std::time::Duration::from_secs(60),
+            frame_limiter,
+            move || {
+                let value = value.clone();
+                let drawn = drawn.clone();
+                async move {
+                    let seen = value.load(std::sync::atomic::Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+                    drawn.store(seen, std::sync::atomic::Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+        ))
+    };
+
+    for i in 1..=100 {
+        value.store(i, std::sync::atomic::Ordering::SeqCst);
+        signal.request();
+        if i % 10 == 0 {
+            tokio::time::advance(std::time::Duration::from_millis(1)).await;
+        }
+    }
+    // With the clock paused, this returns once the loop has nothing left to do.
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    draw_loop.abort();
+
+    assert_eq!(drawn.load(std::sync::atomic::Ordering::SeqCst), 100);
+}
+
+#[tokio::test(start_paused = true)]
+async fn draw_loop_draws_one_frame_at_a_time() {
+    let signal = std::sync::Arc::new(DrawSignal::default());
+    let frame_limiter = std::sync::Arc::new(tokio::sync::Mutex::new(FrameLimiter::new(100)));
+    let frames = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let is_drawing = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let draw_loop = {
+        let frames = frames.clone();
+        let is_drawing = is_drawing.clone();
+        tokio::spawn(run_draw_loop(
+            signal.clone(),
+            std::time::Duration::from_millis(20),
+            frame_limiter,
+            move || {
+                let frames = frames.clone();
+                let is_drawing = is_drawing.clone();
+                async move {
+                    assert!(!is_drawing.swap(true, std::sync::atomic::Ordering::SeqCst));
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    frames.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    is_drawing.store(false, std::sync::atomic::Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+        ))
+    };
+
+    let senders: Vec<_> = (0..20)
+        .map(|_| {
+            let signal = signal.clone();
+            tokio::spawn(async move {
+                for _ in 0..5 {
+                    signal.request();
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect();
+    for sender in senders {
+        sender.await.unwrap();
+    }
+    for _ in 0..50 {
+        tokio::time::advance(std::time::Duration::from_millis(1)).await;
+        tokio::task::yield_now().await;
+    }
+    draw_loop.abort();
+
+    let frames = frames.load(std::sync::atomic::Ordering::SeqCst);
+    assert!(frames >= 1);
+    assert!(frames <= 50 / 10 + 1);
+    assert!(draw_loop.await.unwrap_err().is_cancelled());
+}
+
+#[tokio::test]
+async fn active_indicator_marks_only_the_active_title() {
+    let mut config = test_config();
+    config.active_indicator = ActiveIndicator::Marker("*".to_string());
+    let state = test_state(config);
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(2);
+    add_test_process(&state, 1, Vector2::new(18, 8)).await;
+    add_test_process(&state, 2, Vector2::new(18, 8)).await;
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(40, 10))
+        .await
+        .unwrap();
+    let title_row: Vec<char> = row_text(&canvas, 0).chars().collect();
+    let inactive: String = title_row[..20].iter().collect();
+    let active: String = title_row[20..].iter().collect();
+    assert!(inactive.starts_with("┌[]"));
+    assert!(active.starts_with("┌* []"));
+}
+
+#[tokio::test]
+async fn active_pane_cursor_style_is_emitted() {
+    let (state, output) = test_state_with_output(test_config());
+    state.set_size(Vector2::new(20, 10)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 8)).await;
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"\x1b[6 q");
+    }
+
+    draw(state.clone()).await.unwrap();
+
+    let output = String::from_utf8(output.bytes()).unwrap();
+    assert!(output.contains("\x1b[6 q"));
+}
+
+#[tokio::test]
+async fn empty_layout_shows_splash() {
+    let state = test_state(test_config());
+    let mut canvas = Canvas::new_filled(Vector2::new(60, 10), Cell::new('#'));
+    compose(state.clone(), &mut canvas, Vector2::new(60, 10))
+        .await
+        .unwrap();
+
+    assert!(row_text(&canvas, 5).contains("No panes"));
+    assert!(!row_text(&canvas, 0).contains('#'));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn status_command_output_is_right_aligned() {
+    let mut config = test_config();
+    config.status_bar = true;
+    config.status_right_command = Some("echo hello; echo world".to_string());
+    let state = test_state(config);
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    add_test_process(&state, 1, Vector2::new(18, 2)).await;
+    refresh_status_right(&state).await;
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    assert!(row_text(&canvas, 4).ends_with("hello "));
+    assert!(row_text(&canvas, 3).starts_with("└"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn failing_status_command_shows_nothing() {
+    let mut config = test_config();
+    config.status_bar = true;
+    config.status_right_command = Some("echo partial; exit 1".to_string());
+    let state = test_state(config);
+    refresh_status_right(&state).await;
+
+    assert_eq!(*state.status_right.read().await, None);
+}
+
+#[tokio::test]
+async fn debug_metrics_record_each_draw() {
+    let args = CliArgs {
+        debug_metrics: true,
+        ..test_args()
+    };
+    let state = StateContainer::new(State::new(
+        args,
+        test_config(),
+        tokio::io::empty(),
+        tokio::io::sink(),
+    ));
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    add_test_process(&state, 1, Vector2::new(18, 3)).await;
+    state.set_size(Vector2::new(20, 5)).await;
+
+    draw(state.clone()).await.unwrap();
+
+    let metrics = state.render_metrics.lock().await;
+    assert_eq!(metrics.frames(), 1);
+    assert!(metrics.average_bytes() > 0.0);
+    assert_eq!(metrics.average_changed_cells(), 100.0);
+}
+
+#[test]
+fn reverse_video_run_is_reset_after() {
+    let mut canvas = Canvas::new(Vector2::new(4, 1));
+    let reverse = Style::default().with_inverse(true);
+    canvas.set_cell(Vector2::new(0, 0), Cell::new_styled("a", reverse.clone()));
+    canvas.set_cell(Vector2::new(1, 0), Cell::new_styled("b", reverse.clone()));
+    canvas.set_cell(Vector2::new(2, 0), Cell::new("c"));
+    let mut output = Vec::new();
+    encode_canvas(None, &canvas, &mut output);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\x1b[1;1H\x1b[0m\x1b[49m\x1b[39m\x1b[7mab\x1b[0m\x1b[49m\x1b[39mc \x1b[0m"
+    );
+
+    // A cursor on the run shows the cell the right way round.
+    canvas.set_cell(
+        Vector2::new(1, 0),
+        Cell::new_styled("b", inverted_style(&reverse)),
+    );
+    let mut output = Vec::new();
+    encode_canvas(None, &canvas, &mut output);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\x1b[1;1H\x1b[0m\x1b[49m\x1b[39m\x1b[7ma\x1b[0m\x1b[49m\x1b[39mbc \x1b[0m"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn concurrent_parse_and_draw_shows_whole_updates() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 3)).await;
+    let terminal_info = { process.read().await.terminal_info.clone() };
+    let parser = tokio::spawn(async move {
+        for i in 0..200 {
+            let digit = char::from(b'0' + (i % 10) as u8);
+            let screen = format!("\x1b[H{}", digit.to_string().repeat(18 * 3));
+            terminal_info.lock().await.process(screen.as_bytes());
+            tokio::task::yield_now().await;
+        }
+    });
+
+    while !parser.is_finished() {
+        let mut canvas = Canvas::default();
+        compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+            .await
+            .unwrap();
+        let content: String = (1..4)
+            .map(|y| row_text(&canvas, y).trim_matches('│').to_string())
+            .collect();
+        let first = content.chars().next().unwrap();
+        assert!(content.chars().all(|char| char == first), "{}", content);
+    }
+    parser.await.unwrap();
+}
+
+#[tokio::test]
+async fn new_pane_shows_placeholder_until_output() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    state.set_size(Vector2::new(20, 7)).await;
+    run_process_tasks(&state).await;
+    let id = create_span(state.clone()).await.unwrap();
+    let (data, mut output) = virtual_process(Vector2::new(18, 5));
+    let process = add_process(
+        state.clone(),
+        id,
+        data,
+        TerminalInfo::new(Vector2::new(18, 5)),
+        PaneCommand::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 7))
+        .await
+        .unwrap();
+    assert_eq!(row_text(&canvas, 3), "│    starting…     │");
+
+    output.writer.write_all(b"$ ").await.unwrap();
+    let has_output = { process.read().await.has_output.clone() };
+    for _ in 0..100 {
+        if has_output.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 7))
+        .await
+        .unwrap();
+    assert!(row_text(&canvas, 1).starts_with("│$ "));
+    assert!(!row_text(&canvas, 3).contains("starting"));
+}
+
+#[tokio::test]
+async fn composed_frame_has_both_panes_and_the_cursor() {
+    let (state, output) = test_state_with_output(test_config());
+    state.set_size(Vector2::new(40, 10)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(2);
+    for (id, text) in [(1, "left"), (2, "\x1b[4 qright")] {
+        let (process, _) = add_test_process(&state, id, Vector2::new(18, 8)).await;
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(text.as_bytes());
+    }
+
+    let frame = state.compose_frame().await.unwrap();
+    assert_eq!(frame.canvas.size(), Vector2::new(40, 10));
+    let row: Vec<char> = row_text(&frame.canvas, 1).chars().collect();
+    assert_eq!(row[..20].iter().collect::<String>(), "│left              │");
+    assert_eq!(row[20..].iter().collect::<String>(), "│right             │");
+    let (position, cursor_state) = frame.cursor.unwrap();
+    assert_eq!(position, Vector2::new(26, 1));
+    assert_eq!(cursor_state, CursorState::from_decscusr(4));
+    assert!(output.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn resize_during_compose_is_left_for_the_next_frame() {
+    let state = test_state(test_config());
+    state.set_size(Vector2::new(40, 10)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    let mut processes = Vec::new();
+    for id in [1, 2] {
+        processes.push(add_test_process(&state, id, Vector2::new(18, 8)).await.0);
+    }
+    let pane_sizes = || async {
+        let mut sizes = Vec::new();
+        for process in &processes {
+            let process = process.read().await;
+            let terminal_size = process.terminal_info.lock().await.size();
+            let pty_size = process.terminal.lock().await.size();
+            sizes.push((terminal_size, pty_size));
+        }
+        sizes
+    };
+    state.draw_signal.take();
+
+    // The host is resized after the frame took its size snapshot.
+    let snapshot = state.size.read().await.to_owned();
+    state.set_size(Vector2::new(60, 20)).await;
+    let frame = state.compose_frame_at(snapshot).await.unwrap();
+    assert_eq!(frame.canvas.size(), Vector2::new(40, 10));
+    let pane_size = Vector2::new(18, 8);
+    assert_eq!(pane_sizes().await, vec![(pane_size.clone(), pane_size); 2]);
+    assert!(state.draw_signal.take());
+
+    let frame = state.compose_frame().await.unwrap();
+    assert_eq!(frame.canvas.size(), Vector2::new(60, 20));
+    let pane_size = Vector2::new(28, 18);
+    assert_eq!(pane_sizes().await, vec![(pane_size.clone(), pane_size); 2]);
+    assert!(!state.draw_signal.take());
+}
+
+#[tokio::test]
+async fn redraw_key_emits_the_whole_frame_again() {
+    let (state, output) = test_state_with_output(test_config());
+    state.set_size(Vector2::new(20, 5)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 3)).await;
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"hello");
+    }
+    let written_since = |start: usize| String::from_utf8(output.bytes()[start..].to_vec()).unwrap();
+
+    draw(state.clone()).await.unwrap();
+    assert!(written_since(0).contains("hello"));
+    let start = output.bytes().len();
+    draw(state.clone()).await.unwrap();
+    assert!(!written_since(start).contains("hello"));
+
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    let l = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), l).await.unwrap();
+    let start = output.bytes().len();
+    draw(state.clone()).await.unwrap();
+    assert!(written_since(start).contains("hello"));
+}
+
+#[tokio::test]
+async fn draw_composes_into_the_kept_canvas() {
+    let mut config = test_config();
+    config.pane_gap = 1;
+    let state = test_state(config);
+    state.set_size(Vector2::new(20, 5)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(1);
+    for id in [1, 2] {
+        let (process, _) = add_test_process(&state, id, Vector2::new(7, 3)).await;
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"hello");
+    }
+    // What the canvas held two frames ago must not show through the gap.
+    *state.get_current_canvas().lock().await =
+        Canvas::new_filled(Vector2::new(20, 5), Cell::new('#'));
+
+    draw(state.clone()).await.unwrap();
+    let drawn = state.get_last_canvas();
+    let drawn = drawn.lock().await;
+    assert_eq!(*drawn, state.compose_frame().await.unwrap().canvas);
+    assert!(row_text(&drawn, 2).contains("hello"));
+    assert!(!drawn.to_ansi_string().contains('#'));
+}
+
+/// How many columns a row takes up in a terminal, and the column each cell
+/// starts at.
+fn row_columns(canvas: &Canvas, y: i32) -> (usize, Vec<usize>) {
+    let mut columns = Vec::new();
+    let mut width = 0;
+    for x in 0..canvas.size().x {
+        columns.push(width);
+        width += canvas.get_cell(Vector2::new(x, y)).width();
+    }
+
+    (width, columns)
+}
+
+#[tokio::test]
+async fn wide_titles_and_status_stay_within_their_slots() {
+    let mut config = test_config();
+    config.status_bar = true;
+    let state = test_state(config);
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(3, NodeData::Span(span)));
+    let titles = ["\x1b]0;中文标题很长很长\x07", "\x1b]0;ascii title\x07"];
+    for (id, title) in [1, 2].into_iter().zip(titles) {
+        let (process, _) = add_test_process(&state, id, Vector2::new(9, 3)).await;
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(title.as_bytes());
+    }
+    *state.status_right.write().await = Some("状态 ok".to_string());
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(22, 6))
+        .await
+        .unwrap();
+
+    let (width, columns) = row_columns(&canvas, 0);
+    assert_eq!(width, 22);
+    assert_eq!(canvas.get_cell(Vector2::new(10, 0)).to_string(), "┐");
+    assert_eq!(columns[10], 10, "the left title runs into the next pane");
+    assert_eq!(canvas.get_cell(Vector2::new(11, 0)).to_string(), "┌");
+    assert_eq!(row_text(&canvas, 0), "┌[中文标…─┐┌[ascii t…┐");
+
+    let (width, _) = row_columns(&canvas, 5);
+    assert_eq!(width, 22);
+    assert!(row_text(&canvas, 5).ends_with("状态 ok "));
+}
@@ -0,0 +1,57 @@
+use crate::encoding::TerminalCommand;
+
+#[test]
+fn parse_mixed_input() {
+    let commands =
+        TerminalCommand::parse(b"hello\x1b[1;31mred\x1b]0;title\x07\r\n\x1b]8;;url\x1b\\end");
+    assert_eq!(
+        commands,
+        vec![
+            TerminalCommand::string("hello"),
+            TerminalCommand::csi("1;31m"),
+            TerminalCommand::string("red"),
+            TerminalCommand::osc("0;title"),
+            TerminalCommand::string("\r\n"),
+            TerminalCommand::osc("8;;url"),
+            TerminalCommand::string("end"),
+        ]
+    );
+}
+
+#[test]
+fn parse_keeps_other_escapes_as_text() {
+    let commands = TerminalCommand::parse(b"\x1b7a\x1b[?25l");
+    assert_eq!(
+        commands,
+        vec![
+            TerminalCommand::string("\x1b7a"),
+            TerminalCommand::csi("?25l")
+        ]
+    );
+}
+
+#[test]
+fn parse_keeps_unterminated_sequence_as_text() {
+    let commands = TerminalCommand::parse(b"ab\x1b]0;ti");
+    assert_eq!(commands, vec![TerminalCommand::string("ab\x1b]0;ti")]);
+}
+
+#[test]
+fn commands_are_built_from_bytes() {
+    assert_eq!(
+        TerminalCommand::from(b"\x1b[1;31m"),
+        TerminalCommand::csi("1;31m")
+    );
+    assert_eq!(
+        TerminalCommand::from(&b"\x1b]0;title\x1b\\"[..]),
+        TerminalCommand::osc("0;title")
+    );
+    assert_eq!(
+        TerminalCommand::from(b"text"),
+        TerminalCommand::string("text")
+    );
+    assert_eq!(
+        TerminalCommand::from(b"a\x1b[1m"),
+        TerminalCommand::string("a\x1b[1m")
+    );
+}
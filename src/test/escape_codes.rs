@@ -0,0 +1,23 @@
+use crate::escape_codes::{ClearScreen, SetScrollRegion, SetWin32InputMode};
+
+#[test]
+fn scroll_region_bytes() {
+    let bytes: Vec<u8> = SetScrollRegion::new(0, 23).into();
+    assert_eq!(bytes, b"\x1b[1;24r");
+    let bytes: Vec<u8> = SetScrollRegion::reset().into();
+    assert_eq!(bytes, b"\x1b[r");
+}
+
+#[test]
+fn clear_screen_bytes() {
+    let bytes: &[u8] = ClearScreen::new().into();
+    assert_eq!(bytes, b"\x1b[2J\x1b[H");
+}
+
+#[test]
+fn win32_input_mode_bytes() {
+    let bytes: &[u8] = SetWin32InputMode::new(true).into();
+    assert_eq!(bytes, b"\x1b[?9001h");
+    let bytes: &[u8] = SetWin32InputMode::new(false).into();
+    assert_eq!(bytes, b"\x1b[?9001l");
+}
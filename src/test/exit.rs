@@ -0,0 +1,43 @@
+use crate::exit::{write_restore_sequence, ScreenModes};
+
+#[test]
+fn restore_sequence_leaves_alternate_screen() {
+    let mut output = Vec::new();
+    let modes = ScreenModes {
+        alternate_screen: true,
+        win32_input: false,
+        enhanced_keyboard: false,
+    };
+    write_restore_sequence(&mut output, modes).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(
+        output.contains("\x1b[?2004l"),
+        "bracketed paste is turned off"
+    );
+    assert!(
+        output.contains("\x1b[?1004l"),
+        "focus reports are turned off"
+    );
+    assert!(output.contains("\x1b[?1000l"));
+    assert!(output.contains("\x1b[?1003l"));
+    assert!(output.contains("\x1b[?1006l"));
+    assert!(output.contains("\x1b[0m"));
+    assert!(output.contains("\x1b[r"));
+    assert!(output.contains("\x1b[?1049l"));
+    assert!(output.ends_with("\x1b[?25h"));
+}
+
+#[test]
+fn inline_restore_stays_on_the_main_screen() {
+    let mut output = Vec::new();
+    let modes = ScreenModes {
+        alternate_screen: false,
+        win32_input: false,
+        enhanced_keyboard: false,
+    };
+    write_restore_sequence(&mut output, modes).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(!output.contains("\x1b[?1049l"));
+    assert!(output.contains("\x1b[r"));
+    assert!(output.contains("\r\n"));
+}
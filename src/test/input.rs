@@ -0,0 +1,687 @@
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventState, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use renterm::vector::Vector2;
+
+use crate::{
+    clipboard::copy_to_clipboard,
+    config::{parse_config, Launcher},
+    input::{handle_key_event, handle_mouse_event, handle_paste},
+    keybind::{Action, MouseBinding},
+    mode::InputMode,
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+    spawn::kill_process,
+    state::StateContainer,
+};
+
+use super::{add_test_process, run_process_tasks, test_config, test_state, test_state_with_output};
+
+#[tokio::test]
+async fn wheel_scrolls_by_configured_lines() {
+    let mut config = test_config();
+    config.scroll_lines = 5;
+    let state = test_state(config);
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    {
+        let process = process.read().await;
+        let mut terminal_info = process.terminal_info.lock().await;
+        for line in 0..100 {
+            terminal_info.process(format!("line {}\r\n", line).as_bytes());
+        }
+    }
+
+    let event = MouseEvent {
+        kind: MouseEventKind::ScrollUp,
+        column: 10,
+        row: 10,
+        modifiers: KeyModifiers::NONE,
+    };
+    handle_mouse_event(&state, event).await.unwrap();
+
+    let process = process.read().await;
+    let terminal_info = process.terminal_info.lock().await;
+    assert_eq!(terminal_info.scroll_offset(), 5);
+}
+
+#[tokio::test]
+async fn single_line_paste_skips_confirmation() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+
+    handle_paste(state.clone(), "echo hi".to_string())
+        .await
+        .unwrap();
+
+    assert!(state.pending_paste.lock().await.is_none());
+    assert_eq!(input.bytes(), b"echo hi");
+}
+
+#[tokio::test]
+async fn multiline_paste_requires_confirmation() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+
+    handle_paste(state.clone(), "echo one\necho two\n".to_string())
+        .await
+        .unwrap();
+
+    assert!(state.pending_paste.lock().await.is_some());
+    assert_eq!(state.paste_prompt().await.unwrap(), "Paste 2 lines? [y/n]");
+    assert!(input.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn prefix_m_toggles_mouse_capture() {
+    let (state, output) = test_state_with_output(test_config());
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    let m = KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), m).await.unwrap();
+    let mouse_enabled = || {
+        state
+            .mouse_enabled
+            .load(std::sync::atomic::Ordering::Relaxed)
+    };
+    assert!(!mouse_enabled());
+    let written = String::from_utf8(output.bytes()).unwrap();
+    assert!(written.contains("\x1b[?1003l"));
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), m).await.unwrap();
+    assert!(mouse_enabled());
+    let written = String::from_utf8(output.bytes()).unwrap();
+    assert!(written.ends_with("\x1b[?1006h"));
+    assert!(input.bytes().is_empty());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn launcher_key_opens_the_program_in_a_new_pane() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    config.launchers = vec![Launcher {
+        name: "sleeper".to_string(),
+        command: "sh -c 'sleep 10'".to_string(),
+        key: Some(KeyCode::Char('g')),
+        tint: None,
+    }];
+    let state = test_state(config);
+    run_process_tasks(&state).await;
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state
+        .span_id_counter
+        .store(1, std::sync::atomic::Ordering::Relaxed);
+    state.set_active_span(1);
+    add_test_process(&state, 1, Vector2::new(80, 24)).await;
+
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    let g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), g).await.unwrap();
+
+    assert_eq!(state.processes.read().await.len(), 2);
+    let launched = state.find_process(|process| process.span_id != 1).await;
+    let launched = launched.unwrap();
+    let span_id = launched.read().await.span_id;
+    {
+        let process = launched.read().await;
+        assert_eq!(process.command.program, "sh");
+        assert_eq!(process.command.args, vec!["-c", "sleep 10"]);
+        assert_eq!(process.command.title.as_deref(), Some("sleeper"));
+        assert_ne!(process.pid, 0);
+        let alive = nix::sys::signal::kill(nix::unistd::Pid::from_raw(process.pid as i32), None);
+        assert!(alive.is_ok());
+    }
+
+    kill_process(state.clone(), span_id).await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn pending_prefix_clears_after_the_timeout() {
+    let mut config = test_config();
+    config.prefix_timeout_ms = 2000;
+    let state = test_state(config);
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    let pending_prefix = || {
+        state
+            .pending_prefix
+            .load(std::sync::atomic::Ordering::Relaxed)
+    };
+
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    // Let the timeout task start its timer.
+    tokio::task::yield_now().await;
+    assert!(pending_prefix());
+    tokio::time::advance(std::time::Duration::from_millis(1999)).await;
+    tokio::task::yield_now().await;
+    assert!(pending_prefix());
+    tokio::time::advance(std::time::Duration::from_millis(1)).await;
+    tokio::task::yield_now().await;
+    assert!(!pending_prefix());
+
+    // The key after the timeout goes to the pane instead of running an action.
+    let m = KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), m).await.unwrap();
+    assert_eq!(input.bytes(), b"m");
+    assert!(state
+        .mouse_enabled
+        .load(std::sync::atomic::Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn prefix_twice_sends_the_prefix_to_the_pane() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+
+    let status_bar_visible = || {
+        state
+            .status_bar_visible
+            .load(std::sync::atomic::Ordering::Relaxed)
+    };
+    let was_visible = status_bar_visible();
+
+    // ctrl+b is also prefix + b, which would toggle the status bar.
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), prefix).await.unwrap();
+
+    assert_eq!(input.bytes(), b"\x02");
+    assert!(!state
+        .pending_prefix
+        .load(std::sync::atomic::Ordering::Relaxed));
+    assert_eq!(status_bar_visible(), was_visible);
+}
+
+#[tokio::test]
+async fn read_only_pane_drops_input() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (process, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    process.write().await.read_only = true;
+
+    let key = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), key).await.unwrap();
+    handle_paste(state.clone(), "echo hi".to_string())
+        .await
+        .unwrap();
+
+    assert!(input.bytes().is_empty());
+    assert_eq!(
+        state.current_message().await.as_deref(),
+        Some("Pane is read-only")
+    );
+}
+
+#[tokio::test]
+async fn enter_sends_carriage_return_in_application_keypad_mode() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (process, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"\x1b=");
+    }
+
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    handle_key_event(state.clone(), enter).await.unwrap();
+    let keypad_enter = KeyEvent::new_with_kind_and_state(
+        KeyCode::Enter,
+        KeyModifiers::NONE,
+        crossterm::event::KeyEventKind::Press,
+        KeyEventState::KEYPAD,
+    );
+    handle_key_event(state.clone(), keypad_enter).await.unwrap();
+
+    assert_eq!(input.bytes(), b"\r\x1bOM");
+}
+
+#[tokio::test]
+async fn keypad_enter_can_be_enabled_for_every_enter() {
+    let mut config = test_config();
+    config.keypad_enter = true;
+    let state = test_state(config);
+    state.set_active_span(1);
+    let (process, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"\x1b=");
+    }
+
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    handle_key_event(state.clone(), enter).await.unwrap();
+
+    assert_eq!(input.bytes(), b"\x1bOM");
+}
+
+#[tokio::test]
+async fn keypad_digits_send_application_keypad_sequences() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (process, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    let keypad = |char| {
+        KeyEvent::new_with_kind_and_state(
+            KeyCode::Char(char),
+            KeyModifiers::NONE,
+            crossterm::event::KeyEventKind::Press,
+            KeyEventState::KEYPAD,
+        )
+    };
+
+    handle_key_event(state.clone(), keypad('5')).await.unwrap();
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"\x1b=");
+    }
+    handle_key_event(state.clone(), keypad('5')).await.unwrap();
+    handle_key_event(state.clone(), keypad('+')).await.unwrap();
+    let digit = KeyEvent::new(KeyCode::Char('5'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), digit).await.unwrap();
+
+    assert_eq!(input.bytes(), b"5\x1bOu\x1bOk5");
+}
+
+#[tokio::test]
+async fn bound_middle_click_runs_action_without_forwarding() {
+    let mut config = test_config();
+    config.mouse_bindings = vec![MouseBinding {
+        button: MouseButton::Middle,
+        area: None,
+        action: Action::ToggleReadOnly,
+    }];
+    let state = test_state(config);
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, input) = add_test_process(&state, 1, Vector2::new(78, 22)).await;
+    {
+        let process = process.read().await;
+        let mut terminal_info = process.terminal_info.lock().await;
+        terminal_info.process(b"\x1b[?1000h\x1b[?1006h");
+    }
+
+    for kind in [
+        MouseEventKind::Down(MouseButton::Middle),
+        MouseEventKind::Up(MouseButton::Middle),
+    ] {
+        let event = MouseEvent {
+            kind,
+            column: 10,
+            row: 10,
+            modifiers: KeyModifiers::NONE,
+        };
+        handle_mouse_event(&state, event).await.unwrap();
+    }
+
+    assert!(process.read().await.read_only);
+    assert!(input.bytes().is_empty());
+
+    let event = MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 10,
+        row: 10,
+        modifiers: KeyModifiers::NONE,
+    };
+    state.config.write().await.mouse_bindings.clear();
+    state.set_active_span(1);
+    process.write().await.read_only = false;
+    handle_mouse_event(&state, event).await.unwrap();
+    assert!(!input.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn quit_waits_for_confirmation() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    add_test_process(&state, 2, Vector2::new(80, 24)).await;
+    let key = |char| KeyEvent::new(KeyCode::Char(char), KeyModifiers::NONE);
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    let is_shut_down = || async {
+        tokio::time::timeout(
+            std::time::Duration::from_millis(10),
+            state.shutdown.notified(),
+        )
+        .await
+        .is_ok()
+    };
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), key('Q')).await.unwrap();
+    assert!(state.quit_prompt().await.is_some());
+    handle_key_event(state.clone(), key('n')).await.unwrap();
+    assert!(state.quit_prompt().await.is_none());
+    assert!(!is_shut_down().await);
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), key('Q')).await.unwrap();
+    assert!(!is_shut_down().await);
+    handle_key_event(state.clone(), key('y')).await.unwrap();
+    assert!(is_shut_down().await);
+    assert!(input.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn prefix_b_toggles_status_bar() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    let pane_height = || async {
+        let process = process.read().await;
+        let size = process.terminal_info.lock().await.size();
+        size.y
+    };
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    let b = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::NONE);
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), b).await.unwrap();
+    assert_eq!(state.status_bar_height().await, 1);
+    let with_status_bar = pane_height().await;
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), b).await.unwrap();
+    assert_eq!(state.status_bar_height().await, 0);
+    assert_eq!(pane_height().await, with_status_bar + 1);
+}
+
+#[tokio::test]
+async fn bound_key_sends_its_string_to_the_active_pane() {
+    let mut config = test_config();
+    config.send_strings =
+        parse_config(r#"keybindings { t "send-string" "clear\n"; k "send-string" "\u{1b}[A"; }"#)
+            .unwrap()
+            .send_strings;
+    let state = test_state(config);
+    state.set_active_span(1);
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    let t = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), t).await.unwrap();
+    assert_eq!(input.bytes(), b"clear\n");
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    let k = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), k).await.unwrap();
+    assert_eq!(input.bytes(), b"clear\n\x1b[A");
+}
+
+#[tokio::test]
+async fn resize_mode_arrows_resize_until_escape() {
+    let state = test_state(test_config());
+    state.set_size((80, 24)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    for id in [1, 2] {
+        span.children
+            .push(SpanChild::new(Node::new(id, NodeData::Void)));
+    }
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(38, 22)).await;
+    add_test_process(&state, 2, Vector2::new(38, 22)).await;
+    let sizes = || async {
+        let root = state.root_node.read().await.clone().unwrap();
+        let NodeData::Span(span) = root.data else {
+            panic!("Expected a span at the root");
+        };
+        span.children
+            .iter()
+            .map(|child| child.size)
+            .collect::<Vec<_>>()
+    };
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), key(KeyCode::Char('R')))
+        .await
+        .unwrap();
+    assert_eq!(state.input_mode().await, InputMode::Resize);
+    assert!(state.mode_prompt().await.is_some());
+    handle_key_event(state.clone(), key(KeyCode::Right))
+        .await
+        .unwrap();
+    handle_key_event(state.clone(), key(KeyCode::Right))
+        .await
+        .unwrap();
+    let grown = sizes().await;
+    assert!((grown[0] - 1.2).abs() < 1e-9, "{:?}", grown);
+    assert!((grown[1] - 0.8).abs() < 1e-9, "{:?}", grown);
+    handle_key_event(state.clone(), key(KeyCode::Up))
+        .await
+        .unwrap();
+    handle_key_event(state.clone(), key(KeyCode::Char('x')))
+        .await
+        .unwrap();
+    assert_eq!(sizes().await, grown);
+    assert!(input.bytes().is_empty());
+
+    handle_key_event(state.clone(), key(KeyCode::Esc))
+        .await
+        .unwrap();
+    assert_eq!(state.input_mode().await, InputMode::Normal);
+    handle_key_event(state.clone(), key(KeyCode::Right))
+        .await
+        .unwrap();
+    assert_eq!(sizes().await, grown);
+    assert_eq!(input.bytes(), b"\x1b[C");
+}
+
+#[tokio::test]
+async fn click_focusing_a_pane_is_not_forwarded() {
+    let state = test_state(test_config());
+    state.set_size((80, 24)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(1);
+    add_test_process(&state, 1, Vector2::new(38, 22)).await;
+    let (process, input) = add_test_process(&state, 2, Vector2::new(38, 22)).await;
+    {
+        let process = process.read().await;
+        let mut terminal_info = process.terminal_info.lock().await;
+        terminal_info.process(b"\x1b[?1000h\x1b[?1006h");
+    }
+    let click = MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 60,
+        row: 10,
+        modifiers: KeyModifiers::NONE,
+    };
+
+    handle_mouse_event(&state, click).await.unwrap();
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        2
+    );
+    assert!(input.bytes().is_empty());
+
+    handle_mouse_event(&state, click).await.unwrap();
+    assert!(!input.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn drag_and_release_after_a_focus_click_are_not_forwarded() {
+    let state = test_state(test_config());
+    state.set_size((80, 24)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(1);
+    add_test_process(&state, 1, Vector2::new(38, 22)).await;
+    let (process, input) = add_test_process(&state, 2, Vector2::new(38, 22)).await;
+    {
+        let process = process.read().await;
+        let mut terminal_info = process.terminal_info.lock().await;
+        terminal_info.process(b"\x1b[?1002h\x1b[?1006h");
+    }
+    let event = |kind, column| MouseEvent {
+        kind,
+        column,
+        row: 10,
+        modifiers: KeyModifiers::NONE,
+    };
+
+    let left = MouseButton::Left;
+    handle_mouse_event(&state, event(MouseEventKind::Down(left), 60))
+        .await
+        .unwrap();
+    handle_mouse_event(&state, event(MouseEventKind::Drag(left), 61))
+        .await
+        .unwrap();
+    handle_mouse_event(&state, event(MouseEventKind::Up(left), 61))
+        .await
+        .unwrap();
+    assert!(input.bytes().is_empty());
+
+    // The next press is the program's again, drags included.
+    handle_mouse_event(&state, event(MouseEventKind::Down(left), 60))
+        .await
+        .unwrap();
+    handle_mouse_event(&state, event(MouseEventKind::Drag(left), 61))
+        .await
+        .unwrap();
+    let written = String::from_utf8(input.bytes()).unwrap();
+    assert_eq!(written.matches("\x1b[<").count(), 2);
+}
+
+#[tokio::test]
+async fn navigation_enters_the_most_recently_active_neighbour() {
+    let state = test_state(test_config());
+    state.set_size((80, 24)).await;
+    let mut column = Span::new(SpanDirection::Vertical);
+    column
+        .children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    column
+        .children
+        .push(SpanChild::new(Node::new(3, NodeData::Void)));
+    let mut row = Span::new(SpanDirection::Horizontal);
+    row.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    row.children
+        .push(SpanChild::new(Node::new(10, NodeData::Span(column))));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(row)));
+    for span_id in 1..=3 {
+        add_test_process(&state, span_id, Vector2::new(38, 10)).await;
+    }
+    let active_id = || state.active_id.load(std::sync::atomic::Ordering::Relaxed);
+    let left = KeyEvent::new(KeyCode::Left, KeyModifiers::ALT);
+    let right = KeyEvent::new(KeyCode::Right, KeyModifiers::ALT);
+
+    // Neither neighbour was active yet, so the first of them is picked.
+    state.set_active_span(1);
+    handle_key_event(state.clone(), right).await.unwrap();
+    assert_eq!(active_id(), 2);
+
+    state.set_active_span(3);
+    handle_key_event(state.clone(), left).await.unwrap();
+    assert_eq!(active_id(), 1);
+    handle_key_event(state.clone(), right).await.unwrap();
+    assert_eq!(active_id(), 3);
+}
+
+#[tokio::test]
+async fn double_click_selects_word() {
+    let state = test_state(test_config());
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    let (process, input) = add_test_process(&state, 1, Vector2::new(78, 22)).await;
+    let terminal_info = { process.read().await.terminal_info.clone() };
+    terminal_info.lock().await.process(b"foo bar-baz(qux)");
+    let click = MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 7,
+        row: 1,
+        modifiers: KeyModifiers::NONE,
+    };
+
+    handle_mouse_event(&state, click).await.unwrap();
+    assert_eq!(terminal_info.lock().await.selection(), None);
+    handle_mouse_event(&state, click).await.unwrap();
+    let selected = terminal_info.lock().await.selected_text();
+    assert_eq!(selected.as_deref(), Some("bar-baz"));
+    assert!(input.bytes().is_empty());
+
+    handle_mouse_event(&state, click).await.unwrap();
+    assert_eq!(terminal_info.lock().await.selection(), None);
+}
+
+async fn double_click_word(state: &StateContainer) {
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    let (process, _) = add_test_process(state, 1, Vector2::new(78, 22)).await;
+    let terminal_info = { process.read().await.terminal_info.clone() };
+    terminal_info.lock().await.process(b"foo bar-baz(qux)");
+    let click = MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 7,
+        row: 1,
+        modifiers: KeyModifiers::NONE,
+    };
+    handle_mouse_event(state, click).await.unwrap();
+    handle_mouse_event(state, click).await.unwrap();
+}
+
+#[tokio::test]
+async fn selected_word_is_copied_with_osc_52() {
+    let (state, output) = test_state_with_output(test_config());
+
+    double_click_word(&state).await;
+
+    let output = String::from_utf8_lossy(&output.bytes()).to_string();
+    assert!(output.contains("\x1b]52;c;YmFyLWJheg==\x07"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn text_is_piped_to_the_copy_command() {
+    let output = std::env::temp_dir().join(format!("citymux-copy-{}", std::process::id()));
+    let mut config = test_config();
+    config.copy_command = Some(format!("cat > {}", output.display()));
+    let (state, host_output) = test_state_with_output(config);
+
+    let task = copy_to_clipboard(&state, "bar-baz").await.unwrap();
+    task.unwrap().await.unwrap();
+    let copied = std::fs::read_to_string(&output);
+    let _ = std::fs::remove_file(&output);
+
+    assert_eq!(copied.unwrap(), "bar-baz");
+    assert!(!String::from_utf8_lossy(&host_output.bytes()).contains("\x1b]52"));
+}
+
+#[tokio::test]
+async fn alt_shift_q_is_not_the_kill_shortcut() {
+    let state = test_state(test_config());
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(80, 24)).await;
+    let event = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::ALT | KeyModifiers::SHIFT);
+
+    handle_key_event(state.clone(), event).await.unwrap();
+
+    assert_eq!(state.processes.read().await.len(), 1);
+    assert!(!input.bytes().is_empty());
+}
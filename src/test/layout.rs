@@ -0,0 +1,190 @@
+use renterm::{rect::Rect, vector::Vector2};
+
+use crate::{
+    layout::{get_pane_dimensions, layout_diagram, pane_order},
+    span::{equalize_spans, resize_pane, Node, NodeData, Span, SpanChild, SpanDirection},
+};
+
+fn two_pane_tree() -> Node {
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    Node::new(0, NodeData::Span(span))
+}
+
+#[test]
+fn pane_gap_insets_panes() {
+    let root = two_pane_tree();
+    let size = Vector2::new(21, 10);
+    let left = get_pane_dimensions(&root, 1, size.clone(), 1).unwrap();
+    let right = get_pane_dimensions(&root, 2, size.clone(), 1).unwrap();
+    assert_eq!(left, Rect::new(Vector2::new(1, 1), Vector2::new(9, 8)));
+    assert_eq!(right, Rect::new(Vector2::new(11, 1), Vector2::new(9, 8)));
+    assert!(left.bottom_right().x < right.position().x);
+    assert!(right.bottom_right().x < size.x);
+}
+
+#[test]
+fn no_pane_gap_tiles_screen() {
+    let root = two_pane_tree();
+    let size = Vector2::new(21, 10);
+    let left = get_pane_dimensions(&root, 1, size.clone(), 0).unwrap();
+    let right = get_pane_dimensions(&root, 2, size, 0).unwrap();
+    assert_eq!(left, Rect::new(Vector2::new(0, 0), Vector2::new(11, 10)));
+    assert_eq!(right, Rect::new(Vector2::new(11, 0), Vector2::new(10, 10)));
+}
+
+#[test]
+fn panes_are_ordered_top_to_bottom_then_left_to_right() {
+    let mut column = Span::new(SpanDirection::Vertical);
+    column
+        .children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    column
+        .children
+        .push(SpanChild::new(Node::new(3, NodeData::Void)));
+    let mut row = Span::new(SpanDirection::Horizontal);
+    row.children
+        .push(SpanChild::new(Node::new(4, NodeData::Span(column))));
+    row.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    let root = Node::new(5, NodeData::Span(row));
+
+    assert_eq!(root.leaf_ids(), vec![1, 3, 2]);
+    assert_eq!(pane_order(&root, Vector2::new(100, 40), 0), vec![1, 2, 3]);
+}
+
+#[test]
+fn finding_a_node_returns_its_ancestors() {
+    let mut root = Node::new(10, NodeData::Span(Span::new(SpanDirection::Vertical)));
+    if let NodeData::Span(span) = &mut root.data {
+        span.children.push(SpanChild::new(two_pane_tree()));
+        span.children
+            .push(SpanChild::new(Node::new(3, NodeData::Void)));
+    }
+
+    for id in [10, 0, 1, 2, 3] {
+        let (node, path) = root.find(id).unwrap();
+        assert_eq!(node.id, id);
+        assert_eq!(
+            Some(path),
+            root.clone().find_by_id(id).map(|(_, path)| path)
+        );
+    }
+    assert_eq!(root.find(2).unwrap().1, vec![10, 0]);
+    assert!(root.find(4).is_none());
+}
+
+#[test]
+fn resizing_uses_the_closest_span_in_that_direction() {
+    let mut root = Node::new(10, NodeData::Span(Span::new(SpanDirection::Vertical)));
+    if let NodeData::Span(span) = &mut root.data {
+        span.children.push(SpanChild::new(two_pane_tree()));
+        span.children
+            .push(SpanChild::new(Node::new(3, NodeData::Void)));
+    }
+    let sizes = |root: &Node, id: usize| {
+        let mut root = root.clone();
+        let (node, _) = root.find_by_id(id).unwrap();
+        let NodeData::Span(span) = &node.data else {
+            panic!("Expected a span");
+        };
+        span.children
+            .iter()
+            .map(|child| child.size)
+            .collect::<Vec<_>>()
+    };
+
+    assert!(resize_pane(&mut root, 2, SpanDirection::Vertical, 0.25));
+    assert_eq!(sizes(&root, 10), vec![1.5, 0.5]);
+    assert_eq!(sizes(&root, 0), vec![1.0, 1.0]);
+
+    // The last pane takes its space from the one before it, down to one step.
+    assert!(resize_pane(&mut root, 2, SpanDirection::Horizontal, -0.25));
+    assert_eq!(sizes(&root, 0), vec![1.5, 0.5]);
+    assert!(!resize_pane(&mut root, 2, SpanDirection::Horizontal, -0.25));
+    assert!(!resize_pane(&mut root, 3, SpanDirection::Horizontal, 0.25));
+}
+
+#[test]
+fn equalizing_only_resets_spans_in_that_direction() {
+    let mut row = Span::new(SpanDirection::Horizontal);
+    row.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)).with_size(1.5));
+    row.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)).with_size(0.5));
+    let mut column = Span::new(SpanDirection::Vertical);
+    column
+        .children
+        .push(SpanChild::new(Node::new(0, NodeData::Span(row))).with_size(0.4));
+    column
+        .children
+        .push(SpanChild::new(Node::new(3, NodeData::Void)).with_size(1.6));
+    let mut root = Node::new(10, NodeData::Span(column));
+    let sizes = |root: &Node, id: usize| {
+        let mut root = root.clone();
+        let (node, _) = root.find_by_id(id).unwrap();
+        let NodeData::Span(span) = &node.data else {
+            panic!("Expected a span");
+        };
+        span.children
+            .iter()
+            .map(|child| child.size)
+            .collect::<Vec<_>>()
+    };
+
+    assert!(equalize_spans(&mut root, SpanDirection::Horizontal));
+    assert_eq!(sizes(&root, 0), vec![1.0, 1.0]);
+    assert_eq!(sizes(&root, 10), vec![0.4, 1.6]);
+    assert!(!equalize_spans(&mut root, SpanDirection::Horizontal));
+
+    assert!(equalize_spans(&mut root, SpanDirection::Vertical));
+    assert_eq!(sizes(&root, 10), vec![1.0, 1.0]);
+}
+
+#[test]
+fn layout_diagram_draws_panes_with_their_ids_and_sizes() {
+    let mut column = Span::new(SpanDirection::Vertical);
+    column
+        .children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    column
+        .children
+        .push(SpanChild::new(Node::new(3, NodeData::Void)));
+    let mut row = Span::new(SpanDirection::Horizontal);
+    row.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    row.children
+        .push(SpanChild::new(Node::new(4, NodeData::Span(column))));
+    let root = Node::new(0, NodeData::Span(row));
+
+    assert_eq!(
+        layout_diagram(&root, Vector2::new(20, 6)),
+        concat!(
+            "+---------+---------+\n",
+            "|1        |2        |\n",
+            "|10x6     |10x3     |\n",
+            "|         +---------+\n",
+            "|         |3        |\n",
+            "|         |10x3     |\n",
+            "+---------+---------+\n",
+        )
+    );
+}
+
+#[test]
+fn layout_diagram_clamps_panes_reaching_past_the_grid() {
+    let mut span = Span::new(SpanDirection::Horizontal);
+    for (id, size) in [(1, -1.0), (2, 1.0), (3, 1.0)] {
+        span.children
+            .push(SpanChild::new(Node::new(id, NodeData::Void)).with_size(size));
+    }
+    let root = Node::new(0, NodeData::Span(span));
+
+    let diagram = layout_diagram(&root, Vector2::new(10, 3));
+
+    assert_eq!(diagram.lines().count(), 4);
+    assert!(diagram.lines().all(|line| line.chars().count() == 11));
+}
@@ -0,0 +1,123 @@
+use renterm::vector::Vector2;
+
+use crate::{
+    layout::get_pane_dimensions,
+    main_pane::toggle_main_pane,
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+    spawn::create_span,
+    test::{test_config, test_state},
+};
+
+#[tokio::test]
+async fn new_panes_are_stacked_next_to_the_main_pane() {
+    let state = test_state(test_config());
+    let size = Vector2::new(100, 40);
+    state.set_size(size.clone()).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    state
+        .span_id_counter
+        .store(1, std::sync::atomic::Ordering::Relaxed);
+    toggle_main_pane(&state).await;
+
+    let second = create_span(state.clone()).await.unwrap();
+    let third = create_span(state.clone()).await.unwrap();
+    state.set_active_span(third);
+    let fourth = create_span(state.clone()).await.unwrap();
+
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids(), vec![1, second, third, fourth]);
+    let main = get_pane_dimensions(&root, 1, size.clone(), 0).unwrap();
+    assert_eq!(main.position(), Vector2::new(0, 0));
+    assert_eq!(main.size(), Vector2::new(60, 40));
+    for id in [second, third, fourth] {
+        let pane = get_pane_dimensions(&root, id, size.clone(), 0).unwrap();
+        assert_eq!(pane.position().x, 60);
+        assert_eq!(pane.size().x, 40);
+    }
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        fourth
+    );
+}
+
+#[tokio::test]
+async fn stacking_a_pane_keeps_the_sizes_of_the_others() {
+    let state = test_state(test_config());
+    state.set_size(Vector2::new(100, 40)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    state
+        .span_id_counter
+        .store(1, std::sync::atomic::Ordering::Relaxed);
+    toggle_main_pane(&state).await;
+    create_span(state.clone()).await.unwrap();
+    create_span(state.clone()).await.unwrap();
+    {
+        let mut root = state.root_node.write().await;
+        let NodeData::Span(span) = &mut root.as_mut().unwrap().data else {
+            panic!("Expected a span");
+        };
+        span.children[0].size = 0.8;
+        span.children[1].size = 0.2;
+        let NodeData::Span(stack) = &mut span.children[1].node.data else {
+            panic!("Expected a stack");
+        };
+        stack.children[0].size = 3.0;
+    }
+
+    create_span(state.clone()).await.unwrap();
+
+    let root = state.root_node.read().await.clone().unwrap();
+    let NodeData::Span(span) = &root.data else {
+        panic!("Expected a span");
+    };
+    let sizes = |span: &Span| {
+        span.children
+            .iter()
+            .map(|child| child.size)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sizes(span), vec![0.8, 0.2]);
+    let NodeData::Span(stack) = &span.children[1].node.data else {
+        panic!("Expected a stack");
+    };
+    // The new pane gets the average size of the panes already in the stack.
+    assert_eq!(sizes(stack), vec![3.0, 1.0, 2.0]);
+}
+
+#[tokio::test]
+async fn clearing_the_main_pane_restores_the_previous_layout() {
+    let state = test_state(test_config());
+    state.set_size(Vector2::new(100, 40)).await;
+    let mut span = Span::new(SpanDirection::Vertical);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)).with_size(3.0));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(3, NodeData::Span(span)));
+    state.set_active_span(2);
+    state
+        .span_id_counter
+        .store(3, std::sync::atomic::Ordering::Relaxed);
+    toggle_main_pane(&state).await;
+    let new_pane = create_span(state.clone()).await.unwrap();
+
+    state.set_active_span(2);
+    toggle_main_pane(&state).await;
+
+    assert_eq!(*state.main_pane.read().await, None);
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.id, 3);
+    let NodeData::Span(span) = &root.data else {
+        panic!("Expected a span");
+    };
+    assert_eq!(span.direction, SpanDirection::Vertical);
+    let children = span
+        .children
+        .iter()
+        .map(|child| (child.node.id, child.size))
+        .collect::<Vec<_>>();
+    // The pane opened in between is added with the average size.
+    assert_eq!(children, vec![(1, 3.0), (2, 1.0), (new_pane, 2.0)]);
+}
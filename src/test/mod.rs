@@ -0,0 +1,201 @@
+mod config;
+mod control;
+mod draw;
+mod encoding;
+mod escape_codes;
+mod exit;
+mod input;
+mod layout;
+mod main_pane;
+mod mode;
+mod picker;
+mod process;
+mod project;
+#[cfg(unix)]
+mod signal;
+mod size;
+mod spawn;
+mod startup;
+mod state;
+mod term;
+mod virtual_process;
+mod window;
+mod zoom;
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{atomic::AtomicBool, Arc},
+    task::{Context, Poll},
+};
+
+use renterm::{color::ColorDepth, vector::Vector2};
+use tokio::{
+    io::AsyncWrite,
+    sync::{Mutex, RwLock},
+};
+
+use crate::{
+    args::CliArgs,
+    config::{get_default_config, BorderStyle, Config},
+    process::{TerminalError, TerminalLike, Utf8Carryover},
+    state::{PaneCommand, Process, State, StateContainer},
+    term::TerminalInfo,
+};
+
+pub struct TestTerminal {
+    size: Vector2,
+    /// Counts the calls to `set_size`.
+    pub resizes: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl TestTerminal {
+    pub fn new(size: Vector2) -> Self {
+        TestTerminal {
+            size,
+            resizes: Arc::default(),
+        }
+    }
+}
+
+impl TerminalLike for TestTerminal {
+    fn release<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), TerminalError>> + 'a + Send>> {
+        Box::pin(async { Ok(()) })
+    }
+    fn set_size(&mut self, size: Vector2) -> Result<(), TerminalError> {
+        self.size = size;
+        self.resizes
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+    fn size(&self) -> Vector2 {
+        self.size.clone()
+    }
+    fn take_done_future(
+        &mut self,
+    ) -> Option<Pin<Box<dyn Future<Output = Result<(), TerminalError>> + Send>>> {
+        None
+    }
+}
+
+/// Collects everything written to a test process' stdin, or to a state's output.
+#[derive(Clone, Default)]
+pub struct TestInput {
+    bytes: Arc<std::sync::Mutex<Vec<u8>>>,
+}
+
+impl TestInput {
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.lock().unwrap().clone()
+    }
+}
+
+impl AsyncWrite for TestInput {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        self.bytes.lock().unwrap().extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), std::io::Error>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub fn test_state(config: Config) -> StateContainer {
+    let args = test_args();
+    StateContainer::new(State::new(
+        args,
+        config,
+        tokio::io::empty(),
+        tokio::io::sink(),
+    ))
+}
+
+/// Creates a state whose output is collected, returning it alongside.
+pub fn test_state_with_output(config: Config) -> (StateContainer, TestInput) {
+    let output = TestInput::default();
+    let args = test_args();
+    let state = StateContainer::new(State::new(args, config, tokio::io::empty(), output.clone()));
+
+    (state, output)
+}
+
+pub fn test_args() -> CliArgs {
+    CliArgs {
+        log_file: None,
+        enable_logging: false,
+        debug_layout: false,
+        debug_metrics: false,
+        strict_config: false,
+        check_config: false,
+        project: None,
+        zoom: false,
+    }
+}
+
+pub fn test_config() -> Config {
+    let mut config = get_default_config();
+    config.color_depth = ColorDepth::TrueColor;
+    config.border_style = BorderStyle::Single;
+
+    config
+}
+
+/// Adds a process without a real child to the state, returning its stdin.
+pub async fn add_test_process(
+    state: &StateContainer,
+    span_id: usize,
+    size: Vector2,
+) -> (Arc<RwLock<Process>>, TestInput) {
+    add_test_process_with_terminal(state, span_id, TestTerminal::new(size)).await
+}
+
+/// Like `add_test_process`, with a terminal the caller can keep an eye on.
+pub async fn add_test_process_with_terminal(
+    state: &StateContainer,
+    span_id: usize,
+    terminal: TestTerminal,
+) -> (Arc<RwLock<Process>>, TestInput) {
+    let size = terminal.size();
+    let input = TestInput::default();
+    let process = Process {
+        stdout: Arc::new(Mutex::new(tokio::io::empty())),
+        stdin: Arc::new(Mutex::new(input.clone())),
+        terminal_info: Arc::new(Mutex::new(TerminalInfo::new(size.clone()))),
+        terminal: Arc::new(Mutex::new(Box::new(terminal))),
+        span_id,
+        read_only: false,
+        pid: 0,
+        output_carryover: Arc::new(Mutex::new(Utf8Carryover::default())),
+        has_output: Arc::new(AtomicBool::new(true)),
+        command: PaneCommand::default(),
+        is_virtual: false,
+        tint: None,
+    };
+    let process = Arc::new(RwLock::new(process));
+    state.processes.write().await.push(process.clone());
+
+    (process, input)
+}
+
+/// Runs the tasks that handle processes' output, as `startup` does.
+pub async fn run_process_tasks(state: &StateContainer) {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    *state.process_channel.lock().await = Some(tx);
+    tokio::spawn(async move {
+        while let Some(task) = rx.recv().await {
+            tokio::spawn(task);
+        }
+    });
+}
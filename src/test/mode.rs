@@ -0,0 +1,63 @@
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use renterm::vector::Vector2;
+
+use crate::{
+    input::{handle_key_event, handle_mouse_event},
+    mode::{dispatch_key, dispatch_mouse, InputMode},
+    span::{Node, NodeData},
+};
+
+use super::{add_test_process, test_config, test_state};
+
+fn click() -> MouseEvent {
+    MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 5,
+        row: 5,
+        modifiers: KeyModifiers::NONE,
+    }
+}
+
+#[tokio::test]
+async fn normal_mode_lets_events_through() {
+    let state = test_state(test_config());
+    let key = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+
+    assert_eq!(state.input_mode().await, InputMode::Normal);
+    assert!(!dispatch_key(&state, key).await.unwrap());
+    assert!(!dispatch_mouse(&state, click()).await);
+}
+
+#[tokio::test]
+async fn quit_confirmation_mode_takes_input_until_answered() {
+    let state = test_state(test_config());
+    state.set_size((80, 24)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    let (_, input) = add_test_process(&state, 1, Vector2::new(78, 22)).await;
+    add_test_process(&state, 2, Vector2::new(78, 22)).await;
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    let quit = KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::NONE);
+
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), quit).await.unwrap();
+    assert_eq!(state.input_mode().await, InputMode::ConfirmQuit);
+    assert!(state.mode_prompt().await.unwrap().starts_with("Quit"));
+
+    // Releases and clicks are swallowed without answering the prompt.
+    let mut release = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE);
+    release.kind = KeyEventKind::Release;
+    handle_key_event(state.clone(), release).await.unwrap();
+    handle_mouse_event(&state, click()).await.unwrap();
+    assert_eq!(state.input_mode().await, InputMode::ConfirmQuit);
+    assert!(input.bytes().is_empty());
+
+    let no = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE);
+    handle_key_event(state.clone(), no).await.unwrap();
+    assert_eq!(state.input_mode().await, InputMode::Normal);
+    assert!(input.bytes().is_empty());
+    handle_key_event(state.clone(), no).await.unwrap();
+    assert_eq!(input.bytes(), b"n");
+}
@@ -0,0 +1,108 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use renterm::{canvas::Canvas, surface::Surface, vector::Vector2};
+
+use crate::{
+    draw::compose,
+    input::handle_key_event,
+    picker::{filter_entries, fuzzy_match, pane_entries},
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+};
+
+use super::{add_test_process, test_config, test_state};
+
+#[test]
+fn fuzzy_match_finds_characters_in_order() {
+    assert!(fuzzy_match("", "anything"));
+    assert!(fuzzy_match("htp", "1: htop (test)"));
+    assert!(fuzzy_match("HT", "1: htop (test)"));
+    assert!(!fuzzy_match("pth", "1: htop"));
+}
+
+#[tokio::test]
+async fn filtering_narrows_the_list_and_enter_focuses() {
+    let state = test_state(test_config());
+    state.set_size(Vector2::new(60, 10)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    for id in 1..=3 {
+        span.children
+            .push(SpanChild::new(Node::new(id, NodeData::Void)));
+    }
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(1);
+    for (id, title) in [(1, "vim"), (2, "htop"), (3, "bash")] {
+        let (process, _) = add_test_process(&state, id, Vector2::new(18, 8)).await;
+        process.write().await.command.program = "test".to_string();
+        let process = process.read().await;
+        let title = format!("\x1b]0;{}\x07", title);
+        process.terminal_info.lock().await.process(title.as_bytes());
+    }
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    let matches = || async {
+        let picker = state.pane_picker.lock().await.clone().unwrap();
+        filter_entries(&picker, pane_entries(&state).await)
+            .into_iter()
+            .map(|entry| entry.span_id)
+            .collect::<Vec<_>>()
+    };
+
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), key(KeyCode::Char('w')))
+        .await
+        .unwrap();
+    assert_eq!(matches().await, vec![1, 2, 3]);
+    handle_key_event(state.clone(), key(KeyCode::Char('h')))
+        .await
+        .unwrap();
+    assert_eq!(matches().await, vec![2, 3]);
+    handle_key_event(state.clone(), key(KeyCode::Char('o')))
+        .await
+        .unwrap();
+    assert_eq!(matches().await, vec![2]);
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(60, 10))
+        .await
+        .unwrap();
+    let text: String = (0..canvas.size().y)
+        .flat_map(|y| (0..canvas.size().x).map(move |x| Vector2::new(x, y)))
+        .map(|position| canvas.get_cell(position).to_string())
+        .collect();
+    assert!(text.contains("> ho"));
+    assert!(text.contains("2: htop (test)"));
+
+    handle_key_event(state.clone(), key(KeyCode::Enter))
+        .await
+        .unwrap();
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        2
+    );
+    assert!(state.pane_picker.lock().await.is_none());
+}
+
+#[tokio::test]
+async fn escape_closes_the_picker_without_focusing() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    add_test_process(&state, 1, Vector2::new(18, 8)).await;
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    let prefix = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+    handle_key_event(state.clone(), prefix).await.unwrap();
+    handle_key_event(state.clone(), key(KeyCode::Char('w')))
+        .await
+        .unwrap();
+    handle_key_event(state.clone(), key(KeyCode::Char('z')))
+        .await
+        .unwrap();
+
+    handle_key_event(state.clone(), key(KeyCode::Esc))
+        .await
+        .unwrap();
+    assert!(state.pane_picker.lock().await.is_none());
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        1
+    );
+}
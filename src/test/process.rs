@@ -0,0 +1,124 @@
+use renterm::vector::Vector2;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    process::Utf8Carryover,
+    spawn::{add_process, create_span},
+    state::PaneCommand,
+    term::TerminalInfo,
+    test::{run_process_tasks, test_config, test_state, test_state_with_output, TestInput},
+    virtual_process::virtual_process,
+};
+
+#[test]
+fn split_character_is_carried_over() {
+    let bytes = "a€b".as_bytes();
+    let mut carryover = Utf8Carryover::default();
+
+    let first = carryover.feed(&bytes[..3]);
+    assert_eq!(first, b"a");
+    let second = carryover.feed(&bytes[3..]);
+    assert_eq!(String::from_utf8(second).unwrap(), "€b");
+}
+
+#[test]
+fn complete_and_invalid_bytes_pass_through() {
+    let mut carryover = Utf8Carryover::default();
+    assert_eq!(carryover.feed("a€".as_bytes()), "a€".as_bytes());
+    assert_eq!(carryover.feed(b"\xffx"), b"\xffx");
+    assert_eq!(carryover.feed(b"\x80"), b"\x80");
+}
+
+#[test]
+fn character_split_over_several_reads_is_rebuilt() {
+    let bytes = "😀".as_bytes();
+    let mut carryover = Utf8Carryover::default();
+    let mut output = Vec::new();
+    for byte in bytes {
+        output.extend(carryover.feed(&[*byte]));
+    }
+    assert_eq!(output, bytes);
+}
+
+#[tokio::test]
+async fn cursor_position_query_is_answered() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    state.set_size(Vector2::new(20, 10)).await;
+    run_process_tasks(&state).await;
+    let id = create_span(state.clone()).await.unwrap();
+    let (mut data, mut output) = virtual_process(Vector2::new(18, 8));
+    let input = TestInput::default();
+    data.stdin = Box::new(input.clone());
+    let terminal_info = TerminalInfo::new(Vector2::new(18, 8));
+    add_process(
+        state.clone(),
+        id,
+        data,
+        terminal_info,
+        PaneCommand::default(),
+    )
+    .await
+    .unwrap();
+
+    output
+        .writer
+        .write_all(b"\r\nabc\x1b[6n\x1b[c")
+        .await
+        .unwrap();
+    for _ in 0..100 {
+        if !input.bytes().is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+
+    assert_eq!(input.bytes(), b"\x1b[2;4R\x1b[?1;2c");
+}
+
+#[tokio::test]
+async fn background_bells_are_forwarded_once_per_interval() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    config.forward_bell = true;
+    let (state, host_output) = test_state_with_output(config);
+    state.set_size(Vector2::new(20, 10)).await;
+    run_process_tasks(&state).await;
+    let id = create_span(state.clone()).await.unwrap();
+    let (data, mut output) = virtual_process(Vector2::new(18, 8));
+    let process = add_process(
+        state.clone(),
+        id,
+        data,
+        TerminalInfo::new(Vector2::new(18, 8)),
+        PaneCommand::default(),
+    )
+    .await
+    .unwrap();
+    state.set_active_span(id + 1);
+
+    let (has_output, terminal_info) = {
+        let process = process.read().await;
+        (process.has_output.clone(), process.terminal_info.clone())
+    };
+    // Waits for each chunk to be parsed, which moves the cursor to `column`.
+    for (chunk, column) in [(&b"\x07"[..], 0), (b"a\x07\x07", 1), (b"b\x07", 2)] {
+        output.writer.write_all(chunk).await.unwrap();
+        for _ in 0..100 {
+            if has_output.load(std::sync::atomic::Ordering::Relaxed)
+                && terminal_info.lock().await.cursor_position().x == column
+            {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+    }
+
+    let bells = host_output
+        .bytes()
+        .iter()
+        .filter(|byte| **byte == 0x07)
+        .count();
+    assert_eq!(bells, 1);
+}
@@ -0,0 +1,179 @@
+use renterm::vector::Vector2;
+
+use crate::{
+    args::CliArgs,
+    project::{
+        find_project_file, load_project, open_initial_panes, open_project, parse_project,
+        point_out_project_file, PROJECT_FILE_NAME,
+    },
+    span::NodeData,
+    spawn::kill_process,
+    state::{State, StateContainer},
+};
+
+use super::{run_process_tasks, test_args, test_config, test_state};
+
+const PROJECT: &str = r#"
+layout direction="vertical" {
+    pane "nvim ." title="editor"
+    span size=0.5 {
+        pane "cargo watch -x test"
+        pane
+    }
+}
+"#;
+
+#[test]
+fn project_file_is_found_above_the_current_directory() {
+    let root = std::env::temp_dir().join(format!("citymux-project-{}", std::process::id()));
+    let nested = root.join("src").join("bin");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(root.join(PROJECT_FILE_NAME), PROJECT).unwrap();
+
+    let found = find_project_file(&nested, None);
+    let loaded = found.as_deref().map(load_project);
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(found, Some(root.join(PROJECT_FILE_NAME)));
+    assert!(loaded.unwrap().is_ok());
+}
+
+#[test]
+fn project_file_search_stops_at_home_and_the_repository_root() {
+    let root = std::env::temp_dir().join(format!("citymux-project-root-{}", std::process::id()));
+    let home = root.join("home");
+    let repo = home.join("repo");
+    let nested = repo.join("src");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(root.join(PROJECT_FILE_NAME), PROJECT).unwrap();
+
+    let above_home = find_project_file(&nested, Some(&home));
+    std::fs::create_dir(repo.join(".git")).unwrap();
+    let above_repo = find_project_file(&nested, None);
+    std::fs::write(repo.join(PROJECT_FILE_NAME), PROJECT).unwrap();
+    let in_repo = find_project_file(&nested, None);
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(above_home, None);
+    assert_eq!(above_repo, None);
+    assert_eq!(in_repo, Some(repo.join(PROJECT_FILE_NAME)));
+}
+
+#[tokio::test]
+async fn found_project_file_is_pointed_out_but_not_opened() {
+    let root = std::env::temp_dir().join(format!("citymux-project-found-{}", std::process::id()));
+    std::fs::create_dir_all(root.join(".git")).unwrap();
+    std::fs::write(root.join(PROJECT_FILE_NAME), PROJECT).unwrap();
+    let state = test_state(test_config());
+
+    point_out_project_file(&state, &root).await;
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert!(state.root_node.read().await.is_none());
+    let message = state.current_message().await.unwrap();
+    assert!(message.contains(PROJECT_FILE_NAME));
+    assert!(message.contains("--project"));
+}
+
+#[tokio::test]
+async fn project_opens_the_declared_panes_and_commands() {
+    let mut config = test_config();
+    config.default_shell = "bash".to_string();
+    config.default_shell_args = vec!["-l".to_string()];
+    let state = test_state(config);
+    let project = parse_project(PROJECT).unwrap();
+
+    let panes = open_project(&state, &project).await;
+
+    let commands: Vec<String> = panes
+        .iter()
+        .map(|(_, command)| command.command_line())
+        .collect();
+    assert_eq!(commands, ["nvim .", "cargo watch -x test", "bash -l"]);
+    assert_eq!(panes[0].1.title.as_deref(), Some("editor"));
+    let root = state.root_node.read().await.clone().unwrap();
+    let leaf_ids: Vec<usize> = panes.iter().map(|(id, _)| *id).collect();
+    assert_eq!(root.leaf_ids(), leaf_ids);
+    let NodeData::Span(span) = &root.data else {
+        panic!("Expected a span at the root");
+    };
+    assert_eq!(span.children[1].size, 0.5);
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        leaf_ids[0]
+    );
+}
+
+#[test]
+fn project_without_panes_is_an_error() {
+    let error = parse_project("layout {\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("has no panes"));
+    let error = parse_project("layout {\n    tab\n}\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("expects \"pane\" or \"span\""));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn startup_spawns_the_panes_that_can_be_started() {
+    let path = std::env::temp_dir().join(format!("citymux-startup-{}.kdl", std::process::id()));
+    std::fs::write(
+        &path,
+        "layout {\n    pane \"sh -c 'sleep 10'\"\n    pane \"citymux-no-such-program\"\n    pane \"sleep 10\"\n}\n",
+    )
+    .unwrap();
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let args = CliArgs {
+        project: Some(path.to_string_lossy().into_owned()),
+        ..test_args()
+    };
+    let state = StateContainer::new(State::new(
+        args,
+        config,
+        tokio::io::empty(),
+        tokio::io::sink(),
+    ));
+    state.set_size(Vector2::new(80, 24)).await;
+    run_process_tasks(&state).await;
+
+    let result = open_initial_panes(&state).await;
+    std::fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    let leaf_ids = state.root_node.read().await.as_ref().unwrap().leaf_ids();
+    assert_eq!(leaf_ids.len(), 2);
+    let mut commands = Vec::new();
+    for span_id in &leaf_ids {
+        let process = state
+            .find_process(|process| process.span_id == *span_id)
+            .await;
+        let process = process.unwrap();
+        let process = process.read().await;
+        let alive = nix::sys::signal::kill(nix::unistd::Pid::from_raw(process.pid as i32), None);
+        assert!(alive.is_ok());
+        commands.push((
+            process.command.program.clone(),
+            process.command.args.clone(),
+        ));
+    }
+    assert_eq!(
+        commands,
+        [
+            (
+                "sh".to_string(),
+                vec!["-c".to_string(), "sleep 10".to_string()]
+            ),
+            ("sleep".to_string(), vec!["10".to_string()]),
+        ]
+    );
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        leaf_ids[0]
+    );
+    let message = state.current_message().await.unwrap();
+    assert!(message.contains("citymux-no-such-program"));
+
+    for span_id in leaf_ids {
+        kill_process(state.clone(), span_id).await.unwrap();
+    }
+}
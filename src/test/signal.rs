@@ -0,0 +1,42 @@
+use std::os::unix::process::ExitStatusExt;
+
+use renterm::vector::Vector2;
+
+use crate::{
+    signal::{parse_signal, send_signal},
+    test::{add_test_process, test_config, test_state},
+};
+
+#[test]
+fn signal_names_are_parsed() {
+    use nix::sys::signal::Signal;
+
+    assert_eq!(parse_signal("INT"), Some(Signal::SIGINT));
+    assert_eq!(parse_signal("sigterm"), Some(Signal::SIGTERM));
+    assert_eq!(parse_signal("USR1"), None);
+}
+
+#[tokio::test]
+async fn sigterm_ends_pane_process() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    let state = test_state(test_config());
+    let (process, _) = add_test_process(&state, 1, Vector2::new(10, 5)).await;
+    process.write().await.pid = child.id();
+
+    send_signal(&state, 1, "TERM").await.unwrap();
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.signal(), Some(nix::libc::SIGTERM));
+}
+
+#[tokio::test]
+async fn pane_without_pid_is_not_signalled() {
+    let state = test_state(test_config());
+    add_test_process(&state, 1, Vector2::new(10, 5)).await;
+
+    assert!(send_signal(&state, 1, "TERM").await.is_err());
+    assert!(send_signal(&state, 2, "TERM").await.is_err());
+}
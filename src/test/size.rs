@@ -0,0 +1,84 @@
+use renterm::{canvas::Canvas, surface::Surface, vector::Vector2};
+
+use crate::{
+    draw::compose,
+    size::{clamp_console_size, resize_panes, validate_console_size},
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+    test::{add_test_process_with_terminal, test_config, test_state, TestTerminal},
+};
+
+#[test]
+fn oversized_console_size_is_clamped() {
+    let size = Vector2::<i32>::new(i16::MAX as i32 + 10, 40);
+    assert_eq!(
+        clamp_console_size(size),
+        Vector2::<i16>::new(i16::MAX, 40i16)
+    );
+}
+
+#[test]
+fn vector_try_from_rejects_overflow() {
+    let size = Vector2::<i32>::new(i16::MAX as i32 + 1, 40);
+    assert!(Vector2::<i16>::try_from(size).is_err());
+    let size = Vector2::<i32>::new(80, 24);
+    assert_eq!(
+        Vector2::<i16>::try_from(size),
+        Ok(Vector2::<i16>::new(80i16, 24i16))
+    );
+}
+
+#[test]
+fn empty_console_size_is_clamped() {
+    let size = validate_console_size(Vector2::new(0, 0)).unwrap();
+    assert_eq!(size, Vector2::<i16>::new(1i16, 1i16));
+}
+
+#[test]
+fn negative_console_size_is_rejected() {
+    assert!(validate_console_size(Vector2::new(-1, 24)).is_err());
+}
+
+#[tokio::test]
+async fn host_resize_resizes_each_pane_once() {
+    let state = test_state(test_config());
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(3, NodeData::Span(span)));
+    let mut resizes = Vec::new();
+    for id in [1, 2] {
+        let terminal = TestTerminal::new(Vector2::new(38, 22));
+        resizes.push(terminal.resizes.clone());
+        add_test_process_with_terminal(&state, id, terminal).await;
+    }
+
+    let size = Vector2::new(120, 40);
+    state.set_size(size.clone()).await;
+    resize_panes(&state).await.unwrap();
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, size).await.unwrap();
+
+    for resizes in resizes {
+        assert_eq!(resizes.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+    let pane = state.get_span_dimensions(1).await.unwrap();
+    assert_eq!(pane.size(), Vector2::new(60, 40));
+}
+
+#[tokio::test]
+async fn frames_stay_within_the_canvas_cell_limit() {
+    let mut config = test_config();
+    config.max_canvas_cells = 40 * 12;
+    let state = test_state(config);
+    state.set_size(Vector2::new(40, 20)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let terminal = TestTerminal::new(Vector2::new(38, 18));
+    let (process, _) = add_test_process_with_terminal(&state, 1, terminal).await;
+
+    let frame = state.compose_frame().await.unwrap();
+    assert_eq!(frame.canvas.size(), Vector2::new(40, 12));
+    let process = process.read().await;
+    assert_eq!(process.terminal.lock().await.size(), Vector2::new(38, 10));
+}
@@ -0,0 +1,379 @@
+use renterm::{surface::Surface, vector::Vector2};
+
+use crate::{
+    config::SplitRatio,
+    control::handle_control_line,
+    draw::draw,
+    layout::get_pane_dimensions,
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+    spawn::{
+        add_process, create_process, create_span, kill_process, kill_span, pane_content_size,
+        place_closed_pane, remove_node_from_state, spawn_in_pane,
+    },
+    state::PaneCommand,
+    term::TerminalInfo,
+    test::{add_test_process, run_process_tasks, test_config, test_state},
+    virtual_process::virtual_process,
+};
+
+#[tokio::test]
+async fn split_ratio_sizes_new_pane() {
+    let mut config = test_config();
+    config.default_split_ratio = SplitRatio::new(0.3);
+    let state = test_state(config);
+    let size = Vector2::new(100, 40);
+    state.set_size(size.clone()).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    state
+        .span_id_counter
+        .store(1, std::sync::atomic::Ordering::Relaxed);
+
+    let new_id = create_span(state.clone()).await.unwrap();
+
+    let root = state.root_node.read().await.clone().unwrap();
+    let new_pane = get_pane_dimensions(&root, new_id, size.clone(), 0).unwrap();
+    let old_pane = get_pane_dimensions(&root, 1, size, 0).unwrap();
+    assert!((29..=31).contains(&new_pane.size().x));
+    assert_eq!(new_pane.size().x + old_pane.size().x, 100);
+}
+
+#[tokio::test]
+async fn split_before_makes_the_new_pane_the_earlier_sibling() {
+    let mut config = test_config();
+    config.split_before = true;
+    let state = test_state(config);
+    let size = Vector2::new(100, 40);
+    state.set_size(size.clone()).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    state
+        .span_id_counter
+        .store(1, std::sync::atomic::Ordering::Relaxed);
+
+    let second = create_span(state.clone()).await.unwrap();
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids(), vec![second, 1]);
+    let new_pane = get_pane_dimensions(&root, second, size.clone(), 0).unwrap();
+    let old_pane = get_pane_dimensions(&root, 1, size.clone(), 0).unwrap();
+    assert_eq!(new_pane.position(), Vector2::new(0, 0));
+    assert_eq!(old_pane.position(), Vector2::new(50, 0));
+
+    state.set_active_span(1);
+    let third = create_span(state.clone()).await.unwrap();
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids(), vec![second, third, 1]);
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        third
+    );
+    let new_pane = get_pane_dimensions(&root, third, size.clone(), 0).unwrap();
+    let old_pane = get_pane_dimensions(&root, 1, size, 0).unwrap();
+    assert_eq!(new_pane.position(), Vector2::new(50, 0));
+    assert_eq!(old_pane.position(), Vector2::new(50, 20));
+
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(3, NodeData::Span(span)));
+    state.set_size(Vector2::new(100, 10)).await;
+    state.set_active_span(2);
+    let fourth = create_span(state.clone()).await.unwrap();
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids(), vec![1, fourth, 2]);
+}
+
+#[tokio::test]
+async fn first_pane_starts_at_terminal_size() {
+    let state = test_state(test_config());
+    state.set_size(Vector2::new(80, 24)).await;
+
+    let id = create_span(state.clone()).await.unwrap();
+
+    assert_eq!(pane_content_size(&state, id).await, Vector2::new(78, 22));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn first_shell_reads_the_terminal_size_on_launch() {
+    let path = std::env::temp_dir().join(format!("citymux-size-{}", std::process::id()));
+    let _ignored = std::fs::remove_file(&path);
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    config.default_shell = "sh".to_string();
+    config.default_shell_args = vec![
+        "-c".to_string(),
+        format!("stty size > '{}'; sleep 10", path.display()),
+    ];
+    let state = test_state(config);
+    run_process_tasks(&state).await;
+    state.set_size(Vector2::new(80, 24)).await;
+
+    let process = create_process(state.clone()).await.unwrap();
+    let (span_id, size) = {
+        let process = process.read().await;
+        let size = process.terminal.lock().await.size();
+        (process.span_id, size)
+    };
+    assert_eq!(size, Vector2::new(78, 22));
+    let mut reported = String::new();
+    for _ in 0..100 {
+        reported = std::fs::read_to_string(&path).unwrap_or_default();
+        if reported.ends_with('\n') {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(reported, "22 78\n");
+
+    kill_process(state.clone(), span_id).await.unwrap();
+    let _ignored = std::fs::remove_file(&path);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn spawned_process_reports_its_pid() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    run_process_tasks(&state).await;
+    state.set_size(Vector2::new(40, 10)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let command = PaneCommand {
+        program: "sleep".to_string(),
+        args: vec!["10".to_string()],
+        title: None,
+        tint: None,
+    };
+
+    let process = spawn_in_pane(state.clone(), 1, command).await.unwrap();
+    let pid = process.read().await.pid;
+    assert_ne!(pid, 0);
+    assert_eq!(state.pid_of(1).await, Some(pid));
+    assert_eq!(state.pid_of(2).await, None);
+    let alive = nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), None);
+    assert!(alive.is_ok());
+    let reply = handle_control_line(&state, "list-panes").await;
+    assert_eq!(reply, format!("1 {} 40x10 sleep 10\nok", pid));
+
+    kill_process(state.clone(), 1).await.unwrap();
+}
+
+#[tokio::test]
+async fn closing_last_pane_can_keep_running() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    add_test_process(&state, 1, Vector2::new(10, 5)).await;
+
+    // With the option on, this would exit the test binary.
+    kill_span(state.clone(), 1).await.unwrap();
+
+    assert!(state.root_node.read().await.is_none());
+    assert!(state.processes.read().await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn draws_never_see_a_half_killed_pane() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    state.set_size(Vector2::new(40, 10)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    for id in [1, 2] {
+        span.children
+            .push(SpanChild::new(Node::new(id, NodeData::Void)));
+    }
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(2);
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 8)).await;
+    add_test_process(&state, 2, Vector2::new(18, 8)).await;
+    let has_node = || async {
+        state
+            .root_node
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|root| root.find(1).is_some())
+    };
+    let has_process = || async {
+        state
+            .find_process(|process| process.span_id == 1)
+            .await
+            .is_some()
+    };
+
+    // Stall the kill while it releases the pane's terminal.
+    let terminal = process.read().await.terminal.clone();
+    let terminal_guard = terminal.lock().await;
+    let kill = tokio::spawn(kill_span(state.clone(), 1));
+    while has_process().await {
+        tokio::task::yield_now().await;
+    }
+
+    let drawn = tokio::time::timeout(std::time::Duration::from_secs(5), draw(state.clone())).await;
+    drawn.expect("the draw waited for the release").unwrap();
+    assert!(!kill.is_finished());
+    assert!(!has_node().await);
+    let canvas = state.get_last_canvas();
+    let canvas = canvas.lock().await;
+    let row: String = (0..40)
+        .map(|x| canvas.get_cell(Vector2::new(x, 1)).to_string())
+        .collect();
+    assert_eq!(
+        row.matches('│').count(),
+        2,
+        "only pane 2 is drawn: {:?}",
+        row
+    );
+    assert!(row.starts_with('│') && row.ends_with('│'));
+    drop(canvas);
+
+    drop(terminal_guard);
+    kill.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn closed_pane_is_placed_back_in_its_slot() {
+    let state = test_state(test_config());
+    let size = Vector2::new(100, 40);
+    state.set_size(size.clone()).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+    state
+        .span_id_counter
+        .store(1, std::sync::atomic::Ordering::Relaxed);
+    add_test_process(&state, 1, Vector2::new(10, 5)).await;
+    let second = create_span(state.clone()).await.unwrap();
+    add_test_process(&state, second, Vector2::new(10, 5)).await;
+    let third = create_span(state.clone()).await.unwrap();
+    add_test_process(&state, third, Vector2::new(10, 5)).await;
+    let before = {
+        let root = state.root_node.read().await.clone().unwrap();
+        get_pane_dimensions(&root, second, size.clone(), 0).unwrap()
+    };
+
+    kill_span(state.clone(), second).await.unwrap();
+    let info = state.closed_panes.lock().await.pop().unwrap();
+    let reopened = place_closed_pane(&state, &info).await.unwrap();
+
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_ne!(reopened, second);
+    assert_eq!(root.leaf_ids().len(), 3);
+    assert_eq!(
+        get_pane_dimensions(&root, reopened, size, 0).unwrap(),
+        before
+    );
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        reopened
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn creating_a_pane_runs_the_created_hook() {
+    let output = std::env::temp_dir().join(format!("citymux-hook-{}", std::process::id()));
+    let _ = std::fs::remove_file(&output);
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    config.hooks.pane_created = Some(format!(
+        "echo \"$1 $CITYMUX_PANE_ID $2\" > {}",
+        output.display()
+    ));
+    let state = test_state(config);
+    state.set_size(Vector2::new(40, 10)).await;
+    run_process_tasks(&state).await;
+
+    let span_id = create_span(state.clone()).await.unwrap();
+    let (data, _output) = virtual_process(Vector2::new(38, 8));
+    let command = PaneCommand {
+        title: Some("logs".to_string()),
+        ..PaneCommand::default()
+    };
+    let mut terminal_info = TerminalInfo::new(Vector2::new(38, 8));
+    terminal_info.set_fallback_title(command.title.clone());
+    add_process(state.clone(), span_id, data, terminal_info, command)
+        .await
+        .unwrap();
+    let mut written = String::new();
+    for _ in 0..100 {
+        written = std::fs::read_to_string(&output).unwrap_or_default();
+        if written.ends_with('\n') {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let _ = std::fs::remove_file(&output);
+
+    assert_eq!(written, format!("{} {} logs\n", span_id, span_id));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn closing_the_active_pane_runs_the_focused_hook() {
+    let output = std::env::temp_dir().join(format!("citymux-focus-hook-{}", std::process::id()));
+    let _ = std::fs::remove_file(&output);
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    config.hooks.pane_focused = Some(format!("echo \"$1 [$2]\" > {}", output.display()));
+    let state = test_state(config);
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state
+        .active_id
+        .store(2, std::sync::atomic::Ordering::Relaxed);
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 8)).await;
+    process.write().await.command.program = "test".to_string();
+    let process = process.read().await;
+    let mut terminal_info = process.terminal_info.lock().await;
+    terminal_info.process(b"\x1b]0;logs\x07");
+
+    // The pane that gets focused is busy with output, so the hook is passed
+    // its command instead of waiting for its title.
+    remove_node_from_state(state.clone(), 2).await.unwrap();
+    assert_eq!(
+        state.active_id.load(std::sync::atomic::Ordering::Relaxed),
+        1
+    );
+    let mut written = String::new();
+    for _ in 0..100 {
+        written = std::fs::read_to_string(&output).unwrap_or_default();
+        if written.ends_with('\n') {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let _ = std::fs::remove_file(&output);
+
+    assert_eq!(written, "1 [test]\n");
+}
+
+#[tokio::test]
+async fn closing_a_pane_forgets_when_it_was_focused() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(0, NodeData::Span(span)));
+    state.set_active_span(1);
+    state.set_active_span(2);
+
+    remove_node_from_state(state.clone(), 2).await.unwrap();
+
+    let last_active = state.last_active.lock().unwrap();
+    assert!(last_active.contains_key(&1));
+    assert!(!last_active.contains_key(&2));
+}
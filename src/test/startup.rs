@@ -0,0 +1,94 @@
+use renterm::{canvas::Canvas, vector::Vector2};
+
+use crate::startup::{redraw_after_resume, write_startup_sequence};
+
+use super::{test_config, test_state_with_output};
+
+#[tokio::test]
+async fn startup_enables_mouse_by_default() {
+    let (state, output) = test_state_with_output(test_config());
+    write_startup_sequence(state).await.unwrap();
+    let output = String::from_utf8(output.bytes()).unwrap();
+    assert!(output.contains("\x1b[?1003h"));
+    assert!(output.contains("\x1b[?1006h"));
+}
+
+#[tokio::test]
+async fn startup_without_mouse_emits_no_mouse_codes() {
+    let mut config = test_config();
+    config.enable_mouse = false;
+    let (state, output) = test_state_with_output(config);
+    write_startup_sequence(state).await.unwrap();
+    let output = String::from_utf8(output.bytes()).unwrap();
+    assert!(!output.contains("\x1b[?1000h"));
+    assert!(!output.contains("\x1b[?1003h"));
+    assert!(!output.contains("\x1b[?1006h"));
+}
+
+#[tokio::test]
+async fn startup_enables_enhanced_keyboard_by_default() {
+    let (state, output) = test_state_with_output(test_config());
+    write_startup_sequence(state).await.unwrap();
+    let output = String::from_utf8(output.bytes()).unwrap();
+    assert!(output.contains("\x1b[>15u"));
+}
+
+#[tokio::test]
+async fn startup_without_input_modes_omits_their_codes() {
+    let mut config = test_config();
+    config.win32_input = false;
+    config.enhanced_keyboard = false;
+    let (state, output) = test_state_with_output(config);
+    write_startup_sequence(state).await.unwrap();
+    let output = String::from_utf8(output.bytes()).unwrap();
+    assert!(!output.contains("\x1b[?9001h"));
+    assert!(!output.contains("\x1b[>"));
+}
+
+#[tokio::test]
+async fn inline_startup_keeps_the_main_screen() {
+    let mut config = test_config();
+    config.use_alternate_screen = false;
+    let (state, output) = test_state_with_output(config);
+    state.set_size(Vector2::new(20, 3)).await;
+    write_startup_sequence(state).await.unwrap();
+    let output = String::from_utf8(output.bytes()).unwrap();
+    // The screen is scrolled up rather than cleared, so nothing is lost.
+    assert!(output.starts_with("\n\n\n"));
+    assert!(!output.contains("\x1b[?1049h"));
+    assert!(!output.contains("\x1b[2J"));
+    assert!(output.contains("\x1b[r"));
+}
+
+#[tokio::test]
+async fn resume_restores_modes_and_redraws_in_full() {
+    let (state, output) = test_state_with_output(test_config());
+    *state.get_last_canvas().lock().await = Canvas::new(Vector2::new(10, 5));
+
+    redraw_after_resume(&state).await.unwrap();
+
+    let output = String::from_utf8(output.bytes()).unwrap();
+    assert!(output.contains("\x1b[?1049h"));
+    assert!(output.contains("\x1b[?1003h"));
+    assert_eq!(output.matches("\x1b[?1000h").count(), 1);
+    // Stopped from outside, the flags pushed at startup are still on the stack.
+    assert_eq!(output.matches("\x1b[<1u").count(), 1);
+    assert_eq!(output.matches("\x1b[>15u").count(), 1);
+    assert!(output.find("\x1b[<1u") < output.find("\x1b[>15u"));
+    assert_eq!(*state.get_last_canvas().lock().await, Canvas::default());
+}
+
+#[tokio::test]
+async fn resume_after_suspending_pushes_the_keyboard_flags_again() {
+    let (state, output) = test_state_with_output(test_config());
+    state
+        .suspended
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+
+    redraw_after_resume(&state).await.unwrap();
+
+    let output = String::from_utf8(output.bytes()).unwrap();
+    assert!(!output.contains("\x1b[<1u"));
+    assert_eq!(output.matches("\x1b[>15u").count(), 1);
+    assert!(!state.suspended.load(std::sync::atomic::Ordering::Relaxed));
+}
@@ -0,0 +1,46 @@
+use renterm::vector::Vector2;
+
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    draw::draw,
+    span::{Node, NodeData},
+    state::{State, StateContainer},
+    test::{add_test_process, test_config, test_state},
+};
+
+#[tokio::test]
+async fn processes_can_be_iterated() {
+    let state = test_state(test_config());
+    for id in 1..=3 {
+        add_test_process(&state, id, Vector2::new(10, 5)).await;
+    }
+
+    let mut count = 0;
+    state.for_each_process(|_| count += 1).await;
+    assert_eq!(count, 3);
+
+    let found = state.find_process(|process| process.span_id == 3).await;
+    assert!(found.is_some());
+    assert_eq!(state.pid_of(4).await, None);
+}
+
+#[tokio::test]
+async fn first_draw_reaches_the_output() {
+    let (state, _input, mut output) = State::new_for_test();
+    let state = StateContainer::new(state);
+    state.set_size(Vector2::new(20, 6)).await;
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    let (process, _) = add_test_process(&state, 1, Vector2::new(18, 4)).await;
+    {
+        let process = process.read().await;
+        process.terminal_info.lock().await.process(b"hello");
+    }
+
+    draw(state.clone()).await.unwrap();
+    let mut buffer = vec![0; 64 * 1024];
+    let length = output.read(&mut buffer).await.unwrap();
+    let text = String::from_utf8_lossy(&buffer[..length]);
+    assert!(text.contains('┌'));
+    assert!(text.contains("hello"));
+}
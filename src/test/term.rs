@@ -0,0 +1,126 @@
+use renterm::{color::Color, surface::Surface, vector::Vector2};
+
+use crate::term::{CursorShape, CursorState, TerminalInfo};
+
+#[test]
+fn default_background_fills_empty_cells() {
+    let mut terminal = TerminalInfo::new(Vector2::new(10, 5));
+    terminal.process(b"\x1b]11;rgb:ffff/8080/0000\x1b\\text");
+    let background = Color::new_rgb(255, 128, 0);
+    assert_eq!(terminal.default_background(), background);
+
+    let canvas = terminal.canvas();
+    let empty = canvas.get_cell(Vector2::new(8, 3));
+    assert!(empty.is_empty());
+    assert_eq!(empty.style.background_color(), background);
+
+    terminal.process(b"\x1b]111\x1b\\");
+    assert_eq!(terminal.default_background(), Color::default());
+}
+
+#[test]
+fn set_size_keeps_contents_and_ignores_same_size() {
+    let mut terminal = TerminalInfo::new(Vector2::new(10, 5));
+    terminal.process(b"hello");
+    assert!(!terminal.set_size(Vector2::new(10, 5)));
+    assert!(terminal.set_size(Vector2::new(12, 6)));
+    assert!(!terminal.set_size(Vector2::new(12, 6)));
+
+    let canvas = terminal.canvas();
+    assert_eq!(canvas.size(), Vector2::new(12, 6));
+    assert_eq!(canvas.get_cell(Vector2::new(4, 0)).to_string(), "o");
+}
+
+#[test]
+fn fallback_title_is_replaced_by_program_title() {
+    let mut terminal = TerminalInfo::new(Vector2::new(10, 5));
+    terminal.set_fallback_title(Some("top".to_string()));
+    assert_eq!(terminal.title(), "top");
+
+    terminal.process(b"\x1b]2;htop\x07");
+    assert_eq!(terminal.title(), "htop");
+}
+
+#[test]
+fn cursor_style_and_blink_mode_are_tracked() {
+    let mut terminal = TerminalInfo::new(Vector2::new(10, 5));
+    assert_eq!(terminal.cursor_state().to_decscusr(), 0);
+
+    terminal.process(b"\x1b[?12l");
+    assert_eq!(terminal.cursor_state().to_decscusr(), 2);
+
+    terminal.process(b"\x1b[3 q");
+    assert_eq!(
+        terminal.cursor_state(),
+        CursorState {
+            shape: CursorShape::Underline,
+            blink: Some(true)
+        }
+    );
+    terminal.process(b"\x1b[?12l");
+    assert_eq!(terminal.cursor_state().to_decscusr(), 4);
+}
+
+#[test]
+fn text_attributes_are_copied_into_styles() {
+    let mut terminal_info = TerminalInfo::new(Vector2::new(10, 2));
+    terminal_info.process(b"\x1b[1;4mab\x1b[0;3;7mc\x1b[0md");
+    let canvas = terminal_info.canvas();
+
+    let style = canvas.get_cell(Vector2::new(0, 0)).style;
+    assert!(style.is_bold());
+    assert!(style.is_underline());
+    assert!(!style.is_italic());
+    let style = canvas.get_cell(Vector2::new(2, 0)).style;
+    assert!(style.is_italic());
+    assert!(style.is_inverse());
+    assert!(!style.is_bold());
+    let style = canvas.get_cell(Vector2::new(3, 0)).style;
+    assert!(!style.is_bold() && !style.is_inverse());
+}
+
+#[test]
+fn clearing_scrollback_keeps_the_screen() {
+    let mut terminal = TerminalInfo::new(Vector2::new(10, 5));
+    for line in 0..10 {
+        terminal.process(format!("line {}\r\n", line).as_bytes());
+    }
+    terminal.process(b"\x1b[31mred\x1b[0m\x1b[2;4H");
+    assert_eq!(terminal.scrollback_len(), 6);
+    terminal.scroll_by(2);
+    let position = terminal.cursor_position();
+
+    terminal.clear_scrollback();
+
+    assert_eq!(terminal.scrollback_len(), 0);
+    assert_eq!(terminal.scroll_offset(), 0);
+    assert_eq!(terminal.cursor_position(), position);
+    let canvas = terminal.canvas();
+    let row: String = (0..10)
+        .map(|x| canvas.get_cell(Vector2::new(x, 3)).to_string())
+        .collect();
+    assert_eq!(row, "line 9    ");
+    let red = canvas.get_cell(Vector2::new(0, 4));
+    assert_eq!(red.to_string(), "r");
+    assert_eq!(red.style.foreground_color(), Color::new_one_byte(1));
+}
+
+#[test]
+fn word_range_stops_at_separators() {
+    let mut terminal = TerminalInfo::new(Vector2::new(30, 5));
+    terminal.process(b"  path/to/file.rs:12");
+    assert_eq!(terminal.word_range(0, 5, " :"), Some(2..17));
+    assert_eq!(terminal.word_range(0, 5, " /:"), Some(2..6));
+    assert_eq!(terminal.word_range(0, 1, " /:"), None);
+    assert_eq!(terminal.word_range(0, 25, " /:"), None);
+}
+
+#[test]
+fn bell_is_noticed_outside_of_sequences() {
+    let mut terminal = TerminalInfo::new(Vector2::new(10, 5));
+    terminal.process(b"\x1b]0;title\x07text");
+    assert!(!terminal.take_bell());
+    terminal.process(b"ding\x07");
+    assert!(terminal.take_bell());
+    assert!(!terminal.take_bell());
+}
@@ -0,0 +1,122 @@
+use crossterm::event::KeyCode;
+use renterm::{canvas::Canvas, color::Color, surface::Surface, vector::Vector2};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    config::{Launcher, SendString},
+    draw::compose,
+    keybind::{help_text, KeyBinding},
+    spawn::remove_node_from_state,
+    test::{run_process_tasks, test_config, test_state},
+    virtual_process::{open_help_pane, open_virtual_pane},
+};
+
+#[tokio::test]
+async fn bytes_written_to_a_virtual_pane_are_drawn() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    state.set_size(Vector2::new(20, 5)).await;
+    run_process_tasks(&state).await;
+
+    let (process, mut output) = open_virtual_pane(&state, "help").await.unwrap();
+    output
+        .writer
+        .write_all(b"\x1b[1;31mhi\x1b[0m there")
+        .await
+        .unwrap();
+    for _ in 0..100 {
+        let process = process.read().await;
+        if process.terminal_info.lock().await.cursor_position().x == 8 {
+            break;
+        }
+        drop(process);
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+
+    let mut canvas = Canvas::default();
+    compose(state.clone(), &mut canvas, Vector2::new(20, 5))
+        .await
+        .unwrap();
+    let row: String = (0..20)
+        .map(|x| canvas.get_cell(Vector2::new(x, 1)).to_string())
+        .collect();
+    assert_eq!(row, "│hi there          │");
+    let style = canvas.get_cell(Vector2::new(1, 1)).style;
+    assert_eq!(style.foreground_color(), Color::new_one_byte(1));
+    assert!(style.is_bold());
+    let title: String = (0..7)
+        .map(|x| canvas.get_cell(Vector2::new(x, 0)).to_string())
+        .collect();
+    assert_eq!(title, "┌[help]");
+}
+
+#[tokio::test]
+async fn help_pane_lists_the_bindings() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    state.set_size(Vector2::new(60, 30)).await;
+    run_process_tasks(&state).await;
+
+    open_help_pane(&state).await.unwrap();
+    let process = state.active_process().await.unwrap();
+    let mut text = String::new();
+    for _ in 0..100 {
+        text = {
+            let process = process.read().await;
+            let canvas = process.terminal_info.lock().await.canvas();
+            (0..canvas.size().x)
+                .map(|x| canvas.get_cell(Vector2::new(x, 0)).to_string())
+                .collect()
+        };
+        if text.starts_with("Keys") {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+
+    assert!(text.starts_with("Keys after the prefix, ctrl+b:"));
+}
+
+#[test]
+fn help_leaves_out_keys_taken_over_by_launchers() {
+    let prefix = KeyBinding::parse("ctrl+b").unwrap();
+    let text = help_text(&prefix, &[], &[]);
+    assert!(text.contains("  n    Open a new pane\r\n"));
+    assert!(text.contains("  D    Toggle the layout debug view\r\n"));
+    assert!(text.contains("  Tab  Show the next window\r\n"));
+
+    let launchers = [Launcher {
+        name: "lazygit".to_string(),
+        command: "lazygit".to_string(),
+        key: Some(KeyCode::Char('n')),
+        tint: None,
+    }];
+    let send_strings = [SendString {
+        key: KeyCode::Char('D'),
+        bytes: b"ls".to_vec(),
+    }];
+    let text = help_text(&prefix, &launchers, &send_strings);
+    assert!(!text.contains("Open a new pane\r\n"));
+    assert!(!text.contains("Toggle the layout debug view"));
+    assert!(text.contains("  n    Launch lazygit\r\n"));
+    assert!(text.contains("  D    Send \"ls\"\r\n"));
+}
+
+#[tokio::test]
+async fn closed_virtual_panes_cannot_be_reopened() {
+    let mut config = test_config();
+    config.exit_on_last_pane = false;
+    let state = test_state(config);
+    state.set_size(Vector2::new(20, 5)).await;
+    run_process_tasks(&state).await;
+
+    let (process, _output) = open_virtual_pane(&state, "help").await.unwrap();
+    let span_id = process.read().await.span_id;
+    remove_node_from_state(state.clone(), span_id)
+        .await
+        .unwrap();
+
+    assert!(state.closed_panes.lock().await.is_empty());
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::Ordering;
+
+use crate::{
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+    test::{test_config, test_state},
+    window::{break_pane, join_pane, next_window, Window},
+};
+
+fn two_pane_tree() -> Node {
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    Node::new(0, NodeData::Span(span))
+}
+
+#[tokio::test]
+async fn break_pane_moves_active_pane_to_new_window() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(two_pane_tree());
+    state.set_active_span(2);
+
+    break_pane(&state).await.unwrap();
+
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids(), vec![2]);
+    assert_eq!(state.active_id.load(Ordering::Relaxed), 2);
+    let windows = state.windows.read().await;
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows[0].root_node.as_ref().unwrap().leaf_ids(), vec![1]);
+    assert_eq!(windows[0].active_id, 1);
+}
+
+#[tokio::test]
+async fn break_pane_keeps_a_lone_pane_in_place() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(1, NodeData::Void));
+    state.set_active_span(1);
+
+    break_pane(&state).await.unwrap();
+
+    assert!(state.windows.read().await.is_empty());
+    assert!(state.current_message().await.is_some());
+}
+
+#[tokio::test]
+async fn next_window_swaps_with_background_window() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(two_pane_tree());
+    state.set_active_span(2);
+    break_pane(&state).await.unwrap();
+
+    next_window(&state).await;
+
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids(), vec![1]);
+    assert_eq!(state.active_id.load(Ordering::Relaxed), 1);
+    let windows = state.windows.read().await;
+    assert_eq!(windows[0].root_node.as_ref().unwrap().leaf_ids(), vec![2]);
+}
+
+#[tokio::test]
+async fn join_pane_moves_pane_between_windows() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(Node::new(3, NodeData::Void));
+    state.set_active_span(3);
+    state
+        .windows
+        .write()
+        .await
+        .push_back(Window::new(Some(two_pane_tree())));
+    state.span_id_counter.store(3, Ordering::Relaxed);
+
+    join_pane(&state).await.unwrap();
+
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids().len(), 2);
+    assert!(root.leaf_ids().contains(&1));
+    assert_eq!(state.active_id.load(Ordering::Relaxed), 1);
+    let windows = state.windows.read().await;
+    assert_eq!(windows[0].root_node.as_ref().unwrap().leaf_ids(), vec![2]);
+    assert_eq!(windows[0].active_id, 2);
+}
+
+#[tokio::test]
+async fn join_pane_drops_emptied_window() {
+    let state = test_state(test_config());
+    *state.root_node.write().await = Some(two_pane_tree());
+    state.set_active_span(2);
+    state.span_id_counter.store(2, Ordering::Relaxed);
+    break_pane(&state).await.unwrap();
+
+    join_pane(&state).await.unwrap();
+
+    let root = state.root_node.read().await.clone().unwrap();
+    assert_eq!(root.leaf_ids().len(), 2);
+    assert!(state.windows.read().await.is_empty());
+}
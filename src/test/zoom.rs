@@ -0,0 +1,102 @@
+use crate::{
+    span::{Node, NodeData, Span, SpanChild, SpanDirection},
+    spawn::{create_span, remove_node_from_state},
+    state::{State, StateContainer},
+    zoom::{toggle_zoom, unzoom, zoom, zoom_on_startup},
+};
+
+use super::{test_args, test_config, test_state};
+
+async fn two_panes(state: &StateContainer) {
+    state.set_size((40, 10)).await;
+    let mut span = Span::new(SpanDirection::Horizontal);
+    span.children
+        .push(SpanChild::new(Node::new(1, NodeData::Void)));
+    span.children
+        .push(SpanChild::new(Node::new(2, NodeData::Void)));
+    *state.root_node.write().await = Some(Node::new(3, NodeData::Span(span)));
+    state
+        .span_id_counter
+        .store(3, std::sync::atomic::Ordering::Relaxed);
+    state.set_active_span(2);
+}
+
+async fn leaf_ids(state: &StateContainer) -> Vec<usize> {
+    let root = state.root_node.read().await;
+    root.as_ref().map(Node::leaf_ids).unwrap_or_default()
+}
+
+#[tokio::test]
+async fn startup_with_the_zoom_flag_zooms_the_active_pane() {
+    let mut args = test_args();
+    args.zoom = true;
+    let state = StateContainer::new(State::new(
+        args,
+        test_config(),
+        tokio::io::empty(),
+        tokio::io::sink(),
+    ));
+    two_panes(&state).await;
+
+    zoom_on_startup(&state).await.unwrap();
+
+    assert!(state.zoomed_layout.lock().await.is_some());
+    assert_eq!(leaf_ids(&state).await, vec![2]);
+}
+
+#[tokio::test]
+async fn startup_zooms_only_when_asked_to() {
+    let state = test_state(test_config());
+    two_panes(&state).await;
+    zoom_on_startup(&state).await.unwrap();
+    assert!(state.zoomed_layout.lock().await.is_none());
+
+    let mut config = test_config();
+    config.start_zoomed = true;
+    let state = test_state(config);
+    two_panes(&state).await;
+    zoom_on_startup(&state).await.unwrap();
+    assert!(state.zoomed_layout.lock().await.is_some());
+}
+
+#[tokio::test]
+async fn toggling_zoom_brings_the_layout_back() {
+    let state = test_state(test_config());
+    two_panes(&state).await;
+
+    toggle_zoom(&state).await.unwrap();
+    assert_eq!(leaf_ids(&state).await, vec![2]);
+    toggle_zoom(&state).await.unwrap();
+    assert_eq!(leaf_ids(&state).await, vec![1, 2]);
+    assert!(!unzoom(&state).await);
+}
+
+#[tokio::test]
+async fn splitting_a_zoomed_pane_unzooms_first() {
+    let state = test_state(test_config());
+    two_panes(&state).await;
+    assert!(zoom(&state).await);
+
+    let new_id = create_span(state.clone()).await.unwrap();
+
+    assert!(state.zoomed_layout.lock().await.is_none());
+    assert_eq!(leaf_ids(&state).await, vec![1, 2, new_id]);
+}
+
+#[tokio::test]
+async fn closing_panes_while_zoomed_keeps_the_hidden_layout_in_step() {
+    let state = test_state(test_config());
+    two_panes(&state).await;
+    assert!(zoom(&state).await);
+
+    remove_node_from_state(state.clone(), 1).await.unwrap();
+    assert_eq!(leaf_ids(&state).await, vec![2]);
+    assert!(unzoom(&state).await);
+    assert_eq!(leaf_ids(&state).await, vec![2]);
+
+    two_panes(&state).await;
+    assert!(zoom(&state).await);
+    remove_node_from_state(state.clone(), 2).await.unwrap();
+    assert!(state.zoomed_layout.lock().await.is_none());
+    assert_eq!(leaf_ids(&state).await, vec![1]);
+}
@@ -1,15 +1,140 @@
 #[cfg(unix)]
 pub mod package {
     use std::collections::HashMap;
+    use std::future::Future;
+    use std::os::fd::{AsRawFd, OwnedFd};
+    use std::pin::Pin;
+    use std::process::Stdio;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use nix::pty::{openpty, Winsize};
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
     use renterm::vector::Vector2;
-    use crate::process::ProcessData;
 
+    use crate::process::{ProcessData, TerminalError, TerminalLike};
+
+    type DoneFuture = Pin<Box<dyn Future<Output = Result<(), TerminalError>> + Send>>;
+
+    fn winsize(size: &Vector2) -> Winsize {
+        Winsize {
+            ws_row: size.y.clamp(1, i32::from(u16::MAX)) as u16,
+            ws_col: size.x.clamp(1, i32::from(u16::MAX)) as u16,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+
+    /// Starts `program_to_spawn` as the session leader of a new pseudo
+    /// terminal, with the pty's slave side as its controlling terminal.
     pub async fn spawn_interactive_process(
         program_to_spawn: &str,
         env: &HashMap<String, String>,
         args: &[String],
         size: Vector2,
     ) -> anyhow::Result<ProcessData> {
-        Err(anyhow::anyhow!("Not implemented"))
+        let pty = openpty(Some(&winsize(&size)), None)?;
+        let mut command = tokio::process::Command::new(program_to_spawn);
+        command
+            .args(args)
+            .envs(env)
+            .stdin(Stdio::from(pty.slave.try_clone()?))
+            .stdout(Stdio::from(pty.slave.try_clone()?))
+            .stderr(Stdio::from(pty.slave))
+            .kill_on_drop(true);
+        // Safety: only async-signal-safe calls are made between fork and exec.
+        unsafe {
+            command.pre_exec(|| {
+                nix::unistd::setsid()?;
+                if nix::libc::ioctl(0, nix::libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut child = command.spawn()?;
+        let pid = child.id().unwrap_or_default();
+        tracing::debug!("Child process ID: {:?}", pid);
+
+        let reader = tokio::fs::File::from_std(std::fs::File::from(pty.master.try_clone()?));
+        let writer = tokio::fs::File::from_std(std::fs::File::from(pty.master.try_clone()?));
+        let exited = Arc::new(AtomicBool::new(false));
+        let done_future = {
+            let exited = exited.clone();
+            async move {
+                child
+                    .wait()
+                    .await
+                    .map_err(|err| TerminalError::from(err.to_string()))?;
+                exited.store(true, Ordering::Relaxed);
+
+                Ok(())
+            }
+        };
+
+        Ok(ProcessData {
+            stdin: Box::new(writer),
+            stdout: Box::new(reader),
+            terminal: Box::new(UnixPty {
+                master: Some(pty.master),
+                pid,
+                exited,
+                size,
+                done_future: std::sync::Mutex::new(Some(Box::pin(done_future))),
+            }),
+            pid,
+        })
+    }
+
+    struct UnixPty {
+        /// Closed on release, the pane's reader and writer hold their own copies.
+        master: Option<OwnedFd>,
+        pid: u32,
+        /// Set once the child was waited for, after which its pid may be reused.
+        exited: Arc<AtomicBool>,
+        size: Vector2,
+        /// Behind a mutex only so that `UnixPty` is `Sync`.
+        done_future: std::sync::Mutex<Option<DoneFuture>>,
+    }
+
+    impl TerminalLike for UnixPty {
+        fn take_done_future(&mut self) -> Option<DoneFuture> {
+            self.done_future.get_mut().ok()?.take()
+        }
+
+        /// Hangs up on the child, like closing a terminal window would.
+        fn release<'a>(
+            &'a mut self,
+        ) -> Pin<Box<dyn Future<Output = Result<(), TerminalError>> + 'a + Send>> {
+            Box::pin(async move {
+                let exited = self.exited.load(Ordering::Relaxed);
+                if self.master.take().is_some() && self.pid != 0 && !exited {
+                    let _ignored = kill(Pid::from_raw(self.pid as i32), Signal::SIGHUP);
+                }
+
+                Ok(())
+            })
+        }
+
+        fn set_size(&mut self, size: Vector2) -> Result<(), TerminalError> {
+            let Some(master) = &self.master else {
+                return Ok(());
+            };
+            let winsize = winsize(&size);
+            // Safety: TIOCSWINSZ only reads the winsize it is given.
+            let result =
+                unsafe { nix::libc::ioctl(master.as_raw_fd(), nix::libc::TIOCSWINSZ, &winsize) };
+            if result == -1 {
+                return Err(std::io::Error::last_os_error().to_string().into());
+            }
+            self.size = size;
+
+            Ok(())
+        }
+
+        fn size(&self) -> Vector2 {
+            self.size.clone()
+        }
     }
-}
\ No newline at end of file
+}
@@ -37,6 +37,7 @@ pub mod package {
     };
 
     use crate::process::{ProcessData, TerminalError, TerminalLike};
+    use crate::size::validate_console_size;
     use crate::tty::TtyParameters;
 
     impl From<windows::core::Error> for TerminalError {
@@ -109,9 +110,10 @@ pub mod package {
                 0,
             )?;
 
+            let console_size = validate_console_size(size.clone())?;
             let mut tty_size = Win32::System::Console::COORD::default();
-            tty_size.X = size.x as i16;
-            tty_size.Y = size.y as i16;
+            tty_size.X = console_size.x;
+            tty_size.Y = console_size.y;
             let hpcon = CreatePseudoConsole(tty_size, input_read, output_write, 0)?;
 
             let mut attribute_list_size: usize = 0;
@@ -239,6 +241,7 @@ pub mod package {
                     done_future: Some(Box::pin(done_future)),
                     is_closed,
                 }),
+                pid: process_id,
             })
         }
     }
@@ -292,9 +295,10 @@ pub mod package {
 
         fn set_size(&mut self, size: Vector2) -> Result<(), TerminalError> {
             unsafe {
+                let console_size = validate_console_size(size.clone())?;
                 let mut tty_size = Win32::System::Console::COORD::default();
-                tty_size.X = size.x as i16;
-                tty_size.Y = size.y as i16;
+                tty_size.X = console_size.x;
+                tty_size.Y = console_size.y;
                 let result = windows::Win32::System::Console::ResizePseudoConsole(self.hpcon, tty_size);
                 if let Err(e) = result {
                     tracing::error!("Error resizing pty: {:?}", e);
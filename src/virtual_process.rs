@@ -0,0 +1,116 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use renterm::vector::Vector2;
+use tokio::{
+    io::{AsyncWriteExt, DuplexStream},
+    sync::{Notify, RwLock},
+};
+
+use crate::{
+    keybind::help_text,
+    process::{ProcessData, TerminalError, TerminalLike},
+    spawn::{add_virtual_process, create_span, pane_content_size},
+    state::{PaneCommand, Process, StateContainer},
+    term::TerminalInfo,
+};
+
+/// Buffer between a virtual pane's writer and the pane.
+const VIRTUAL_BUFFER_SIZE: usize = 64 * 1024;
+
+/// A terminal with no child process behind it.
+pub struct VirtualTerminal {
+    size: Vector2,
+    closed: Arc<Notify>,
+}
+
+/// The writing end of a virtual pane.
+pub struct VirtualOutput {
+    pub writer: DuplexStream,
+    closed: Arc<Notify>,
+}
+
+impl VirtualOutput {
+    /// Waits until the pane is closed.
+    pub async fn closed(&self) {
+        self.closed.notified().await;
+    }
+}
+
+impl TerminalLike for VirtualTerminal {
+    fn release<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), TerminalError>> + 'a + Send>> {
+        self.closed.notify_one();
+        Box::pin(async { Ok(()) })
+    }
+    fn set_size(&mut self, size: Vector2) -> Result<(), TerminalError> {
+        self.size = size;
+        Ok(())
+    }
+    fn size(&self) -> Vector2 {
+        self.size.clone()
+    }
+    fn take_done_future(
+        &mut self,
+    ) -> Option<Pin<Box<dyn Future<Output = Result<(), TerminalError>> + Send>>> {
+        // There is no child to exit, the pane ends when its output does.
+        Some(Box::pin(std::future::pending()))
+    }
+}
+
+/// Creates the process of a pane that shows whatever is written to the
+/// returned writer, as if a program had printed it. Input to the pane is
+/// discarded, and dropping the writer ends the process.
+pub fn virtual_process(size: Vector2) -> (ProcessData, VirtualOutput) {
+    let (writer, reader) = tokio::io::duplex(VIRTUAL_BUFFER_SIZE);
+    let closed = Arc::new(Notify::new());
+    let data = ProcessData {
+        stdin: Box::new(tokio::io::sink()),
+        stdout: Box::new(reader),
+        terminal: Box::new(VirtualTerminal {
+            size,
+            closed: closed.clone(),
+        }),
+        pid: 0,
+    };
+
+    (data, VirtualOutput { writer, closed })
+}
+
+/// Opens a new pane with a virtual process, see `virtual_process`.
+pub async fn open_virtual_pane(
+    state_container: &StateContainer,
+    title: &str,
+) -> anyhow::Result<(Arc<RwLock<Process>>, VirtualOutput)> {
+    let new_id = create_span(state_container.clone()).await?;
+    let mut terminal_info = TerminalInfo::new(pane_content_size(state_container, new_id).await);
+    terminal_info.set_fallback_title(Some(title.to_string()));
+    let (data, output) = virtual_process(terminal_info.size());
+    let command = PaneCommand {
+        title: Some(title.to_string()),
+        ..PaneCommand::default()
+    };
+    let process = add_virtual_process(
+        state_container.clone(),
+        new_id,
+        data,
+        terminal_info,
+        command,
+    )
+    .await?;
+
+    Ok((process, output))
+}
+
+/// Opens a pane listing the key bindings, which stays open until it is closed.
+pub async fn open_help_pane(state_container: &StateContainer) -> anyhow::Result<()> {
+    let text = {
+        let config = state_container.config.read().await;
+        help_text(&config.prefix, &config.launchers, &config.send_strings)
+    };
+    let (_, mut output) = open_virtual_pane(state_container, "help").await?;
+    output.writer.write_all(text.as_bytes()).await?;
+    tokio::spawn(async move { output.closed().await });
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::sync::atomic::Ordering;
+
+use crate::{
+    span::{take_node, Node},
+    spawn::insert_node,
+    state::StateContainer,
+    zoom::unzoom,
+};
+
+/// A layout that isn't on screen, together with the pane that was active in it.
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub root_node: Option<Node>,
+    pub active_id: usize,
+}
+
+impl Window {
+    /// Wraps a layout, making its first pane the active one.
+    pub fn new(root_node: Option<Node>) -> Self {
+        let active_id = first_leaf_id(root_node.as_ref());
+        Window {
+            root_node,
+            active_id,
+        }
+    }
+}
+
+fn first_leaf_id(root_node: Option<&Node>) -> usize {
+    root_node
+        .and_then(|root| root.leaf_ids().first().copied())
+        .unwrap_or_default()
+}
+
+/// Moves the active pane out of the current layout into a new window of its
+/// own and switches to it. The layout it left is kept as a background window.
+pub async fn break_pane(state_container: &StateContainer) -> anyhow::Result<()> {
+    unzoom(state_container).await;
+    let state = state_container.state();
+    let active_id = state.active_id.load(Ordering::Relaxed);
+    let mut root_node = state.root_node.write().await;
+    let leaf_count = root_node
+        .as_ref()
+        .map(Node::leaf_ids)
+        .unwrap_or_default()
+        .len();
+    if leaf_count < 2 {
+        drop(root_node);
+        state.flash("Pane is already alone in its window").await;
+        return Ok(());
+    }
+    let Some(node) = take_node(&mut root_node, active_id) else {
+        return Err(anyhow::format_err!(
+            "Could not find active node with id: {}",
+            active_id
+        ));
+    };
+    let source = Window::new(root_node.replace(node));
+    state.windows.write().await.push_back(source);
+
+    Ok(())
+}
+
+/// Takes the active pane of the next background window and splits it into
+/// the current layout next to the active pane.
+pub async fn join_pane(state_container: &StateContainer) -> anyhow::Result<()> {
+    let state = state_container.state();
+    let node = {
+        let mut windows = state.windows.write().await;
+        let Some(source) = windows.front_mut() else {
+            drop(windows);
+            state.flash("No other window to join from").await;
+            return Ok(());
+        };
+        let Some(node) = take_node(&mut source.root_node, source.active_id) else {
+            return Err(anyhow::format_err!(
+                "Could not find active node with id: {}",
+                source.active_id
+            ));
+        };
+        source.active_id = first_leaf_id(source.root_node.as_ref());
+        windows.retain(|window| window.root_node.is_some());
+
+        node
+    };
+    insert_node(state_container.clone(), node).await?;
+
+    Ok(())
+}
+
+/// Shows the next background window, putting the current one at the back.
+pub async fn next_window(state_container: &StateContainer) {
+    unzoom(state_container).await;
+    let state = state_container.state();
+    // Same lock order as `break_pane`: the layout first, then the windows.
+    let mut root_node = state.root_node.write().await;
+    let mut windows = state.windows.write().await;
+    let Some(next) = windows.pop_front() else {
+        return;
+    };
+    let current = Window {
+        root_node: root_node.take(),
+        active_id: state.active_id.load(Ordering::Relaxed),
+    };
+    *root_node = next.root_node;
+    state.active_id.store(next.active_id, Ordering::Relaxed);
+    windows.push_back(current);
+}
+
+/// Removes a pane from whichever background window holds it, dropping
+/// windows that become empty. Returns whether the pane was found.
+pub async fn remove_from_background_windows(
+    state_container: &StateContainer,
+    span_id: usize,
+) -> bool {
+    let state = state_container.state();
+    let mut windows = state.windows.write().await;
+    for window in windows.iter_mut() {
+        if take_node(&mut window.root_node, span_id).is_none() {
+            continue;
+        }
+        if window.active_id == span_id {
+            window.active_id = first_leaf_id(window.root_node.as_ref());
+        }
+        windows.retain(|window| window.root_node.is_some());
+        return true;
+    }
+
+    false
+}
+
+/// Replaces an emptied current layout with the next background window.
+/// Returns false when there is none left.
+pub async fn replace_empty_window(state_container: &StateContainer) -> bool {
+    let state = state_container.state();
+    let Some(next) = state.windows.write().await.pop_front() else {
+        return false;
+    };
+    *state.root_node.write().await = next.root_node;
+    state.active_id.store(next.active_id, Ordering::Relaxed);
+
+    true
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::Ordering;
+
+use crate::{
+    draw::trigger_draw,
+    size::resize_panes,
+    span::{take_node, Node, NodeData},
+    state::StateContainer,
+};
+
+/// Shows the active pane alone in place of the layout, which is kept in
+/// `State::zoomed_layout` until `unzoom`. Returns false when already zoomed.
+pub async fn zoom(state_container: &StateContainer) -> bool {
+    let mut zoomed_layout = state_container.zoomed_layout.lock().await;
+    if zoomed_layout.is_some() {
+        return false;
+    }
+    let active_id = state_container.active_id.load(Ordering::Relaxed);
+    let mut root = state_container.root_node.write().await;
+    let has_active_pane = root
+        .as_ref()
+        .is_some_and(|root| root.leaf_ids().contains(&active_id));
+    if !has_active_pane {
+        return false;
+    }
+    *zoomed_layout = root.replace(Node::new(active_id, NodeData::Void));
+
+    true
+}
+
+/// Brings back the layout hidden by `zoom`. Returns false when not zoomed.
+/// Anything that changes the layout unzooms first, so new panes are split
+/// into the whole layout rather than next to the zoomed pane alone.
+pub async fn unzoom(state_container: &StateContainer) -> bool {
+    let Some(layout) = state_container.zoomed_layout.lock().await.take() else {
+        return false;
+    };
+    *state_container.root_node.write().await = Some(layout);
+
+    true
+}
+
+/// Keeps the hidden layout in step with a pane being closed while zoomed.
+/// Closing the zoomed pane unzooms, other panes are dropped from the layout.
+pub async fn remove_from_zoomed_layout(state_container: &StateContainer, span_id: usize) {
+    let zoomed_id = {
+        let root = state_container.root_node.read().await;
+        root.as_ref().map(Node::id)
+    };
+    if zoomed_id == Some(span_id) {
+        unzoom(state_container).await;
+        return;
+    }
+    let mut zoomed_layout = state_container.zoomed_layout.lock().await;
+    if zoomed_layout.is_some() {
+        take_node(&mut zoomed_layout, span_id);
+    }
+}
+
+pub async fn toggle_zoom(state_container: &StateContainer) -> anyhow::Result<()> {
+    if unzoom(state_container).await {
+        state_container.flash("Layout restored").await;
+    } else if zoom(state_container).await {
+        state_container.flash("Pane zoomed").await;
+    } else {
+        state_container.flash("No pane to zoom").await;
+        return Ok(());
+    }
+    resize_panes(state_container).await?;
+    trigger_draw(state_container).await;
+
+    Ok(())
+}
+
+/// Zooms the active pane when started with `--zoom` or `Config::start_zoomed`.
+pub async fn zoom_on_startup(state_container: &StateContainer) -> anyhow::Result<()> {
+    let start_zoomed = state_container.config.read().await.start_zoomed;
+    if !state_container.args.zoom && !start_zoomed {
+        return Ok(());
+    }
+    if zoom(state_container).await {
+        resize_panes(state_container).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Writes `contents` as the config file of a fresh config directory.
+fn config_home(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "citymux-check-config-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(dir.join("citymux")).unwrap();
+    std::fs::write(dir.join("citymux").join("config.kdl"), contents).unwrap();
+
+    dir
+}
+
+fn check_config(config_home: &Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_citymux"))
+        .arg("--check-config")
+        .env("XDG_CONFIG_HOME", config_home)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn valid_config_passes() {
+    let dir = config_home("valid", "default_shell \"sh\"\nscroll_lines 5\n");
+    let output = check_config(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("ok"));
+}
+
+#[test]
+fn invalid_config_fails_with_line() {
+    let dir = config_home("invalid", "default_shell \"sh\"\nscroll_lines \"many\"\n");
+    let output = check_config(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("`scroll_lines` expects"));
+    assert!(stderr.contains("line 2"));
+}